@@ -1,7 +1,14 @@
 use std::io::Read;
 
 use clap::Parser;
-use jirascope_core::jira::{AtlassianDoc, IssueEdit};
+use jirascope_core::{
+    jira::{
+        AtlassianDoc, FilterDetails, FilterShare, Issue, IssueEdit, IssueKey, JiraDateTime,
+        JiraDuration, ProjectIdOrKey, ProjectKey, TimeTrackingEdit,
+    },
+    ureq::serde_json,
+    IssueDiff, IssueKeyFinder,
+};
 
 #[derive(Debug, Clone, Parser)]
 #[clap(version = "1.0", author = "Stanisław Zagórowski")]
@@ -14,6 +21,20 @@ struct Args {
     user: Option<String>,
     #[clap(short, long, help = "Jira API token")]
     api_token: Option<String>,
+    #[clap(
+        short,
+        long,
+        action = clap::ArgAction::Count,
+        help = "Log requests to stderr (-vv to include bodies)"
+    )]
+    verbose: u8,
+    #[clap(
+        long,
+        help = "Print changes that would be sent instead of sending them"
+    )]
+    dry_run: bool,
+    #[clap(long, help = "Refuse to make any change")]
+    read_only: bool,
     #[clap(subcommand)]
     subcommand: Subcommand,
 }
@@ -21,12 +42,12 @@ struct Args {
 #[derive(Debug, Clone, Parser)]
 enum Subcommand {
     Issue {
-        board_id: String,
-        issue_id: String,
+        board_id: ProjectKey,
+        issue_id: u64,
     },
     Edit {
-        board_id: String,
-        issue_id: String,
+        board_id: ProjectKey,
+        issue_id: u64,
         #[clap(long)]
         summary: Option<String>,
         #[clap(long)]
@@ -37,18 +58,87 @@ enum Subcommand {
         status: Option<String>,
         #[clap(long)]
         assignee: Option<String>,
+        #[clap(long, help = "E.g. 1w 2d 3h 30m")]
+        original_estimate: Option<String>,
+        #[clap(long, help = "E.g. 1w 2d 3h 30m")]
+        remaining_estimate: Option<String>,
+        #[clap(
+            long,
+            help = "Only edit if the issue's updated timestamp is still this one"
+        )]
+        expected_updated: Option<JiraDateTime>,
     },
     All,
-    CreateMeta,
+    CreateMeta {
+        project: ProjectIdOrKey,
+        #[clap(help = "List the fields of this issue type instead of the issue types")]
+        issue_type_id: Option<String>,
+    },
     EditMeta {
-        board_id: String,
-        issue_id: String,
+        board_id: ProjectKey,
+        issue_id: u64,
     },
     Delete {
-        board_id: String,
-        issue_id: String,
+        board_id: ProjectKey,
+        issue_id: u64,
+        #[clap(
+            long,
+            help = "The issue key, to confirm deleting an issue with subtasks"
+        )]
+        confirm: Option<String>,
     },
     Events,
+    #[clap(about = "Compare an issue saved as JSON with another one, or with the server")]
+    Diff {
+        #[clap(help = "JSON file with the old issue")]
+        old: String,
+        #[clap(help = "JSON file with the new issue, fetched from the server if left out")]
+        new: Option<String>,
+    },
+    #[clap(about = "Print the issue keys found in the text read from stdin, once each")]
+    Keys {
+        #[clap(long, help = "Only keys of the projects on the server")]
+        known: bool,
+    },
+    #[clap(about = "Saved filters")]
+    Filter {
+        #[clap(subcommand)]
+        command: FilterCommand,
+    },
+}
+
+#[derive(Debug, Clone, Parser)]
+enum FilterCommand {
+    #[clap(about = "List favourite filters, or the ones matching a name")]
+    List {
+        #[clap(long, help = "Only filters whose name contains this")]
+        search: Option<String>,
+        #[clap(long, help = "List the filters you own instead of your favourites")]
+        mine: bool,
+    },
+    #[clap(about = "List the issues a filter finds")]
+    Run {
+        name: String,
+    },
+    Create {
+        name: String,
+        jql: String,
+        #[clap(long)]
+        description: Option<String>,
+        #[clap(long, help = "Add it to your favourites")]
+        favourite: bool,
+    },
+    Delete {
+        name: String,
+    },
+    #[clap(about = "Share a filter, or list who it's shared with")]
+    Share {
+        name: String,
+        #[clap(
+            help = "global, authenticated, project:<project id>, role:<project id>:<role id>, group:<name> or user:<account id>"
+        )]
+        with: Option<FilterShare>,
+    },
 }
 
 fn main() {
@@ -56,9 +146,22 @@ fn main() {
 
     let subcommand = args.subcommand;
 
+    // dry runs log the held back requests at INFO
+    if args.verbose > 0 || args.dry_run {
+        tracing_subscriber::fmt()
+            .with_max_level(match args.verbose {
+                0 => tracing::Level::INFO,
+                1 => tracing::Level::DEBUG,
+                _ => tracing::Level::TRACE,
+            })
+            .with_writer(std::io::stderr)
+            .init();
+    }
+
     let mut server = None;
     let mut user = None;
     let mut api_token = None;
+    let mut policy = jirascope_core::Policy::default();
 
     if let Some(s) = args.identity {
         let mut file = match std::fs::File::open(s) {
@@ -85,6 +188,16 @@ fn main() {
         if let Some(s) = table.get("api_token") {
             api_token = Some(s.as_str().unwrap().to_string());
         }
+
+        if let Some(p) = table.get("policy") {
+            policy = match p.clone().try_into() {
+                Ok(policy) => policy,
+                Err(e) => {
+                    eprintln!("Error: invalid policy: {}", e);
+                    std::process::exit(1);
+                }
+            };
+        }
     }
 
     if let Some(s) = args.server {
@@ -104,20 +217,23 @@ fn main() {
         std::process::exit(1);
     }
 
-    let config = jirascope_core::Config::new(server.unwrap());
+    let mut config = jirascope_core::Config::new(server.unwrap());
+    config.dump_bodies = args.verbose > 1;
+    config.dry_run = args.dry_run;
+    config.policy = policy;
+    config.policy.read_only |= args.read_only;
     let auth = jirascope_core::Auth::new(user.unwrap(), api_token.unwrap());
 
-    let mut jirascope = jirascope_core::Jirascope::new(config, auth);
-    jirascope.init().unwrap();
+    let jirascope = jirascope_core::Jirascope::new(config, auth);
+    handle_error(jirascope.init());
 
     match subcommand {
         Subcommand::All => {
-            let issues = handle_error(jirascope.get_all_issues());
+            let issues = handle_error(jirascope.get_all_issues().fetch_all());
             println!("{:#?}", issues);
         }
         Subcommand::Issue { board_id, issue_id } => {
-            let issue =
-                handle_error(jirascope.get_issue(format!("{}-{}", board_id, issue_id).as_str()));
+            let issue = handle_error(jirascope.get_issue(IssueKey::new(&board_id, issue_id)));
             println!("{:#?}", issue);
         }
         Subcommand::Edit {
@@ -128,33 +244,186 @@ fn main() {
             priority: _,
             status: _,
             assignee: _,
+            original_estimate,
+            remaining_estimate,
+            expected_updated,
         } => {
             let mut issue_edit = IssueEdit::default();
 
             issue_edit.fields.summary = summary;
             issue_edit.fields.description = description.map(|d| AtlassianDoc::from_markdown(&d));
+            if original_estimate.is_some() || remaining_estimate.is_some() {
+                // a day or a week is as long as the server says
+                let options = handle_error(jirascope.get_time_tracking_options());
+                let parse = |estimate: Option<String>| {
+                    estimate.map(|e| handle_error(JiraDuration::parse(&e, &options)))
+                };
+
+                issue_edit.fields.timetracking = Some(TimeTrackingEdit {
+                    original_estimate: parse(original_estimate),
+                    remaining_estimate: parse(remaining_estimate),
+                });
+            }
             // TODO: rest of the fields
 
-            handle_error(
-                jirascope.edit_issue(format!("{}-{}", board_id, issue_id).as_str(), issue_edit),
-            );
+            let key = IssueKey::new(&board_id, issue_id);
+            handle_error(match expected_updated {
+                Some(expected_updated) => {
+                    jirascope.edit_issue_if_unchanged(key, &expected_updated, issue_edit)
+                }
+                None => jirascope.edit_issue(key, issue_edit),
+            });
         }
-        Subcommand::CreateMeta => {
-            let meta = handle_error(jirascope.get_issue_creation_meta());
-            println!("{:#?}", meta);
+        Subcommand::CreateMeta {
+            project,
+            issue_type_id: None,
+        } => {
+            let issue_types =
+                handle_error(jirascope.get_issue_creation_types(&project).fetch_all());
+            println!("{:#?}", issue_types);
         }
-        Subcommand::EditMeta { board_id, issue_id } => {
-            let meta = handle_error(
-                jirascope.get_issue_edit_meta(format!("{}-{}", board_id, issue_id).as_str()),
+        Subcommand::CreateMeta {
+            project,
+            issue_type_id: Some(issue_type_id),
+        } => {
+            let fields = handle_error(
+                jirascope
+                    .get_issue_creation_fields(&project, &issue_type_id)
+                    .fetch_all(),
             );
+            println!("{:#?}", fields);
+        }
+        Subcommand::EditMeta { board_id, issue_id } => {
+            let meta =
+                handle_error(jirascope.get_issue_edit_meta(IssueKey::new(&board_id, issue_id)));
             println!("{:#?}", meta);
         }
         Subcommand::Events => {
-            let events = jirascope.get_issue_events().unwrap();
+            let events = jirascope.get_issue_events().fetch_all().unwrap();
             println!("{:#?}", events);
         }
-        Subcommand::Delete { board_id, issue_id } => {
-            handle_error(jirascope.delete_issue(format!("{}-{}", board_id, issue_id).as_str()));
+        Subcommand::Delete {
+            board_id,
+            issue_id,
+            confirm,
+        } => {
+            let key = IssueKey::new(&board_id, issue_id);
+            handle_error(match confirm {
+                Some(confirm) => jirascope
+                    .delete_issue_confirmed(key, &jirascope_core::Confirmation::new(confirm)),
+                None => jirascope.delete_issue(key),
+            });
+        }
+        Subcommand::Diff { old, new } => {
+            let old = read_issue(&old);
+            let new = match new {
+                Some(new) => read_issue(&new),
+                None => handle_error(jirascope.get_issue(&old.key)),
+            };
+
+            let diff = IssueDiff::new(&old, &new);
+            println!("{}", serde_json::to_string_pretty(&diff).unwrap());
+        }
+        Subcommand::Keys { known } => {
+            let mut text = String::new();
+            if let Err(e) = std::io::stdin().read_to_string(&mut text) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+
+            let finder = if known {
+                let projects = handle_error(jirascope.get_projects().fetch_all());
+                IssueKeyFinder::with_projects(projects.into_iter().map(|p| p.key))
+            } else {
+                IssueKeyFinder::new()
+            };
+
+            let mut printed = Vec::new();
+            for found in finder.find(&text) {
+                if !printed.contains(&found.key) {
+                    println!("{}", found.key);
+                    printed.push(found.key);
+                }
+            }
+        }
+        Subcommand::Filter { command } => run_filter_command(&jirascope, command),
+    }
+}
+
+fn run_filter_command(jirascope: &jirascope_core::Jirascope, command: FilterCommand) {
+    match command {
+        FilterCommand::List { search, mine } => {
+            let filters = match (search, mine) {
+                (Some(search), _) => jirascope.search_filters(&search),
+                (None, true) => jirascope.get_my_filters(),
+                (None, false) => jirascope.get_favourite_filters(),
+            };
+
+            for filter in handle_error(filters.fetch_all()) {
+                println!("{}\t{}\t{}", filter.id, filter.name, filter.jql);
+            }
+        }
+        FilterCommand::Run { name } => {
+            let filter = handle_error(jirascope.get_filter_by_name(&name));
+
+            for issue in jirascope.run_filter(&filter) {
+                let issue = handle_error(issue);
+                println!(
+                    "{}\t{}\t{}",
+                    issue.key, issue.fields.status.name, issue.fields.summary
+                );
+            }
+        }
+        FilterCommand::Create {
+            name,
+            jql,
+            description,
+            favourite,
+        } => {
+            let filter = handle_error(jirascope.create_filter(FilterDetails {
+                name,
+                description,
+                jql: Some(jql),
+                favourite: Some(favourite),
+            }));
+            println!("{}", filter.id);
+        }
+        FilterCommand::Delete { name } => {
+            let filter = handle_error(jirascope.get_filter_by_name(&name));
+            handle_error(jirascope.delete_filter(filter.id));
+        }
+        FilterCommand::Share { name, with } => {
+            let filter = handle_error(jirascope.get_filter_by_name(&name));
+            let permissions = match with {
+                Some(with) => handle_error(jirascope.share_filter(filter.id, with)),
+                None => handle_error(
+                    jirascope
+                        .get_filter_share_permissions(filter.id)
+                        .fetch_all(),
+                ),
+            };
+
+            for permission in permissions {
+                println!("{}", permission);
+            }
+        }
+    }
+}
+
+fn read_issue(path: &str) -> Issue {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Error: {}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+
+    match serde_json::from_str(&content) {
+        Ok(issue) => issue,
+        Err(e) => {
+            eprintln!("Error: {}: {}", path, e);
+            std::process::exit(1);
         }
     }
 }
@@ -163,29 +432,16 @@ fn handle_error<T>(result: Result<T, jirascope_core::Error>) -> T {
     match result {
         Ok(t) => t,
         Err(e) => {
-            match e {
-                jirascope_core::Error::Jirascope { message } => {
-                    eprintln!("Error: {}", message);
-                }
-                jirascope_core::Error::Auth { message } => {
-                    eprintln!("Error: {}", message);
-                }
-                jirascope_core::Error::Io(e) => {
-                    eprintln!("Error: {}", e);
-                }
-                jirascope_core::Error::Ureq(e) => match *e {
-                    jirascope_core::ureq::Error::Status(code, response) => {
-                        eprintln!("Error: {} {}", code, response.status_text());
-                        eprintln!("{}", response.into_string().unwrap());
-                    }
-                    _ => {
-                        eprintln!("Error: {}", e);
-                    }
-                },
-                jirascope_core::Error::Jira(code, e) => {
-                    eprintln!("Error {}: {}", code, e);
+            eprintln!("Error: {}", e);
+
+            // the error message already contains the parsed Jira errors,
+            // only show bodies we could not make sense of
+            if let Some(body) = e.body() {
+                if body.errors.is_none() && !body.raw.is_empty() {
+                    eprintln!("{}", body.raw);
                 }
             }
+
             std::process::exit(1);
         }
     }
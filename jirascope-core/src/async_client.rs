@@ -0,0 +1,574 @@
+//! Async client, enabled by the `async` feature.
+//!
+//! [`AsyncJirascope`] mirrors [`Jirascope`] method for method. Requests run
+//! on tokio's blocking thread pool, so awaiting them never stalls the async
+//! worker threads, and they share the blocking client's connection pool,
+//! cache, models and error type.
+//!
+//! List endpoints return [`AsyncPages`], which like [`crate::Pages`] fetch a
+//! page at a time, as the items are awaited.
+
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use tokio::task::JoinHandle;
+
+use crate::{
+    jira::{
+        AtlassianDoc, Comment, CreatedIssue, CustomerRequest, CustomerRequestCreation,
+        FieldConfigurationScheme, Filter, FilterDetails, FilterShare, Issue, IssueCreation,
+        IssueCreationField, IssueCreationMeta, IssueEdit, IssueEditMeta, IssueEvent, IssueIdOrKey,
+        IssueRef, IssueTransitionDescriptor, IssueTransitionDescriptors, IssueType, JiraDateTime,
+        ProjectCategory, ProjectCreate, ProjectCreated, ProjectDetailed, ProjectEdit,
+        ProjectIdOrKey, ProjectIssueSecurityScheme, Queue, QueueIssue, RequestComment, RequestType,
+        ServerInfo, ServiceDesk, SharePermission, Sla, TimeTrackingOptions, User,
+    },
+    pagination::Pager,
+    Auth, Config, Confirmation, DryRunRequest, Expand, FieldSet, Hook, Jirascope, Jql, Pages,
+    Policy,
+};
+
+/// An async Jira client. Cheap to clone, clones share one underlying client.
+#[derive(Clone)]
+pub struct AsyncJirascope {
+    inner: Arc<Jirascope>,
+}
+
+impl From<Jirascope> for AsyncJirascope {
+    fn from(jirascope: Jirascope) -> Self {
+        AsyncJirascope {
+            inner: Arc::new(jirascope),
+        }
+    }
+}
+
+impl AsyncJirascope {
+    pub fn new(config: Config, auth: Auth) -> AsyncJirascope {
+        Jirascope::new(config, auth).into()
+    }
+
+    /// The blocking client underneath, for use outside of async code.
+    pub fn blocking(&self) -> &Jirascope {
+        &self.inner
+    }
+
+    /// See [`Jirascope::init`].
+    pub async fn init(&self) -> Result<(), crate::Error> {
+        self.run(|j| j.init()).await
+    }
+
+    /// See [`Jirascope::server`].
+    pub fn server(&self) -> Option<&ServerInfo> {
+        self.inner.server()
+    }
+
+    /// See [`Jirascope::current_user`].
+    pub fn current_user(&self) -> Option<&User> {
+        self.inner.current_user()
+    }
+
+    /// See [`Jirascope::set_dry_run`].
+    pub fn set_dry_run(&self, dry_run: bool) {
+        self.inner.set_dry_run(dry_run)
+    }
+
+    /// See [`Jirascope::dry_run`].
+    pub fn dry_run(&self) -> bool {
+        self.inner.dry_run()
+    }
+
+    /// See [`Jirascope::add_hook`].
+    pub fn add_hook(&self, hook: impl Hook + 'static) {
+        self.inner.add_hook(hook)
+    }
+
+    /// See [`Jirascope::policy`].
+    pub fn policy(&self) -> &Policy {
+        self.inner.policy()
+    }
+
+    /// See [`Jirascope::authorize_bulk`].
+    pub fn authorize_bulk(
+        &self,
+        count: usize,
+        projects: &[&str],
+        confirmation: Option<&Confirmation>,
+    ) -> Result<(), crate::Error> {
+        self.inner.authorize_bulk(count, projects, confirmation)
+    }
+
+    /// See [`Jirascope::take_dry_run_requests`].
+    pub fn take_dry_run_requests(&self) -> Vec<DryRunRequest> {
+        self.inner.take_dry_run_requests()
+    }
+
+    pub async fn server_info(&self) -> Result<ServerInfo, crate::Error> {
+        self.run(|j| j.server_info()).await
+    }
+
+    pub async fn myself(&self) -> Result<User, crate::Error> {
+        self.run(|j| j.myself()).await
+    }
+
+    pub async fn get_time_tracking_options(&self) -> Result<TimeTrackingOptions, crate::Error> {
+        self.run(|j| j.get_time_tracking_options()).await
+    }
+
+    pub fn get_users(&self) -> AsyncPages<User> {
+        self.pages(self.inner.get_users())
+    }
+
+    pub fn get_projects(&self) -> AsyncPages<ProjectDetailed> {
+        self.pages(self.inner.get_projects())
+    }
+
+    pub async fn get_issue(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+    ) -> Result<Issue, crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.get_issue(issue_id)).await
+    }
+
+    pub async fn get_issue_with(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        fields: FieldSet,
+        expand: Vec<Expand>,
+    ) -> Result<Issue, crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.get_issue_with(issue_id, &fields, &expand))
+            .await
+    }
+
+    pub fn get_all_issues(&self) -> AsyncPages<Issue> {
+        self.pages(self.inner.get_all_issues())
+    }
+
+    pub fn search_issues(&self, jql: impl Into<Jql>) -> AsyncPages<Issue> {
+        self.pages(self.inner.search_issues(jql))
+    }
+
+    pub fn search_issues_with(
+        &self,
+        jql: impl Into<Jql>,
+        fields: FieldSet,
+        expand: Vec<Expand>,
+    ) -> AsyncPages<Issue> {
+        self.pages(self.inner.search_issues_with(jql, &fields, &expand))
+    }
+
+    pub fn search_issue_keys(&self, jql: impl Into<Jql>) -> AsyncPages<IssueRef> {
+        self.pages(self.inner.search_issue_keys(jql))
+    }
+
+    pub async fn approximate_issue_count(
+        &self,
+        jql: impl Into<Jql>,
+    ) -> Result<usize, crate::Error> {
+        let jql = jql.into();
+        self.run(move |j| j.approximate_issue_count(&jql)).await
+    }
+
+    pub fn get_issue_events(&self) -> AsyncPages<IssueEvent> {
+        self.pages(self.inner.get_issue_events())
+    }
+
+    #[deprecated(
+        note = "makes a request per project, use `get_issue_creation_types` and `get_issue_creation_fields`"
+    )]
+    #[allow(deprecated)]
+    pub async fn get_issue_creation_meta(&self) -> Result<IssueCreationMeta, crate::Error> {
+        self.run(|j| j.get_issue_creation_meta()).await
+    }
+
+    pub fn get_issue_creation_types(
+        &self,
+        project: impl Into<ProjectIdOrKey>,
+    ) -> AsyncPages<IssueType> {
+        self.pages(self.inner.get_issue_creation_types(project))
+    }
+
+    pub fn get_issue_creation_fields(
+        &self,
+        project: impl Into<ProjectIdOrKey>,
+        issue_type_id: impl Into<String>,
+    ) -> AsyncPages<IssueCreationField> {
+        self.pages(
+            self.inner
+                .get_issue_creation_fields(project, &issue_type_id.into()),
+        )
+    }
+
+    pub async fn get_issue_edit_meta(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+    ) -> Result<IssueEditMeta, crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.get_issue_edit_meta(issue_id)).await
+    }
+
+    pub async fn create_issue(&self, issue: IssueCreation) -> Result<CreatedIssue, crate::Error> {
+        self.run(move |j| j.create_issue(issue)).await
+    }
+
+    pub async fn edit_issue(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        issue: IssueEdit,
+    ) -> Result<(), crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.edit_issue(issue_id, issue)).await
+    }
+
+    pub async fn edit_issue_if_unchanged(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        expected_updated: JiraDateTime,
+        issue: IssueEdit,
+    ) -> Result<(), crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.edit_issue_if_unchanged(issue_id, &expected_updated, issue))
+            .await
+    }
+
+    pub async fn delete_issue(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+    ) -> Result<(), crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.delete_issue(issue_id)).await
+    }
+
+    pub async fn delete_issue_confirmed(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        confirmation: Confirmation,
+    ) -> Result<(), crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.delete_issue_confirmed(issue_id, &confirmation))
+            .await
+    }
+
+    pub async fn get_issue_transitions(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+    ) -> Result<IssueTransitionDescriptors, crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.get_issue_transitions(issue_id)).await
+    }
+
+    pub async fn transition_issue(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        transition: IssueTransitionDescriptor,
+    ) -> Result<(), crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.transition_issue(issue_id, transition))
+            .await
+    }
+
+    pub async fn add_comment(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        body: AtlassianDoc,
+    ) -> Result<Comment, crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.add_comment(issue_id, body)).await
+    }
+
+    pub async fn create_project(
+        &self,
+        project: ProjectCreate,
+    ) -> Result<ProjectCreated, crate::Error> {
+        self.run(move |j| j.create_project(project)).await
+    }
+
+    pub async fn edit_project(
+        &self,
+        project_id: impl Into<ProjectIdOrKey>,
+        project: ProjectEdit,
+    ) -> Result<(), crate::Error> {
+        let project_id = project_id.into();
+        self.run(move |j| j.edit_project(project_id, project)).await
+    }
+
+    pub async fn delete_project(
+        &self,
+        project_id: impl Into<ProjectIdOrKey>,
+    ) -> Result<(), crate::Error> {
+        let project_id = project_id.into();
+        self.run(move |j| j.delete_project(project_id)).await
+    }
+
+    pub async fn delete_project_confirmed(
+        &self,
+        project_id: impl Into<ProjectIdOrKey>,
+        confirmation: Confirmation,
+    ) -> Result<(), crate::Error> {
+        let project_id = project_id.into();
+        self.run(move |j| j.delete_project_confirmed(project_id, &confirmation))
+            .await
+    }
+
+    pub fn get_favourite_filters(&self) -> AsyncPages<Filter> {
+        self.pages(self.inner.get_favourite_filters())
+    }
+
+    pub fn get_my_filters(&self) -> AsyncPages<Filter> {
+        self.pages(self.inner.get_my_filters())
+    }
+
+    pub fn search_filters(&self, name: impl Into<String>) -> AsyncPages<Filter> {
+        self.pages(self.inner.search_filters(&name.into()))
+    }
+
+    pub async fn get_filter(&self, filter_id: i64) -> Result<Filter, crate::Error> {
+        self.run(move |j| j.get_filter(filter_id)).await
+    }
+
+    pub async fn get_filter_by_name(
+        &self,
+        name: impl Into<String>,
+    ) -> Result<Filter, crate::Error> {
+        let name = name.into();
+        self.run(move |j| j.get_filter_by_name(&name)).await
+    }
+
+    pub fn run_filter(&self, filter: Filter) -> AsyncPages<Issue> {
+        self.pages(self.inner.run_filter(&filter))
+    }
+
+    pub async fn create_filter(&self, filter: FilterDetails) -> Result<Filter, crate::Error> {
+        self.run(move |j| j.create_filter(filter)).await
+    }
+
+    pub async fn edit_filter(
+        &self,
+        filter_id: i64,
+        filter: FilterDetails,
+    ) -> Result<Filter, crate::Error> {
+        self.run(move |j| j.edit_filter(filter_id, filter)).await
+    }
+
+    pub async fn delete_filter(&self, filter_id: i64) -> Result<(), crate::Error> {
+        self.run(move |j| j.delete_filter(filter_id)).await
+    }
+
+    pub fn get_filter_share_permissions(&self, filter_id: i64) -> AsyncPages<SharePermission> {
+        self.pages(self.inner.get_filter_share_permissions(filter_id))
+    }
+
+    pub async fn share_filter(
+        &self,
+        filter_id: i64,
+        share: FilterShare,
+    ) -> Result<Vec<SharePermission>, crate::Error> {
+        self.run(move |j| j.share_filter(filter_id, share)).await
+    }
+
+    pub async fn unshare_filter(
+        &self,
+        filter_id: i64,
+        permission_id: i64,
+    ) -> Result<(), crate::Error> {
+        self.run(move |j| j.unshare_filter(filter_id, permission_id))
+            .await
+    }
+
+    pub fn get_service_desks(&self) -> AsyncPages<ServiceDesk> {
+        self.pages(self.inner.get_service_desks())
+    }
+
+    pub async fn get_service_desk(
+        &self,
+        service_desk_id: i64,
+    ) -> Result<ServiceDesk, crate::Error> {
+        self.run(move |j| j.get_service_desk(service_desk_id)).await
+    }
+
+    pub fn get_request_types(&self, service_desk_id: i64) -> AsyncPages<RequestType> {
+        self.pages(self.inner.get_request_types(service_desk_id))
+    }
+
+    pub fn get_customer_requests(&self, service_desk_id: i64) -> AsyncPages<CustomerRequest> {
+        self.pages(self.inner.get_customer_requests(service_desk_id))
+    }
+
+    pub async fn get_customer_request(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+    ) -> Result<CustomerRequest, crate::Error> {
+        let issue_id = issue_id.into();
+        self.run(move |j| j.get_customer_request(issue_id)).await
+    }
+
+    pub async fn create_customer_request(
+        &self,
+        request: CustomerRequestCreation,
+    ) -> Result<CustomerRequest, crate::Error> {
+        self.run(move |j| j.create_customer_request(request)).await
+    }
+
+    pub fn get_request_comments(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+    ) -> AsyncPages<RequestComment> {
+        self.pages(self.inner.get_request_comments(issue_id))
+    }
+
+    pub async fn add_request_comment(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        body: impl Into<String>,
+        public: bool,
+    ) -> Result<RequestComment, crate::Error> {
+        let issue_id = issue_id.into();
+        let body = body.into();
+        self.run(move |j| j.add_request_comment(issue_id, &body, public))
+            .await
+    }
+
+    pub fn get_queues(&self, service_desk_id: i64) -> AsyncPages<Queue> {
+        self.pages(self.inner.get_queues(service_desk_id))
+    }
+
+    pub fn get_queue_issues(&self, service_desk_id: i64, queue_id: i64) -> AsyncPages<QueueIssue> {
+        self.pages(self.inner.get_queue_issues(service_desk_id, queue_id))
+    }
+
+    pub fn get_request_slas(&self, issue_id: impl Into<IssueIdOrKey>) -> AsyncPages<Sla> {
+        self.pages(self.inner.get_request_slas(issue_id))
+    }
+
+    pub fn get_project_categories(&self) -> AsyncPages<ProjectCategory> {
+        self.pages(self.inner.get_project_categories())
+    }
+
+    pub fn get_issue_security_schemes(&self) -> AsyncPages<ProjectIssueSecurityScheme> {
+        self.pages(self.inner.get_issue_security_schemes())
+    }
+
+    pub fn get_field_configuration_schemes(&self) -> AsyncPages<FieldConfigurationScheme> {
+        self.pages(self.inner.get_field_configuration_schemes())
+    }
+
+    fn pages<T>(&self, pages: Pages<'_, T>) -> AsyncPages<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        AsyncPages {
+            inner: self.inner.clone(),
+            state: State::Idle(pages.into_pager()),
+        }
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T, crate::Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Jirascope) -> Result<T, crate::Error> + Send + 'static,
+    {
+        let inner = self.inner.clone();
+
+        match tokio::task::spawn_blocking(move || f(&inner)).await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(e) => Err(crate::Error::jirascope(format!("Request cancelled: {}", e))),
+        }
+    }
+}
+
+/// Results of a list endpoint, fetched a page at a time as they're awaited.
+/// The async counterpart of [`crate::Pages`].
+///
+/// ```no_run
+/// # async fn example(jirascope: jirascope_core::AsyncJirascope) -> Result<(), jirascope_core::Error> {
+/// let mut users = jirascope.get_users().with_page_size(100);
+/// while let Some(user) = users.next().await {
+///     println!("{}", user?.display_name);
+/// }
+///
+/// let first_ten = jirascope.search_issues("project = ABC").with_limit(10).fetch_all().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncPages<T> {
+    inner: Arc<Jirascope>,
+    state: State<T>,
+}
+
+enum State<T> {
+    Idle(Pager<T>),
+    // kept across calls, so dropping a `next()` future mid-request doesn't
+    // lose the pager: the next call awaits the same request
+    Fetching(JoinHandle<(Pager<T>, Result<(), crate::Error>)>),
+    Cancelled,
+}
+
+impl<T: DeserializeOwned + Send + 'static> AsyncPages<T> {
+    /// See [`crate::Pages::with_page_size`].
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        if let State::Idle(pager) = self.state {
+            self.state = State::Idle(pager.with_page_size(page_size));
+        }
+        self
+    }
+
+    /// See [`crate::Pages::with_limit`].
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        if let State::Idle(pager) = self.state {
+            self.state = State::Idle(pager.with_limit(limit));
+        }
+        self
+    }
+
+    /// The next item, fetching the next page first if needed. A failed
+    /// request is yielded as an error and ends the iteration.
+    pub async fn next(&mut self) -> Option<Result<T, crate::Error>> {
+        loop {
+            match &mut self.state {
+                State::Idle(pager) if !pager.needs_page() => return pager.pop().map(Ok),
+                State::Idle(_) => {
+                    if let State::Idle(mut pager) =
+                        std::mem::replace(&mut self.state, State::Cancelled)
+                    {
+                        let inner = self.inner.clone();
+                        self.state = State::Fetching(tokio::task::spawn_blocking(move || {
+                            let result = pager.fetch_page(&inner);
+                            (pager, result)
+                        }));
+                    }
+                }
+                State::Fetching(handle) => match handle.await {
+                    Ok((pager, result)) => {
+                        self.state = State::Idle(pager);
+                        if let Err(e) = result {
+                            return Some(Err(e));
+                        }
+                    }
+                    Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+                    // the pager went with the task, nothing more can be fetched
+                    Err(e) => {
+                        self.state = State::Cancelled;
+                        return Some(Err(crate::Error::jirascope(format!(
+                            "Request cancelled: {}",
+                            e
+                        ))));
+                    }
+                },
+                State::Cancelled => return None,
+            }
+        }
+    }
+
+    /// Fetch every remaining page.
+    pub async fn fetch_all(mut self) -> Result<Vec<T>, crate::Error> {
+        let mut items = Vec::new();
+
+        while let Some(item) = self.next().await {
+            items.push(item?);
+        }
+
+        Ok(items)
+    }
+}
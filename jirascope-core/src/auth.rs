@@ -3,43 +3,47 @@ use ureq::Request;
 
 use crate::Config;
 
+// The header is built once up front, so authenticating a request never
+// needs `&mut self` and an `Auth` can be shared between threads.
 pub struct Auth {
     username: String,
-    api_token: String,
-    cached_basic_auth: Option<String>,
+    basic_auth: String,
+}
+
+// Keep the token and the derived header out of debug output and logs.
+impl std::fmt::Debug for Auth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Auth")
+            .field("username", &self.username)
+            .field("api_token", &"[REDACTED]")
+            .finish()
+    }
 }
 
 impl Auth {
     pub fn new(username: impl Into<String>, api_token: impl Into<String>) -> Auth {
+        let username = username.into();
+        let api_token = api_token.into();
+
+        let base64 = base64::engine::GeneralPurpose::new(&base64::alphabet::STANDARD, base64::engine::GeneralPurposeConfig::default());
+        let basic_auth = base64.encode(format!("{}:{}", username, api_token));
+
         Auth {
-            username: username.into(),
-            api_token: api_token.into(),
-            cached_basic_auth: None,
+            username,
+            basic_auth,
         }
     }
 
-    pub fn login(&mut self, _config: &Config) -> Result<(), crate::Error> {
-        self.ensure_cached_basic_auth();
-
+    pub fn login(&self, _config: &Config) -> Result<(), crate::Error> {
         Ok(())
     }
 
-    fn ensure_cached_basic_auth(&mut self) {
-        if self.cached_basic_auth.is_none() {
-            let base64 = base64::engine::GeneralPurpose::new(&base64::alphabet::STANDARD, base64::engine::GeneralPurposeConfig::default());
-            let basic_auth = base64.encode(format!("{}:{}", self.username, self.api_token));
-            self.cached_basic_auth = Some(basic_auth);
-        }
-    }
-
-    pub fn get_basic_auth(&mut self) -> String {
-        self.ensure_cached_basic_auth();
-
-        self.cached_basic_auth.as_ref().unwrap().clone()
+    pub fn get_basic_auth(&self) -> String {
+        self.basic_auth.clone()
     }
 
-    pub fn auth(&mut self, request: Request) -> Request {
-        request.set("Authorization", format!("Basic {}", self.get_basic_auth()).as_str())
+    pub fn auth(&self, request: Request) -> Request {
+        request.set("Authorization", format!("Basic {}", self.basic_auth).as_str())
     }
 }
 
@@ -49,13 +53,13 @@ mod tests {
 
     #[test]
     fn basic_auth() {
-        let mut auth = Auth::new("username", "api_token");
+        let auth = Auth::new("username", "api_token");
         assert_eq!(auth.get_basic_auth(), "dXNlcm5hbWU6YXBpX3Rva2Vu");
     }
 
     #[test]
     fn auth() {
-        let mut auth = Auth::new("username", "api_token");
+        let auth = Auth::new("username", "api_token");
         let request = ureq::get("https://example.com");
         let request = auth.auth(request);
         assert_eq!(request.header("Authorization").unwrap(), "Basic dXNlcm5hbWU6YXBpX3Rva2Vu");
@@ -63,7 +67,7 @@ mod tests {
 
     #[test]
     fn auth_with_login() {
-        let mut auth = Auth::new("username", "api_token");
+        let auth = Auth::new("username", "api_token");
         let config = Config::new("https://example.atlassian.net");
         auth.login(&config).unwrap();
 
@@ -0,0 +1,342 @@
+//! Conditional request cache for `GET` requests.
+//!
+//! Responses carrying an `ETag` or `Last-Modified` header are remembered per
+//! URL. The next request for the same URL is sent with `If-None-Match` /
+//! `If-Modified-Since`, and a `304 Not Modified` answer is served from here.
+//! Cached bodies are never served without revalidating them first.
+//!
+//! Identical requests made from several threads at once are coalesced: the
+//! first one goes to the network, the others wait for its response.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Condvar, Mutex, PoisonError},
+};
+
+use crate::response::ApiResponse;
+
+const MAX_ENTRIES: usize = 256;
+
+/// Validators sent along with a conditional request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    fn of(response: &ApiResponse) -> Option<Validators> {
+        if response.etag.is_none() && response.last_modified.is_none() {
+            return None;
+        }
+
+        Some(Validators {
+            etag: response.etag.clone(),
+            last_modified: response.last_modified.clone(),
+        })
+    }
+}
+
+/// What a caller of [`HttpCache::get`] got back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CacheOutcome {
+    /// Fetched from the network, nothing usable was cached.
+    Miss,
+    /// The server answered `304`, the cached body was served.
+    Revalidated,
+    /// Another thread fetched the same URL at the same time.
+    Coalesced,
+}
+
+struct CacheEntry {
+    validators: Validators,
+    response: ApiResponse,
+}
+
+enum Flight {
+    Pending,
+    // `None` if the leading request failed, errors are not shared
+    Done(Option<ApiResponse>),
+}
+
+struct InFlight {
+    state: Mutex<Flight>,
+    done: Condvar,
+}
+
+/// Ends the leader's flight when dropped, so that the followers wake up even
+/// if `fetch` panics.
+struct Landing<'a> {
+    cache: &'a HttpCache,
+    url: &'a str,
+    shared: Option<ApiResponse>,
+}
+
+impl Drop for Landing<'_> {
+    fn drop(&mut self) {
+        // a panic while unwinding would abort, poisoned locks are fine here
+        let flight = self
+            .cache
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(self.url);
+
+        if let Some(flight) = flight {
+            *flight.state.lock().unwrap_or_else(PoisonError::into_inner) =
+                Flight::Done(self.shared.take());
+            flight.done.notify_all();
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct HttpCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    // insertion order, oldest first, used for eviction
+    order: Mutex<VecDeque<String>>,
+    in_flight: Mutex<HashMap<String, Arc<InFlight>>>,
+}
+
+impl HttpCache {
+    pub fn new() -> HttpCache {
+        HttpCache::default()
+    }
+
+    /// Get `url`, using `fetch` to send the actual request.
+    ///
+    /// `fetch` receives the validators to send, if any, and may be called a
+    /// second time without them if the cached entry vanished in between.
+    pub fn get(
+        &self,
+        url: &str,
+        mut fetch: impl FnMut(Option<&Validators>) -> Result<ApiResponse, crate::Error>,
+    ) -> Result<(ApiResponse, CacheOutcome), crate::Error> {
+        let flight = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+
+            match in_flight.get(url) {
+                Some(flight) => Some(flight.clone()),
+                None => {
+                    in_flight.insert(
+                        url.to_string(),
+                        Arc::new(InFlight {
+                            state: Mutex::new(Flight::Pending),
+                            done: Condvar::new(),
+                        }),
+                    );
+                    None
+                }
+            }
+        };
+
+        if let Some(flight) = flight {
+            let mut state = flight.state.lock().unwrap();
+            while let Flight::Pending = *state {
+                state = flight.done.wait(state).unwrap();
+            }
+
+            if let Flight::Done(Some(response)) = &*state {
+                return Ok((response.clone(), CacheOutcome::Coalesced));
+            }
+
+            // the leader failed, try on our own instead of sharing its error
+            drop(state);
+            return self.fetch(url, &mut fetch);
+        }
+
+        let mut landing = Landing {
+            cache: self,
+            url,
+            shared: None,
+        };
+        let result = self.fetch(url, &mut fetch);
+        landing.shared = result.as_ref().ok().map(|(response, _)| response.clone());
+
+        result
+    }
+
+    fn fetch(
+        &self,
+        url: &str,
+        fetch: &mut impl FnMut(Option<&Validators>) -> Result<ApiResponse, crate::Error>,
+    ) -> Result<(ApiResponse, CacheOutcome), crate::Error> {
+        let validators = self
+            .entries
+            .lock()
+            .unwrap()
+            .get(url)
+            .map(|entry| entry.validators.clone());
+
+        let response = fetch(validators.as_ref())?;
+
+        if response.status == 304 {
+            if let Some(entry) = self.entries.lock().unwrap().get(url) {
+                return Ok((entry.response.clone(), CacheOutcome::Revalidated));
+            }
+
+            // evicted while the request was in flight
+            let response = fetch(None)?;
+            self.store(url, &response);
+            return Ok((response, CacheOutcome::Miss));
+        }
+
+        self.store(url, &response);
+
+        Ok((response, CacheOutcome::Miss))
+    }
+
+    fn store(&self, url: &str, response: &ApiResponse) {
+        let validators = match Validators::of(response) {
+            Some(validators) => validators,
+            None => {
+                self.entries.lock().unwrap().remove(url);
+                return;
+            }
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        let mut order = self.order.lock().unwrap();
+
+        if entries
+            .insert(
+                url.to_string(),
+                CacheEntry {
+                    validators,
+                    response: response.clone(),
+                },
+            )
+            .is_none()
+        {
+            order.push_back(url.to_string());
+        }
+
+        while entries.len() > MAX_ENTRIES {
+            match order.pop_front() {
+                Some(oldest) => {
+                    entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn response(status: u16, body: &str, etag: Option<&str>) -> ApiResponse {
+        ApiResponse {
+            status,
+            body: body.to_string(),
+            etag: etag.map(str::to_string),
+            last_modified: None,
+        }
+    }
+
+    #[test]
+    fn not_modified_is_served_from_cache() {
+        let cache = HttpCache::new();
+
+        let (first, outcome) = cache
+            .get("issue/A-1", |validators| {
+                assert!(validators.is_none());
+                Ok(response(200, "{\"key\":\"A-1\"}", Some("\"v1\"")))
+            })
+            .unwrap();
+        assert_eq!(outcome, CacheOutcome::Miss);
+
+        let (second, outcome) = cache
+            .get("issue/A-1", |validators| {
+                assert_eq!(validators.unwrap().etag.as_deref(), Some("\"v1\""));
+                Ok(response(304, "", None))
+            })
+            .unwrap();
+        assert_eq!(outcome, CacheOutcome::Revalidated);
+        assert_eq!(second.body, first.body);
+    }
+
+    #[test]
+    fn responses_without_validators_are_not_cached() {
+        let cache = HttpCache::new();
+
+        cache
+            .get("project", |_| Ok(response(200, "[]", None)))
+            .unwrap();
+
+        cache
+            .get("project", |validators| {
+                assert!(validators.is_none());
+                Ok(response(200, "[]", None))
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn concurrent_requests_are_coalesced() {
+        let cache = Arc::new(HttpCache::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let threads = (0..4)
+            .map(|_| {
+                let cache = cache.clone();
+                let calls = calls.clone();
+                std::thread::spawn(move || {
+                    cache
+                        .get("search", |_| {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            std::thread::sleep(std::time::Duration::from_millis(100));
+                            Ok(response(200, "{}", None))
+                        })
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let outcomes = threads
+            .into_iter()
+            .map(|t| t.join().unwrap().1)
+            .collect::<Vec<_>>();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            outcomes
+                .iter()
+                .filter(|o| **o == CacheOutcome::Coalesced)
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn a_panicking_leader_releases_its_followers() {
+        let cache = Arc::new(HttpCache::new());
+
+        let leader = {
+            let cache = cache.clone();
+            std::thread::spawn(move || {
+                cache.get("search", |_| {
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                    panic!("worker died");
+                })
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(20));
+
+        // waits for the leader, then fetches on its own
+        let (_, outcome) = cache
+            .get("search", |_| Ok(response(200, "{}", None)))
+            .unwrap();
+        assert_eq!(outcome, CacheOutcome::Miss);
+        assert!(leader.join().is_err());
+
+        // nothing is left in flight
+        let (_, outcome) = cache
+            .get("search", |_| Ok(response(200, "{}", None)))
+            .unwrap();
+        assert_eq!(outcome, CacheOutcome::Miss);
+    }
+}
@@ -1,12 +1,40 @@
-#[derive(Debug, Default)]
+use std::path::PathBuf;
+
+use crate::Policy;
+
+#[derive(Debug)]
 pub struct Config {
     pub api_url: String,
+    /// Log redacted request and response bodies at `TRACE` level.
+    pub dump_bodies: bool,
+    /// Revalidate `GET` responses with `ETag` / `Last-Modified` instead of
+    /// downloading them again. On unless switched off.
+    pub http_cache: bool,
+    /// Where to keep the on-disk snapshot, see [`crate::SnapshotStore`].
+    pub cache_dir: Option<PathBuf>,
+    /// Log changes instead of sending them, see [`crate::DryRunRequest`].
+    /// Can be switched later with [`crate::Jirascope::set_dry_run`].
+    pub dry_run: bool,
+    /// Which changes are allowed, see [`crate::Policy`].
+    pub policy: Policy,
 }
 
 impl Config {
     pub fn new(jira_url: impl Into<String>) -> Config {
         Config {
             api_url: jira_url.into(),
+            dump_bodies: false,
+            http_cache: true,
+            cache_dir: None,
+            dry_run: false,
+            policy: Policy::default(),
         }
     }
 }
+
+/// Like [`Config::new`] without a URL.
+impl Default for Config {
+    fn default() -> Config {
+        Config::new("")
+    }
+}
@@ -0,0 +1,404 @@
+//! Field by field comparison of two versions of an issue.
+//!
+//! Every field is compared, custom ones included. Text, which includes
+//! descriptions and other rich text fields after turning them into markdown,
+//! is compared word by word so that a changed sentence doesn't show up as a
+//! whole new description. Anything else is reported as an old and a new
+//! value.
+
+use serde::Serialize;
+use ureq::serde_json::{self, Map, Value};
+
+use crate::jira::{AtlassianDoc, Issue, IssueKey};
+
+/// Fields shown first, in this order. The rest follow sorted by id.
+const FIELD_ORDER: &[&str] = &[
+    "summary",
+    "status",
+    "priority",
+    "issuetype",
+    "assignee",
+    "reporter",
+    "parent",
+    "project",
+    "description",
+    "created",
+    "updated",
+];
+
+/// Above this many cells, a text diff gives up on finding the common words
+/// and reports the whole text as replaced.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// What changed between two versions of an issue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IssueDiff {
+    pub key: IssueKey,
+    pub changes: Vec<FieldChange>,
+}
+
+/// A single changed field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    /// Field id, like `summary` or `customfield_10010`.
+    pub field: String,
+    /// Display name of the field, when either issue was fetched with
+    /// [`crate::Expand::Names`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    /// Word level differences of a text field.
+    Text { chunks: Vec<TextChunk> },
+    /// Any other field. `None` when the field was empty or missing.
+    Value {
+        old: Option<Value>,
+        new: Option<Value>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "op", content = "text", rename_all = "snake_case")]
+pub enum TextChunk {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+impl TextChunk {
+    pub fn text(&self) -> &str {
+        match self {
+            TextChunk::Same(text) | TextChunk::Added(text) | TextChunk::Removed(text) => text,
+        }
+    }
+}
+
+impl IssueDiff {
+    /// Compare `old` with `new`. The key is taken from `new`.
+    pub fn new(old: &Issue, new: &Issue) -> IssueDiff {
+        let old_fields = fields_of(old);
+        let new_fields = fields_of(new);
+
+        let mut ids: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+        ids.sort_by_key(|id| {
+            (
+                FIELD_ORDER
+                    .iter()
+                    .position(|known| known == id)
+                    .unwrap_or(FIELD_ORDER.len()),
+                id.as_str(),
+            )
+        });
+        ids.dedup();
+
+        let changes = ids
+            .into_iter()
+            .filter_map(|id| {
+                let change = compare(old_fields.get(id), new_fields.get(id))?;
+                let name = [new, old]
+                    .iter()
+                    .find_map(|issue| issue.names.as_ref()?.get(id).cloned());
+
+                Some(FieldChange {
+                    field: id.clone(),
+                    name,
+                    change,
+                })
+            })
+            .collect();
+
+        IssueDiff {
+            key: new.key.clone(),
+            changes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// A short human readable form of a field value, like the name of a status
+/// or the display name of a user.
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(display_value)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Object(object) => {
+            if let Some(text) = as_text(value) {
+                return text;
+            }
+
+            ["displayName", "name", "value", "key"]
+                .iter()
+                .find_map(|key| object.get(*key).and_then(Value::as_str))
+                .map(str::to_string)
+                .unwrap_or_else(|| value.to_string())
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn fields_of(issue: &Issue) -> Map<String, Value> {
+    // the fields are plain data, serializing them can't fail
+    match serde_json::to_value(&issue.fields) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    }
+}
+
+fn compare(old: Option<&Value>, new: Option<&Value>) -> Option<Change> {
+    let old = old.filter(|v| !v.is_null());
+    let new = new.filter(|v| !v.is_null());
+
+    if old == new {
+        return None;
+    }
+
+    let old_text = old.map(as_text);
+    let new_text = new.map(as_text);
+
+    match (old_text, new_text) {
+        (Some(None), _) | (_, Some(None)) => Some(Change::Value {
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+        (old_text, new_text) => {
+            let old_text = old_text.flatten().unwrap_or_default();
+            let new_text = new_text.flatten().unwrap_or_default();
+
+            // e.g. a description reformatted without changing its markdown
+            if old_text == new_text {
+                return None;
+            }
+
+            Some(Change::Text {
+                chunks: diff_text(&old_text, &new_text),
+            })
+        }
+    }
+}
+
+/// The value as text, if it is a string or a rich text document.
+fn as_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Object(object) if object.get("type").and_then(Value::as_str) == Some("doc") => {
+            serde_json::from_value::<AtlassianDoc>(value.clone())
+                .ok()
+                .map(|doc| doc.to_markdown())
+        }
+        _ => None,
+    }
+}
+
+/// Words and the whitespace between them, so that joining the tokens gives
+/// back the text.
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+
+    for (i, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        if in_space.is_some_and(|in_space| in_space != space) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        in_space = Some(space);
+    }
+
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+
+    tokens
+}
+
+fn diff_text(old: &str, new: &str) -> Vec<TextChunk> {
+    let old = tokenize(old);
+    let new = tokenize(new);
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut chunks = Vec::new();
+    push_chunk(&mut chunks, TextChunk::Same(old[..prefix].concat()));
+
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    if (old_middle.len() + 1).saturating_mul(new_middle.len() + 1) > MAX_DIFF_CELLS {
+        push_chunk(&mut chunks, TextChunk::Removed(old_middle.concat()));
+        push_chunk(&mut chunks, TextChunk::Added(new_middle.concat()));
+    } else {
+        for chunk in lcs_diff(old_middle, new_middle) {
+            push_chunk(&mut chunks, chunk);
+        }
+    }
+
+    push_chunk(
+        &mut chunks,
+        TextChunk::Same(old[old.len() - suffix..].concat()),
+    );
+
+    chunks
+}
+
+fn lcs_diff(old: &[&str], new: &[&str]) -> Vec<TextChunk> {
+    let width = new.len() + 1;
+    // lengths[i * width + j] is the longest common subsequence of old[i..]
+    // and new[j..]
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            chunks.push(TextChunk::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < old.len()
+            && (j == new.len() || lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            chunks.push(TextChunk::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            chunks.push(TextChunk::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+
+    chunks
+}
+
+/// Append `chunk`, merging it into the previous one of the same kind.
+fn push_chunk(chunks: &mut Vec<TextChunk>, chunk: TextChunk) {
+    if chunk.text().is_empty() {
+        return;
+    }
+
+    match (chunks.last_mut(), chunk) {
+        (Some(TextChunk::Same(last)), TextChunk::Same(text))
+        | (Some(TextChunk::Added(last)), TextChunk::Added(text))
+        | (Some(TextChunk::Removed(last)), TextChunk::Removed(text)) => last.push_str(&text),
+        (_, chunk) => chunks.push(chunk),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(fields: Value) -> Issue {
+        crate::test_utils::issue("ABC-1", fields)
+    }
+
+    #[test]
+    fn words_are_diffed() {
+        assert_eq!(
+            diff_text("the quick brown fox", "the slow brown fox jumps"),
+            vec![
+                TextChunk::Same("the ".to_string()),
+                TextChunk::Removed("quick".to_string()),
+                TextChunk::Added("slow".to_string()),
+                TextChunk::Same(" brown fox".to_string()),
+                TextChunk::Added(" jumps".to_string()),
+            ]
+        );
+        assert_eq!(
+            diff_text("", "new"),
+            vec![TextChunk::Added("new".to_string())]
+        );
+        assert_eq!(tokenize(" a  b\n").concat(), " a  b\n");
+    }
+
+    #[test]
+    fn only_changed_fields_are_reported() {
+        let old = issue(serde_json::json!({}));
+        assert!(IssueDiff::new(&old, &old).is_empty());
+
+        let new = issue(serde_json::json!({
+            "summary": "First issue, renamed",
+            "status": { "id": "2", "name": "Done" },
+            "description": {
+                "version": 1,
+                "type": "doc",
+                "content": [{
+                    "type": "paragraph",
+                    "content": [{ "type": "text", "text": "Some details" }]
+                }]
+            },
+            "customfield_10010": 5
+        }));
+        let diff = IssueDiff::new(&old, &new);
+
+        assert_eq!(diff.key, "ABC-1");
+        assert_eq!(
+            diff.changes
+                .iter()
+                .map(|c| c.field.as_str())
+                .collect::<Vec<_>>(),
+            vec!["summary", "status", "description", "customfield_10010"]
+        );
+        assert_eq!(
+            diff.changes[0].change,
+            Change::Text {
+                chunks: vec![
+                    TextChunk::Same("First ".to_string()),
+                    TextChunk::Removed("issue".to_string()),
+                    TextChunk::Added("issue, renamed".to_string()),
+                ]
+            }
+        );
+        assert!(matches!(
+            &diff.changes[2].change,
+            Change::Text { chunks } if chunks[0] == TextChunk::Added("Some details\n".to_string())
+        ));
+        assert_eq!(
+            diff.changes[3].change,
+            Change::Value {
+                old: None,
+                new: Some(serde_json::json!(5))
+            }
+        );
+    }
+
+    #[test]
+    fn values_are_displayed_by_name() {
+        assert_eq!(
+            display_value(&serde_json::json!({ "id": "1", "name": "Done" })),
+            "Done"
+        );
+        assert_eq!(
+            display_value(&serde_json::json!([{ "value": "red" }, { "value": "blue" }])),
+            "red, blue"
+        );
+        assert_eq!(display_value(&serde_json::json!(5)), "5");
+    }
+}
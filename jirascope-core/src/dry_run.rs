@@ -0,0 +1,163 @@
+//! Dry-run mode, where changes are logged instead of sent.
+//!
+//! Reads still go to the server, so scripts and workflows run against real
+//! data. Every `POST`, `PUT` and `DELETE` is logged at `INFO` level, kept for
+//! [`crate::Jirascope::take_dry_run_requests`], and answered with a made up
+//! success, shaped like the real response so that callers decoding it keep
+//! working.
+
+use std::fmt::{Display, Formatter};
+
+use chrono::Utc;
+use ureq::serde_json::{self, json, Value};
+
+use crate::{jira::JiraDateTime, response::ApiResponse};
+
+/// At most this many requests are kept, the oldest are dropped first.
+pub(crate) const MAX_KEPT_REQUESTS: usize = 100;
+
+/// A request which would have been sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRunRequest {
+    pub method: String,
+    pub url: String,
+    pub body: Option<Value>,
+}
+
+impl Display for DryRunRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.method, self.url)?;
+
+        if let Some(ref body) = self.body {
+            let body = serde_json::to_string_pretty(body).map_err(|_| std::fmt::Error)?;
+            write!(f, "\n{}", body)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What the server would roughly have answered to `method` on `path`.
+pub(crate) fn response(method: &str, path: &str, body: Option<&Value>) -> ApiResponse {
+    let segments = path.split('?').next().unwrap_or_default();
+    let segments = segments.split('/').collect::<Vec<_>>();
+    let body = body.unwrap_or(&Value::Null);
+
+    let response = match (method, segments.as_slice()) {
+        ("POST", ["issue"]) => {
+            let project = body["fields"]["project"]["key"]
+                .as_str()
+                .unwrap_or("DRYRUN");
+            Some(json!({
+                "id": "0",
+                "key": format!("{}-0", project),
+                "self": "",
+            }))
+        }
+        ("POST", ["issue", _, "comment"]) => {
+            let now = JiraDateTime(Utc::now().into()).to_string();
+            Some(json!({
+                "id": "0",
+                "author": null,
+                "body": body["body"],
+                "created": now,
+                "updated": now,
+            }))
+        }
+        ("POST", ["filter"]) | ("PUT", ["filter", _]) => Some(json!({
+            "id": segments.get(1).copied().unwrap_or("0"),
+            "name": body["name"],
+            "jql": body["jql"].as_str().unwrap_or_default(),
+        })),
+        ("POST", ["filter", _, "permission"]) => Some(json!([])),
+        ("POST", ["", "rest", "servicedeskapi", "request"]) => Some(json!({
+            "issueId": "0",
+            "issueKey": "DRYRUN-0",
+            "requestTypeId": body["requestTypeId"],
+            "serviceDeskId": body["serviceDeskId"],
+            "createdDate": {
+                "iso8601": "",
+                "friendly": "",
+                "epochMillis": 0,
+            },
+        })),
+        ("POST", ["", "rest", "servicedeskapi", "request", _, "comment"]) => Some(json!({
+            "id": "0",
+            "body": body["body"],
+            "public": body["public"],
+        })),
+        ("POST", ["project"]) => Some(json!({
+            "id": 0,
+            "key": body["key"],
+        })),
+        _ => None,
+    };
+
+    match response {
+        Some(response) => ApiResponse {
+            status: 201,
+            body: response.to_string(),
+            etag: None,
+            last_modified: None,
+        },
+        None => ApiResponse {
+            status: 204,
+            body: String::new(),
+            etag: None,
+            last_modified: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jira::{Comment, CreatedIssue, RequestComment};
+
+    #[test]
+    fn responses_decode_like_real_ones() {
+        let created: CreatedIssue = response(
+            "POST",
+            "issue",
+            Some(&json!({ "fields": { "project": { "key": "ABC" } } })),
+        )
+        .json()
+        .unwrap();
+        assert_eq!(created.key, "ABC-0");
+
+        let body = json!({ "version": 1, "type": "doc", "content": [] });
+        let comment: Comment = response(
+            "POST",
+            "issue/ABC-1/comment",
+            Some(&json!({ "body": body })),
+        )
+        .json()
+        .unwrap();
+        assert!(comment.body.content.is_empty());
+
+        let comment: RequestComment = response(
+            "POST",
+            "/rest/servicedeskapi/request/HELP-1/comment",
+            Some(&json!({ "body": "On it", "public": false })),
+        )
+        .json()
+        .unwrap();
+        assert!(!comment.public);
+
+        assert_eq!(response("PUT", "issue/ABC-1", None).status, 204);
+    }
+
+    #[test]
+    fn requests_show_method_url_and_body() {
+        let request = DryRunRequest {
+            method: "PUT".to_string(),
+            url: "https://example.atlassian.net/rest/api/3/issue/ABC-1".to_string(),
+            body: Some(json!({ "fields": { "summary": "New" } })),
+        };
+
+        assert_eq!(
+            request.to_string(),
+            "PUT https://example.atlassian.net/rest/api/3/issue/ABC-1\n{\n  \"fields\": {\n    \"summary\": \"New\"\n  }\n}"
+        );
+    }
+}
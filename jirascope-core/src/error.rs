@@ -1,14 +1,123 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    time::Duration,
+};
 
-use crate::jira;
+use ureq::serde_json;
+
+use crate::{jira, response::snippet, Operation, PolicyViolation};
 
 #[derive(Debug)]
 pub enum Error {
-    Jirascope { message: String },
-    Auth { message: String },
+    Jirascope {
+        message: String,
+    },
+    Auth {
+        message: String,
+    },
     Io(std::io::Error),
-    Ureq(Box<ureq::Error>), // ureq::Error is Big
-    Jira(u16, jira::ErrorCollection),
+    BadHost {
+        url: String,
+        message: String,
+    },
+    Tls {
+        url: String,
+        message: String,
+    },
+    NotJira {
+        url: String,
+    },
+    /// 401, the login or API token was rejected.
+    Unauthorized(Box<ErrorBody>),
+    /// 403, the user lacks permission for the resource.
+    Forbidden(Box<ErrorBody>),
+    /// 404
+    NotFound(Box<ErrorBody>),
+    /// 429, `retry_after` is taken from the `Retry-After` header if present.
+    RateLimited {
+        retry_after: Option<Duration>,
+        body: Box<ErrorBody>,
+    },
+    /// 400, Jira rejected the request content.
+    Validation {
+        field_errors: HashMap<String, String>,
+        body: Box<ErrorBody>,
+    },
+    /// 5xx
+    Server {
+        status: u16,
+        body: Box<ErrorBody>,
+    },
+    /// Any other unsuccessful status.
+    Status {
+        status: u16,
+        body: Box<ErrorBody>,
+    },
+    Transport(Box<ureq::Transport>), // ureq::Transport is Big
+    /// The response body could not be decoded into the expected type.
+    Decode {
+        status: u16,
+        body_snippet: String,
+        /// Where in the body decoding failed, e.g. `issues[3].fields.status`.
+        path: Option<String>,
+        /// The issue that part of the body belongs to, if any.
+        issue_key: Option<String>,
+        source: serde_json::Error,
+    },
+    /// The issue was updated since the version a change was made against.
+    /// `current` is the issue as it is now, to merge the change into.
+    Conflict {
+        current: Box<jira::Issue>,
+    },
+    /// The change isn't allowed by the client's [`crate::Policy`], nothing
+    /// was sent.
+    PolicyViolation(PolicyViolation),
+    /// A [`crate::Hook`] vetoed the change, nothing was sent.
+    Vetoed {
+        operation: Operation,
+        reason: String,
+    },
+    /// A key or id didn't have the shape of one, e.g. `abc-1` as an issue
+    /// key.
+    InvalidId {
+        kind: &'static str,
+        value: String,
+    },
+    /// A duration wasn't in Jira's `1w 2d 3h 30m` syntax.
+    InvalidDuration {
+        value: String,
+    },
+    /// A timestamp or date wasn't in any format Jira uses.
+    InvalidDate {
+        value: String,
+    },
+}
+
+/// Body of an unsuccessful response, kept verbatim for diagnostics.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorBody {
+    pub raw: String,
+    /// The body parsed as a Jira error collection, if it was one.
+    pub errors: Option<jira::ErrorCollection>,
+}
+
+impl ErrorBody {
+    pub fn new(raw: String) -> ErrorBody {
+        let errors = serde_json::from_str(&raw).ok();
+
+        ErrorBody { raw, errors }
+    }
+}
+
+impl Display for ErrorBody {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.errors {
+            Some(errors) => write!(f, "{}", errors),
+            None if self.raw.is_empty() => write!(f, "No response body"),
+            None => write!(f, "{}", snippet(&self.raw)),
+        }
+    }
 }
 
 impl Error {
@@ -23,6 +132,117 @@ impl Error {
             message: message.into(),
         }
     }
+
+    /// Build the error for an unsuccessful response, reading its body.
+    pub(crate) fn from_status(status: u16, response: ureq::Response) -> Error {
+        let retry_after = response
+            .header("Retry-After")
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+
+        let body = match response.into_string() {
+            Ok(raw) => Box::new(ErrorBody::new(raw)),
+            Err(e) => return Error::Io(e),
+        };
+
+        match status {
+            400 => Error::Validation {
+                field_errors: body
+                    .errors
+                    .as_ref()
+                    .map(|e| e.errors.clone())
+                    .unwrap_or_default(),
+                body,
+            },
+            401 => Error::Unauthorized(body),
+            403 => Error::Forbidden(body),
+            404 => Error::NotFound(body),
+            429 => Error::RateLimited { retry_after, body },
+            500..=599 => Error::Server { status, body },
+            _ => Error::Status { status, body },
+        }
+    }
+
+    /// Classify a transport failure that happened while talking to `url`.
+    ///
+    /// ureq does not expose TLS failures as a separate kind, so those are
+    /// recognized by the message of the underlying error.
+    pub(crate) fn from_transport(url: &str, transport: ureq::Transport) -> Error {
+        let message = transport.to_string();
+
+        match transport.kind() {
+            ureq::ErrorKind::InvalidUrl | ureq::ErrorKind::UnknownScheme | ureq::ErrorKind::Dns => {
+                Error::BadHost {
+                    url: url.to_string(),
+                    message,
+                }
+            }
+            _ if is_tls_message(&message) => Error::Tls {
+                url: url.to_string(),
+                message,
+            },
+            _ => Error::Transport(Box::new(transport)),
+        }
+    }
+
+    /// Whether repeating the same request later may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::RateLimited { .. } => true,
+            Error::Server { status, .. } => matches!(status, 502..=504),
+            Error::Transport(transport) => matches!(
+                transport.kind(),
+                ureq::ErrorKind::Dns
+                    | ureq::ErrorKind::ConnectionFailed
+                    | ureq::ErrorKind::Io
+                    | ureq::ErrorKind::ProxyConnect
+            ),
+            _ => false,
+        }
+    }
+
+    /// The HTTP status of the failed response, if there was one.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Unauthorized(_) => Some(401),
+            Error::Forbidden(_) => Some(403),
+            Error::NotFound(_) => Some(404),
+            Error::RateLimited { .. } => Some(429),
+            Error::Validation { .. } => Some(400),
+            Error::Server { status, .. }
+            | Error::Status { status, .. }
+            | Error::Decode { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// The raw body of the failed response, if there was one.
+    pub fn body(&self) -> Option<&ErrorBody> {
+        match self {
+            Error::Unauthorized(body)
+            | Error::Forbidden(body)
+            | Error::NotFound(body)
+            | Error::RateLimited { body, .. }
+            | Error::Validation { body, .. }
+            | Error::Server { body, .. }
+            | Error::Status { body, .. } => Some(body),
+            _ => None,
+        }
+    }
+}
+
+fn is_tls_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+
+    ["tls", "ssl", "certificate", "handshake"]
+        .iter()
+        .any(|needle| message.contains(needle))
+}
+
+impl From<PolicyViolation> for Error {
+    fn from(violation: PolicyViolation) -> Self {
+        Error::PolicyViolation(violation)
+    }
 }
 
 impl From<std::io::Error> for Error {
@@ -33,7 +253,10 @@ impl From<std::io::Error> for Error {
 
 impl From<ureq::Error> for Error {
     fn from(error: ureq::Error) -> Self {
-        Error::Ureq(Box::new(error))
+        match error {
+            ureq::Error::Status(status, response) => Error::from_status(status, response),
+            ureq::Error::Transport(transport) => Error::Transport(Box::new(transport)),
+        }
     }
 }
 
@@ -43,10 +266,150 @@ impl Display for Error {
             Error::Jirascope { message } => write!(f, "Jirascope error: {}", message),
             Error::Auth { message } => write!(f, "Auth error: {}", message),
             Error::Io(e) => write!(f, "IO error: {}", e),
-            Error::Ureq(e) => write!(f, "Ureq error: {}", e),
-            Error::Jira(code, e) => write!(f, "Jira error {}: {}", code, e),
+            Error::BadHost { url, message } => {
+                write!(f, "Could not reach {}: {}", url, message)
+            }
+            Error::Tls { url, message } => {
+                write!(f, "TLS connection to {} failed: {}", url, message)
+            }
+            Error::NotJira { url } => write!(f, "{} is not a Jira instance", url),
+            Error::Unauthorized(body) => write!(f, "Invalid login or API token: {}", body),
+            Error::Forbidden(body) => write!(f, "Forbidden: {}", body),
+            Error::NotFound(body) => write!(f, "Not found: {}", body),
+            Error::RateLimited {
+                retry_after: Some(retry_after),
+                body,
+            } => write!(
+                f,
+                "Rate limited, retry after {}s: {}",
+                retry_after.as_secs(),
+                body
+            ),
+            Error::RateLimited {
+                retry_after: None,
+                body,
+            } => write!(f, "Rate limited: {}", body),
+            Error::Validation { body, .. } => write!(f, "Invalid request: {}", body),
+            Error::Server { status, body } => write!(f, "Jira server error {}: {}", status, body),
+            Error::Status { status, body } => write!(f, "Jira error {}: {}", status, body),
+            Error::Transport(e) => write!(f, "Transport error: {}", e),
+            Error::Decode {
+                status,
+                body_snippet,
+                path,
+                issue_key,
+                source,
+            } => {
+                write!(f, "Could not decode response (status {})", status)?;
+                if let Some(issue_key) = issue_key {
+                    write!(f, " of issue {}", issue_key)?;
+                }
+                if let Some(path) = path {
+                    write!(f, " at {}", path)?;
+                }
+                write!(f, ": {}, body: {}", source, body_snippet)
+            }
+            Error::Conflict { current } => write!(
+                f,
+                "Issue {} was changed by someone else, it was last updated at {}",
+                current.key, current.fields.updated
+            ),
+            Error::PolicyViolation(violation) => write!(f, "Blocked by policy: {}", violation),
+            Error::Vetoed { operation, reason } => write!(f, "Can't {}: {}", operation, reason),
+            Error::InvalidId { kind, value } => write!(f, "{:?} isn't a valid {}", value, kind),
+            Error::InvalidDuration { value } => {
+                write!(f, "{:?} isn't a duration like 1w 2d 3h 30m", value)
+            }
+            Error::InvalidDate { value } => write!(f, "{:?} isn't a valid date", value),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Transport(e) => Some(e.as_ref()),
+            Error::Decode { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_error(status: u16, headers: &str, body: &str) -> Error {
+        let response = format!(
+            "HTTP/1.1 {} Status\r\n{}Content-Length: {}\r\n\r\n{}",
+            status,
+            headers,
+            body.len(),
+            body
+        );
+        Error::from_status(status, response.parse::<ureq::Response>().unwrap())
+    }
+
+    #[test]
+    fn tls_message() {
+        assert!(is_tls_message("invalid peer certificate: UnknownIssuer"));
+        assert!(is_tls_message("TLS handshake eof"));
+        assert!(!is_tls_message("Connection refused (os error 111)"));
+    }
+
+    #[test]
+    fn validation_field_errors() {
+        let error = status_error(
+            400,
+            "",
+            r#"{"errorMessages":[],"errors":{"summary":"Summary is required."}}"#,
+        );
+
+        match error {
+            Error::Validation { field_errors, body } => {
+                assert_eq!(field_errors["summary"], "Summary is required.");
+                assert!(body.errors.is_some());
+            }
+            other => panic!("Expected validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rate_limited_retry_after() {
+        let error = status_error(429, "Retry-After: 30\r\n", "");
+
+        assert!(error.is_retryable());
+        assert!(matches!(
+            error,
+            Error::RateLimited {
+                retry_after: Some(d),
+                ..
+            } if d == Duration::from_secs(30)
+        ));
+    }
+
+    #[test]
+    fn server_error_keeps_raw_body() {
+        let error = status_error(502, "", "<html>Bad Gateway</html>");
+
+        assert!(error.is_retryable());
+        assert_eq!(error.status(), Some(502));
+
+        let body = error.body().unwrap();
+        assert_eq!(body.raw, "<html>Bad Gateway</html>");
+        assert!(body.errors.is_none());
+    }
+
+    #[test]
+    fn not_found_is_not_retryable() {
+        let error = status_error(
+            404,
+            "",
+            r#"{"errorMessages":["Issue does not exist"],"errors":{}}"#,
+        );
+
+        assert!(!error.is_retryable());
+        assert_eq!(error.to_string(), "Not found: Issue does not exist");
+    }
+}
@@ -0,0 +1,189 @@
+//! Finding issue keys in free text, like branch names, commit messages and
+//! code comments.
+//!
+//! Anything shaped like `ABC-123` is a candidate, as long as it stands on its
+//! own: `xABC-1` and `ABC-1a` aren't keys. Names which only look like keys,
+//! like `UTF-8`, `ISO-8601` or `GPL-3.0`, are left out. Knowing the projects
+//! there are makes the search exact, only their keys are found then.
+
+use std::{collections::HashSet, ops::Range};
+
+use crate::jira::{IssueKey, ProjectKey};
+
+/// Prefixes of standards, encodings and the like, which are followed by a
+/// number often enough to be mistaken for issue keys.
+const LOOK_ALIKES: &[&str] = &[
+    "AES", "AGPL", "CVE", "CWE", "ECMA", "GPL", "IEC", "IEEE", "ISO", "LGPL", "MD", "RFC", "SHA",
+    "UCS", "UTF",
+];
+
+/// An issue key found in a text, and the bytes it takes up in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueKeyMatch {
+    pub key: IssueKey,
+    pub span: Range<usize>,
+}
+
+/// Finds issue keys in text, of any project or only of known ones.
+#[derive(Debug, Clone, Default)]
+pub struct IssueKeyFinder {
+    projects: Option<HashSet<ProjectKey>>,
+}
+
+impl IssueKeyFinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only find keys of these projects. A project of these is trusted even
+    /// if its key looks like `UTF` or `ISO`.
+    pub fn with_projects(projects: impl IntoIterator<Item = ProjectKey>) -> Self {
+        IssueKeyFinder {
+            projects: Some(projects.into_iter().collect()),
+        }
+    }
+
+    /// The keys in `text`, in the order they appear.
+    pub fn find(&self, text: &str) -> Vec<IssueKeyMatch> {
+        let bytes = text.as_bytes();
+        let mut matches = Vec::new();
+        let mut start = 0;
+
+        while start < bytes.len() {
+            match self.match_at(text, start) {
+                Some(found) => {
+                    start = found.span.end;
+                    matches.push(found);
+                }
+                None => start += 1,
+            }
+        }
+
+        matches
+    }
+
+    fn match_at(&self, text: &str, start: usize) -> Option<IssueKeyMatch> {
+        let bytes = text.as_bytes();
+
+        if !bytes[start].is_ascii_uppercase() || !is_boundary(text[..start].chars().next_back()) {
+            return None;
+        }
+
+        let dash = start
+            + bytes[start..]
+                .iter()
+                .take_while(|&&b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_')
+                .count();
+        if bytes.get(dash) != Some(&b'-') {
+            return None;
+        }
+
+        let end = dash
+            + 1
+            + bytes[dash + 1..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+        // numbers start at 1, `ABC-0` or `ABC-01` are something else
+        if end == dash + 1 || bytes[dash + 1] == b'0' {
+            return None;
+        }
+
+        // `ABC-1a`, and versions like `ABC-1.2` or `ISO-8859-1`
+        let rest = &text[end..];
+        let mut after = rest.chars();
+        match after.next() {
+            Some('-' | '.') if matches!(after.next(), Some(c) if c.is_ascii_digit()) => {
+                return None
+            }
+            next if !is_boundary(next) => return None,
+            _ => {}
+        }
+
+        let project = &text[start..dash];
+        let known = match self.projects {
+            Some(ref projects) => projects.contains(project),
+            None => project.parse::<ProjectKey>().is_ok() && !LOOK_ALIKES.contains(&project),
+        };
+        if !known {
+            return None;
+        }
+
+        text[start..end].parse().ok().map(|key| IssueKeyMatch {
+            key,
+            span: start..end,
+        })
+    }
+}
+
+/// Whether a key may start after, or end before, this character.
+fn is_boundary(c: Option<char>) -> bool {
+    match c {
+        Some(c) => !c.is_alphanumeric() && c != '_',
+        None => true,
+    }
+}
+
+/// Finds the keys of any project in `text`, see [`IssueKeyFinder`].
+pub fn find_issue_keys(text: &str) -> Vec<IssueKeyMatch> {
+    IssueKeyFinder::new().find(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(finder: &IssueKeyFinder, text: &str) -> Vec<String> {
+        finder
+            .find(text)
+            .into_iter()
+            .map(|found| found.key.into())
+            .collect()
+    }
+
+    #[test]
+    fn keys_are_found_with_their_spans() {
+        let text = "feature/ABC-12-fix: fixes ABC-12 and (XY_2-3).";
+        let found = find_issue_keys(text);
+
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].key, "ABC-12");
+        assert_eq!(&text[found[0].span.clone()], "ABC-12");
+        assert_eq!(found[1].span, 26..32);
+        assert_eq!(&text[found[2].span.clone()], "XY_2-3");
+    }
+
+    #[test]
+    fn look_alikes_are_skipped() {
+        let finder = IssueKeyFinder::new();
+
+        for text in [
+            "UTF-8",
+            "ISO-8601 dates",
+            "ISO-8859-1",
+            "GPL-3.0",
+            "SHA-256",
+            "xABC-1",
+            "ABC-1a",
+            "ABC-0",
+            "ABC-012",
+            "abc-1",
+            "A-1",
+            "ÄBC-1",
+            "BÄBC-1",
+        ] {
+            assert!(keys(&finder, text).is_empty(), "{}", text);
+        }
+
+        // after a dash, or next to text that isn't ASCII
+        assert_eq!(keys(&finder, "MY-PROJ-3"), ["PROJ-3"]);
+        assert_eq!(keys(&finder, "→ABC-1€"), ["ABC-1"]);
+    }
+
+    #[test]
+    fn known_projects_restrict_the_keys() {
+        let finder = IssueKeyFinder::with_projects(["ABC", "UTF"].map(|key| key.parse().unwrap()));
+
+        assert_eq!(keys(&finder, "ABC-1, DEF-2 and UTF-8"), ["ABC-1", "UTF-8"]);
+    }
+}
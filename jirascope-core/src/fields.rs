@@ -0,0 +1,152 @@
+//! Which parts of an issue to fetch.
+//!
+//! Issues can be large, mostly because of their descriptions and custom
+//! fields. Asking only for what's needed keeps searches over big projects
+//! fast.
+
+/// An issue field, by its id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IssueField {
+    Summary,
+    Description,
+    Priority,
+    Status,
+    Assignee,
+    Reporter,
+    Created,
+    Updated,
+    Project,
+    Parent,
+    IssueType,
+    DueDate,
+    ResolutionDate,
+    TimeTracking,
+    /// Any other field, e.g. `customfield_10010`.
+    Other(String),
+}
+
+impl IssueField {
+    pub fn id(&self) -> &str {
+        match self {
+            IssueField::Summary => "summary",
+            IssueField::Description => "description",
+            IssueField::Priority => "priority",
+            IssueField::Status => "status",
+            IssueField::Assignee => "assignee",
+            IssueField::Reporter => "reporter",
+            IssueField::Created => "created",
+            IssueField::Updated => "updated",
+            IssueField::Project => "project",
+            IssueField::Parent => "parent",
+            IssueField::IssueType => "issuetype",
+            IssueField::DueDate => "duedate",
+            IssueField::ResolutionDate => "resolutiondate",
+            IssueField::TimeTracking => "timetracking",
+            IssueField::Other(id) => id,
+        }
+    }
+}
+
+/// The fields to fetch with each issue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldSet {
+    /// Every field, the default of `issue/{id}`.
+    All,
+    /// Fields shown in the issue navigator, the default of searches.
+    Navigable,
+    /// Only the listed fields.
+    Only(Vec<IssueField>),
+}
+
+impl FieldSet {
+    /// What the issue tree needs, which leaves out descriptions. Includes
+    /// every field [`crate::jira::Issue`] can't be decoded without.
+    pub fn lean() -> FieldSet {
+        FieldSet::Only(vec![
+            IssueField::Summary,
+            IssueField::Status,
+            IssueField::Project,
+            IssueField::Parent,
+            IssueField::Created,
+            IssueField::Updated,
+        ])
+    }
+
+    pub(crate) fn to_query(&self) -> String {
+        match self {
+            FieldSet::All => "*all".to_string(),
+            FieldSet::Navigable => "*navigable".to_string(),
+            FieldSet::Only(fields) => fields
+                .iter()
+                .map(IssueField::id)
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+/// Extra information to include with each issue.
+///
+/// With searches, `names` and `schema` describe the whole page rather than
+/// each issue, and don't end up in the returned issues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Expand {
+    /// Fields rendered as HTML, in [`crate::jira::Issue::rendered_fields`].
+    RenderedFields,
+    /// Display names of the fields.
+    Names,
+    /// Types of the fields.
+    Schema,
+    /// Transitions which can be made from the current status.
+    Transitions,
+    /// History of changes.
+    Changelog,
+    /// What can be edited, like [`crate::Jirascope::get_issue_edit_meta`].
+    EditMeta,
+}
+
+impl Expand {
+    pub fn id(&self) -> &'static str {
+        match self {
+            Expand::RenderedFields => "renderedFields",
+            Expand::Names => "names",
+            Expand::Schema => "schema",
+            Expand::Transitions => "transitions",
+            Expand::Changelog => "changelog",
+            Expand::EditMeta => "editmeta",
+        }
+    }
+}
+
+/// Query parameters selecting `fields` and `expand`, without the leading
+/// separator.
+pub(crate) fn query(fields: &FieldSet, expand: &[Expand]) -> String {
+    let mut query = format!("fields={}", crate::utils::url_encode(&fields.to_query()));
+
+    if !expand.is_empty() {
+        let expand = expand.iter().map(Expand::id).collect::<Vec<_>>().join(",");
+        query.push_str(&format!("&expand={}", crate::utils::url_encode(&expand)));
+    }
+
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_lists_fields_and_expand() {
+        assert_eq!(query(&FieldSet::Navigable, &[]), "fields=%2Anavigable");
+        assert_eq!(
+            query(
+                &FieldSet::Only(vec![
+                    IssueField::Summary,
+                    IssueField::Other("customfield_10010".to_string())
+                ]),
+                &[Expand::RenderedFields, Expand::Changelog]
+            ),
+            "fields=summary%2Ccustomfield_10010&expand=renderedFields%2Cchangelog"
+        );
+    }
+}
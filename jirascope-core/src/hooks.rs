@@ -0,0 +1,87 @@
+//! Hooks around changes.
+//!
+//! A [`Hook`] sees every change the client makes. The `before_*` methods run
+//! before the request is built and can change it, e.g. to add a default
+//! label, or veto it, e.g. to require a component on bugs. The `after_*`
+//! methods run once Jira accepted the change, for side effects.
+//!
+//! Hooks run in the order they were added with [`crate::Jirascope::add_hook`],
+//! on the thread making the change. The policy checks the change as the hooks
+//! left it. After hooks don't run in dry-run mode, as nothing was changed.
+
+use std::fmt::{Display, Formatter};
+
+use crate::{
+    jira::{
+        AtlassianDoc, Comment, CreatedIssue, IssueCreation, IssueEdit, IssueIdOrKey,
+        IssueTransitionDescriptor,
+    },
+    Operation,
+};
+
+/// Callbacks around changes to issues. Every method does nothing by default.
+///
+/// `issue_id` is the issue key or id, as given to the client.
+#[allow(unused_variables)]
+pub trait Hook: Send + Sync {
+    fn before_create(&self, issue: &mut IssueCreation) -> Result<(), Veto> {
+        Ok(())
+    }
+
+    fn after_create(&self, issue: &IssueCreation, created: &CreatedIssue) {}
+
+    fn before_edit(&self, issue_id: &IssueIdOrKey, edit: &mut IssueEdit) -> Result<(), Veto> {
+        Ok(())
+    }
+
+    fn after_edit(&self, issue_id: &IssueIdOrKey, edit: &IssueEdit) {}
+
+    fn before_transition(
+        &self,
+        issue_id: &IssueIdOrKey,
+        transition: &mut IssueTransitionDescriptor,
+    ) -> Result<(), Veto> {
+        Ok(())
+    }
+
+    fn after_transition(&self, issue_id: &IssueIdOrKey, transition: &IssueTransitionDescriptor) {}
+
+    fn before_comment(&self, issue_id: &IssueIdOrKey, body: &mut AtlassianDoc) -> Result<(), Veto> {
+        Ok(())
+    }
+
+    fn after_comment(&self, issue_id: &IssueIdOrKey, comment: &Comment) {}
+
+    fn before_delete(&self, issue_id: &IssueIdOrKey) -> Result<(), Veto> {
+        Ok(())
+    }
+
+    fn after_delete(&self, issue_id: &IssueIdOrKey) {}
+}
+
+/// A hook's refusal of a change, turned into [`crate::Error::Vetoed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Veto {
+    pub reason: String,
+}
+
+impl Veto {
+    pub fn new(reason: impl Into<String>) -> Veto {
+        Veto {
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn into_error(self, operation: Operation) -> crate::Error {
+        crate::Error::Vetoed {
+            operation,
+            reason: self.reason,
+        }
+    }
+}
+
+impl Display for Veto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
@@ -0,0 +1,225 @@
+//! Timestamps and dates of issues.
+//!
+//! Jira sends timestamps like `2023-05-01T10:00:00.000+0000`, with an offset
+//! lacking the colon RFC 3339 wants, and dates like `2023-05-10`. Both are
+//! sent back the way they came, and compare by the instant or day they stand
+//! for rather than by their text.
+//!
+//! ```
+//! use jirascope_core::jira::JiraDateTime;
+//!
+//! let utc: JiraDateTime = "2023-05-01T10:00:00.000+0000".parse().unwrap();
+//! let cest: JiraDateTime = "2023-05-01T12:00:00.000+0200".parse().unwrap();
+//! assert_eq!(utc, cest);
+//! assert_eq!(cest.to_string(), "2023-05-01T12:00:00.000+0200");
+//! ```
+
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%z";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A timestamp, like when an issue was created, updated or resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JiraDateTime(pub DateTime<FixedOffset>);
+
+impl JiraDateTime {
+    /// How long ago this was, like `3 days ago`.
+    pub fn relative(&self) -> String {
+        self.relative_to(Utc::now())
+    }
+
+    pub fn relative_to(&self, now: DateTime<Utc>) -> String {
+        let seconds = now.signed_duration_since(self.0).num_seconds();
+
+        let (count, unit) = match seconds.unsigned_abs() {
+            s if s < 60 => return "just now".to_string(),
+            s if s < 3600 => (s / 60, "minute"),
+            s if s < 86400 => (s / 3600, "hour"),
+            s if s < 30 * 86400 => (s / 86400, "day"),
+            s if s < 365 * 86400 => (s / (30 * 86400), "month"),
+            s => (s / (365 * 86400), "year"),
+        };
+
+        relative(count, unit, seconds > 0)
+    }
+}
+
+impl FromStr for JiraDateTime {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Jira's own format, then RFC 3339 for `Z` and `+00:00`
+        DateTime::parse_from_str(s, DATE_TIME_FORMAT)
+            .or_else(|_| DateTime::parse_from_rfc3339(s))
+            .map(JiraDateTime)
+            .map_err(|_| crate::Error::InvalidDate {
+                value: s.to_string(),
+            })
+    }
+}
+
+impl Display for JiraDateTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.format(DATE_TIME_FORMAT))
+    }
+}
+
+impl From<DateTime<FixedOffset>> for JiraDateTime {
+    fn from(date_time: DateTime<FixedOffset>) -> Self {
+        JiraDateTime(date_time)
+    }
+}
+
+impl Serialize for JiraDateTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for JiraDateTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A day without a time, like the day an issue is due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JiraDate(pub NaiveDate);
+
+impl JiraDate {
+    /// How far this day is from today, like `tomorrow` or `in 3 days`.
+    pub fn relative(&self) -> String {
+        self.relative_to(Local::now().date_naive())
+    }
+
+    pub fn relative_to(&self, today: NaiveDate) -> String {
+        let days = today.signed_duration_since(self.0).num_days();
+
+        match days {
+            0 => "today".to_string(),
+            1 => "yesterday".to_string(),
+            -1 => "tomorrow".to_string(),
+            _ => relative(days.unsigned_abs(), "day", days > 0),
+        }
+    }
+}
+
+impl FromStr for JiraDate {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NaiveDate::parse_from_str(s, DATE_FORMAT)
+            .map(JiraDate)
+            .map_err(|_| crate::Error::InvalidDate {
+                value: s.to_string(),
+            })
+    }
+}
+
+impl Display for JiraDate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.format(DATE_FORMAT))
+    }
+}
+
+impl From<NaiveDate> for JiraDate {
+    fn from(date: NaiveDate) -> Self {
+        JiraDate(date)
+    }
+}
+
+impl Serialize for JiraDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for JiraDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn relative(count: u64, unit: &str, past: bool) -> String {
+    let plural = if count == 1 { "" } else { "s" };
+
+    if past {
+        format!("{} {}{} ago", count, unit, plural)
+    } else {
+        format!("in {} {}{}", count, unit, plural)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ureq::serde_json::{self, json};
+
+    use super::*;
+
+    fn date_time(s: &str) -> JiraDateTime {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn jira_formats_are_parsed() {
+        let jira = date_time("2023-05-01T10:00:00.000+0000");
+        assert_eq!(date_time("2023-05-01T10:00:00Z"), jira);
+        assert_eq!(date_time("2023-05-01T05:30:00.000-04:30"), jira);
+        assert!("2023-05-01".parse::<JiraDateTime>().is_err());
+        assert!("".parse::<JiraDateTime>().is_err());
+
+        let due: JiraDate = serde_json::from_value(json!("2023-05-10")).unwrap();
+        assert_eq!(serde_json::to_value(due).unwrap(), json!("2023-05-10"));
+        assert!("10.05.2023".parse::<JiraDate>().is_err());
+    }
+
+    #[test]
+    fn offsets_survive_a_round_trip() {
+        let value = json!("2023-05-01T12:00:00.000+0200");
+        let updated: JiraDateTime = serde_json::from_value(value.clone()).unwrap();
+
+        assert_eq!(serde_json::to_value(updated).unwrap(), value);
+    }
+
+    #[test]
+    fn relative_rendering() {
+        let now = date_time("2023-05-04T10:00:00.000+0000")
+            .0
+            .with_timezone(&Utc);
+
+        for (then, expected) in [
+            ("2023-05-04T09:59:30.000+0000", "just now"),
+            ("2023-05-04T09:59:00.000+0000", "1 minute ago"),
+            ("2023-05-04T07:00:00.000+0000", "3 hours ago"),
+            ("2023-05-01T10:00:00.000+0000", "3 days ago"),
+            ("2021-05-01T10:00:00.000+0000", "2 years ago"),
+            ("2023-05-04T12:00:00.000+0000", "in 2 hours"),
+        ] {
+            assert_eq!(date_time(then).relative_to(now), expected);
+        }
+
+        let today = NaiveDate::from_ymd_opt(2023, 5, 4).unwrap();
+        for (due, expected) in [
+            ("2023-05-04", "today"),
+            ("2023-05-05", "tomorrow"),
+            ("2023-05-03", "yesterday"),
+            ("2023-05-10", "in 6 days"),
+        ] {
+            assert_eq!(
+                due.parse::<JiraDate>().unwrap().relative_to(today),
+                expected
+            );
+        }
+    }
+}
@@ -10,8 +10,9 @@ use serde::{Deserialize, Serialize};
 /// From: https://developer.atlassian.com/cloud/jira/platform/rest/v3/intro/#status-codes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorCollection {
-    #[serde(rename = "errorMessages")]
+    #[serde(rename = "errorMessages", default)]
     pub error_messages: Vec<String>,
+    #[serde(default)]
     pub errors: HashMap<String, String>,
 }
 
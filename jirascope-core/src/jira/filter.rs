@@ -0,0 +1,189 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use ureq::serde_json::{Map, Value};
+
+use super::{AccountId, User};
+
+/// A saved search.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Filter {
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub owner: Option<User>,
+    #[serde(default)]
+    pub jql: String,
+    #[serde(default)]
+    pub favourite: bool,
+    #[serde(default)]
+    pub share_permissions: Vec<SharePermission>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Who a filter is shared with.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SharePermission {
+    #[serde(default)]
+    pub id: Option<i64>,
+    /// `global`, `loggedin`, `project`, `projectRole`, `group` or `user`.
+    #[serde(rename = "type")]
+    pub share_type: String,
+    /// The project, role, group or user shared with.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Display for SharePermission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = |key: &str, field: &str| {
+            self.extra
+                .get(key)
+                .and_then(|v| v[field].as_str())
+                .unwrap_or("?")
+                .to_string()
+        };
+
+        match self.share_type.as_str() {
+            "global" => write!(f, "everyone"),
+            "loggedin" | "authenticated" => write!(f, "logged in users"),
+            "project" => write!(f, "project {}", name("project", "key")),
+            "projectRole" => write!(
+                f,
+                "{} of project {}",
+                name("role", "name"),
+                name("project", "key")
+            ),
+            "group" => write!(f, "group {}", name("group", "name")),
+            "user" => write!(f, "{}", name("user", "displayName")),
+            other => write!(f, "{}", other),
+        }
+    }
+}
+
+/// What a filter is created or edited with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterDetails {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jql: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favourite: Option<bool>,
+}
+
+/// Someone to share a filter with.
+///
+/// Parses from `global`, `authenticated`, `project:<project id>`,
+/// `role:<project id>:<role id>`, `group:<group name>` or
+/// `user:<account id>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type")]
+pub enum FilterShare {
+    #[serde(rename = "global")]
+    Global,
+    #[serde(rename = "authenticated")]
+    LoggedIn,
+    #[serde(rename = "project")]
+    Project {
+        #[serde(rename = "projectId")]
+        project_id: String,
+    },
+    #[serde(rename = "projectRole")]
+    ProjectRole {
+        #[serde(rename = "projectId")]
+        project_id: String,
+        #[serde(rename = "projectRoleId")]
+        role_id: String,
+    },
+    #[serde(rename = "group")]
+    Group {
+        #[serde(rename = "groupname")]
+        group_name: String,
+    },
+    #[serde(rename = "user")]
+    User {
+        #[serde(rename = "accountId")]
+        account_id: AccountId,
+    },
+}
+
+impl FromStr for FilterShare {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.splitn(3, ':').collect::<Vec<_>>();
+
+        let share = match parts.as_slice() {
+            ["global"] => FilterShare::Global,
+            ["authenticated"] | ["loggedin"] => FilterShare::LoggedIn,
+            ["project", project_id] => FilterShare::Project {
+                project_id: project_id.to_string(),
+            },
+            ["role", project_id, role_id] => FilterShare::ProjectRole {
+                project_id: project_id.to_string(),
+                role_id: role_id.to_string(),
+            },
+            ["group", group_name] => FilterShare::Group {
+                group_name: group_name.to_string(),
+            },
+            ["user", account_id] => FilterShare::User {
+                account_id: account_id.parse()?,
+            },
+            _ => {
+                return Err(crate::Error::jirascope(format!(
+                    "Can't share with {}, expected global, authenticated, project:<id>, role:<project id>:<role id>, group:<name> or user:<account id>",
+                    s
+                )))
+            }
+        };
+
+        Ok(share)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ureq::serde_json::{self, json};
+
+    use super::*;
+
+    #[test]
+    fn shares_parse_into_request_bodies() {
+        let share: FilterShare = "role:10000:10002".parse().unwrap();
+        assert_eq!(
+            serde_json::to_value(share).unwrap(),
+            json!({ "type": "projectRole", "projectId": "10000", "projectRoleId": "10002" })
+        );
+        assert_eq!(
+            "global".parse::<FilterShare>().unwrap(),
+            FilterShare::Global
+        );
+        assert!("project".parse::<FilterShare>().is_err());
+    }
+
+    #[test]
+    fn share_permissions_are_described() {
+        let permission: SharePermission = serde_json::from_value(json!({
+            "id": 10010,
+            "type": "project",
+            "project": { "id": "10000", "key": "ABC", "name": "Alphabet" }
+        }))
+        .unwrap();
+
+        assert_eq!(permission.to_string(), "project ABC");
+    }
+}
@@ -0,0 +1,404 @@
+//! Identifiers of issues, projects, users, statuses and transitions.
+//!
+//! Keys and account ids are strings, but not just any string. Parsing one
+//! checks its shape, so a typo fails right there rather than as a request to
+//! the wrong URL, and passing a summary where a key belongs doesn't compile.
+//! What the server sends isn't checked, since instances can be configured
+//! with other key formats, and legacy keys keep their old shape.
+//!
+//! ```
+//! use jirascope_core::jira::{IssueKey, ProjectKey};
+//!
+//! let key: IssueKey = "ABC-123".parse().unwrap();
+//! assert_eq!(key.project(), "ABC".parse::<ProjectKey>().unwrap());
+//! assert_eq!(key.number(), 123);
+//! assert!("ABC 123".parse::<IssueKey>().is_err());
+//! ```
+
+use std::{
+    borrow::Borrow,
+    fmt::{Display, Formatter},
+    ops::Deref,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Validated string identifiers share everything but their check.
+macro_rules! string_id {
+    ($(#[$attr:meta])* $name:ident, $kind:literal, $valid:expr) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = crate::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let valid: fn(&str) -> bool = $valid;
+
+                if valid(s) {
+                    Ok($name(s.to_string()))
+                } else {
+                    Err(crate::Error::InvalidId {
+                        kind: $kind,
+                        value: s.to_string(),
+                    })
+                }
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> String {
+                id.0
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        // unchecked, see the module docs
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer).map($name)
+            }
+        }
+    };
+}
+
+/// Numeric identifiers, which Jira sends as strings.
+macro_rules! numeric_id {
+    ($(#[$attr:meta])* $name:ident, $kind:literal) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(pub i64);
+
+        impl FromStr for $name {
+            type Err = crate::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.parse().map($name).map_err(|_| crate::Error::InvalidId {
+                    kind: $kind,
+                    value: s.to_string(),
+                })
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl From<i64> for $name {
+            fn from(id: i64) -> Self {
+                $name(id)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                crate::utils::serialize_id(&self.0, serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                crate::utils::deserialize_id(deserializer).map($name)
+            }
+        }
+    };
+}
+
+string_id!(
+    /// A project key like `ABC`: an uppercase letter followed by uppercase
+    /// letters, digits or underscores.
+    ProjectKey,
+    "project key",
+    |s| {
+        let mut chars = s.chars();
+        matches!(chars.next(), Some(c) if c.is_ascii_uppercase())
+            && s.len() > 1
+            && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+    }
+);
+
+string_id!(
+    /// An issue key like `ABC-123`, the key of its project and its number.
+    IssueKey,
+    "issue key",
+    |s| match s.rsplit_once('-') {
+        Some((project, number)) => {
+            project.parse::<ProjectKey>().is_ok()
+                && !number.is_empty()
+                && number.bytes().all(|b| b.is_ascii_digit())
+                && number.parse::<u64>().is_ok()
+        }
+        None => false,
+    }
+);
+
+string_id!(
+    /// The id of an Atlassian account, e.g. `5b10a2844c20165700ede21g`.
+    /// Opaque, but never empty, longer than 128 characters or containing
+    /// whitespace.
+    AccountId,
+    "account id",
+    |s| !s.is_empty() && s.len() <= 128 && !s.chars().any(|c| c.is_whitespace() || c.is_control())
+);
+
+numeric_id!(StatusId, "status id");
+numeric_id!(TransitionId, "transition id");
+
+impl IssueKey {
+    pub fn new(project: &ProjectKey, number: u64) -> IssueKey {
+        IssueKey(format!("{}-{}", project, number))
+    }
+
+    pub fn project(&self) -> ProjectKey {
+        ProjectKey(self.project_str().to_string())
+    }
+
+    pub fn number(&self) -> u64 {
+        // 0 for a key of another format
+        self.0
+            .rsplit_once('-')
+            .and_then(|(_, number)| number.parse().ok())
+            .unwrap_or_default()
+    }
+
+    fn project_str(&self) -> &str {
+        self.0
+            .rsplit_once('-')
+            .map(|(project, _)| project)
+            .unwrap_or_default()
+    }
+}
+
+/// An issue given by id or by key, as most of the API accepts either.
+///
+/// Parses from digits as an id, and from anything else as a key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IssueIdOrKey {
+    Id(i64),
+    Key(IssueKey),
+}
+
+impl IssueIdOrKey {
+    pub fn key(&self) -> Option<&IssueKey> {
+        match self {
+            IssueIdOrKey::Key(key) => Some(key),
+            IssueIdOrKey::Id(_) => None,
+        }
+    }
+}
+
+impl FromStr for IssueIdOrKey {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<i64>() {
+            Ok(id) => Ok(IssueIdOrKey::Id(id)),
+            Err(_) => s.parse().map(IssueIdOrKey::Key),
+        }
+    }
+}
+
+impl Display for IssueIdOrKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IssueIdOrKey::Id(id) => write!(f, "{}", id),
+            IssueIdOrKey::Key(key) => write!(f, "{}", key),
+        }
+    }
+}
+
+impl From<IssueKey> for IssueIdOrKey {
+    fn from(key: IssueKey) -> Self {
+        IssueIdOrKey::Key(key)
+    }
+}
+
+impl From<&IssueKey> for IssueIdOrKey {
+    fn from(key: &IssueKey) -> Self {
+        IssueIdOrKey::Key(key.clone())
+    }
+}
+
+impl From<&IssueIdOrKey> for IssueIdOrKey {
+    fn from(issue: &IssueIdOrKey) -> Self {
+        issue.clone()
+    }
+}
+
+/// A project given by id or by key.
+///
+/// Parses from digits as an id, and from anything else as a key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProjectIdOrKey {
+    Id(i64),
+    Key(ProjectKey),
+}
+
+impl FromStr for ProjectIdOrKey {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<i64>() {
+            Ok(id) => Ok(ProjectIdOrKey::Id(id)),
+            Err(_) => s.parse().map(ProjectIdOrKey::Key),
+        }
+    }
+}
+
+impl Display for ProjectIdOrKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectIdOrKey::Id(id) => write!(f, "{}", id),
+            ProjectIdOrKey::Key(key) => write!(f, "{}", key),
+        }
+    }
+}
+
+impl From<ProjectKey> for ProjectIdOrKey {
+    fn from(key: ProjectKey) -> Self {
+        ProjectIdOrKey::Key(key)
+    }
+}
+
+impl From<&ProjectKey> for ProjectIdOrKey {
+    fn from(key: &ProjectKey) -> Self {
+        ProjectIdOrKey::Key(key.clone())
+    }
+}
+
+impl From<&ProjectIdOrKey> for ProjectIdOrKey {
+    fn from(project: &ProjectIdOrKey) -> Self {
+        project.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ureq::serde_json::{self, json};
+
+    use super::*;
+
+    #[test]
+    fn keys_are_validated() {
+        assert!("ABC".parse::<ProjectKey>().is_ok());
+        assert!("AB_2".parse::<ProjectKey>().is_ok());
+        for invalid in ["", "A", "abc", "1AB", "AB-C", "ÄBC"] {
+            assert!(invalid.parse::<ProjectKey>().is_err(), "{}", invalid);
+        }
+
+        assert!("ABC-1".parse::<IssueKey>().is_ok());
+        for invalid in [
+            "ABC",
+            "ABC-",
+            "abc-1",
+            "ABC-1a",
+            "ABC--1",
+            "ABC-+1",
+            "MY-PROJ-3",
+        ] {
+            assert!(invalid.parse::<IssueKey>().is_err(), "{}", invalid);
+        }
+
+        assert!("5b10a2844c20165700ede21g".parse::<AccountId>().is_ok());
+        assert!("qm:a713c8ea:5ad6d358".parse::<AccountId>().is_ok());
+        assert!("".parse::<AccountId>().is_err());
+        assert!("Test User".parse::<AccountId>().is_err());
+    }
+
+    #[test]
+    fn issue_keys_know_their_project() {
+        let key: IssueKey = "ABC_2-12".parse().unwrap();
+        assert_eq!(key.project(), "ABC_2");
+        assert_eq!(key.number(), 12);
+        assert_eq!(IssueKey::new(&key.project(), 13), "ABC_2-13");
+    }
+
+    #[test]
+    fn issues_are_given_by_id_or_key() {
+        assert_eq!(
+            "10001".parse::<IssueIdOrKey>().unwrap(),
+            IssueIdOrKey::Id(10001)
+        );
+        let issue: IssueIdOrKey = "ABC-1".parse().unwrap();
+        assert_eq!(issue.key().unwrap(), "ABC-1");
+        assert_eq!(issue.to_string(), "ABC-1");
+        assert!("abc".parse::<IssueIdOrKey>().is_err());
+    }
+
+    #[test]
+    fn ids_round_trip_through_serde() {
+        let key: IssueKey = serde_json::from_value(json!("ABC-1")).unwrap();
+        assert_eq!(serde_json::to_value(&key).unwrap(), json!("ABC-1"));
+
+        // whatever the server has is kept, even if it couldn't be parsed
+        let key: IssueKey = serde_json::from_value(json!("legacy_1")).unwrap();
+        assert_eq!(key, "legacy_1");
+        let project: ProjectKey = serde_json::from_value(json!("abc")).unwrap();
+        assert_eq!(project, "abc");
+
+        let status: StatusId = serde_json::from_value(json!("3")).unwrap();
+        assert_eq!(status, StatusId(3));
+        assert_eq!(serde_json::to_value(status).unwrap(), json!("3"));
+        let status: StatusId = serde_json::from_value(json!(3)).unwrap();
+        assert_eq!(status, StatusId(3));
+    }
+}
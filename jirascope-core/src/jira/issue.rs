@@ -1,36 +1,118 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
-use ureq::serde_json::Value;
+use ureq::serde_json::{Map, Value};
 
-use super::{AtlassianDoc, Project, User, WrappedId};
+use super::{
+    AtlassianDoc, IssueKey, JiraDate, JiraDateTime, Project, ProjectKey, StatusId, TimeTracking,
+    TimeTrackingEdit, TransitionId, User, WrappedId,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Issues {
     pub issues: Vec<Issue>,
 }
 
+/// A page of `search` results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults<T> {
+    pub start_at: usize,
+    pub max_results: usize,
+    pub total: usize,
+    pub issues: Vec<T>,
+}
+
+/// A page of `search/jql` results, linked by `nextPageToken` instead of
+/// offsets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JqlSearchResults<T> {
+    pub issues: Vec<T>,
+    #[serde(default)]
+    pub next_page_token: Option<String>,
+    #[serde(default)]
+    pub is_last: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApproximateCountQuery {
+    pub jql: String,
+}
+
+/// How many issues match a query. Recently changed issues may not be
+/// counted yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApproximateCount {
+    pub count: usize,
+}
+
+/// Just enough of an issue to know it exists.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct IssueRef {
+    #[serde(deserialize_with = "crate::utils::deserialize_id")]
+    pub id: i64,
+    pub key: IssueKey,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Issue {
-    #[serde(deserialize_with = "crate::utils::deserialize_id")]
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
     pub id: i64,
-    pub key: String,
+    pub key: IssueKey,
     pub fields: IssueFields,
+    // only there when asked for with `crate::Expand`
+    #[serde(
+        rename = "renderedFields",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rendered_fields: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub names: Option<HashMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transitions: Option<Vec<IssueTransitionDescriptor>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editmeta: Option<IssueEditMeta>,
+    /// Everything else the server sent, kept so nothing is lost when the
+    /// issue is serialized again.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct IssueFields {
     pub summary: String,
     pub description: Option<AtlassianDoc>,
-    pub priority: Priority,
+    // null when priorities are disabled, or the issue was created without one
+    pub priority: Option<Priority>,
     pub status: Status,
     pub assignee: Option<User>,
-    pub reporter: User,
-    pub created: String, // ISO 8601 date/time string
-    pub updated: String, // ISO 8601 date/time string
+    // null when the reporter was deleted or anonymized
+    pub reporter: Option<User>,
+    pub created: JiraDateTime,
+    pub updated: JiraDateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolutiondate: Option<JiraDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duedate: Option<JiraDate>,
     pub project: Project,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parent: Option<WrappedId>,
+    /// Estimates and time spent, missing when time tracking is disabled or
+    /// the field wasn't asked for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timetracking: Option<TimeTracking>,
+    /// Custom fields and any other field without a dedicated member.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,13 +134,16 @@ pub struct IssueCreationFields {
     pub assignee: Option<User>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parent: Option<WrappedId>,
+    /// Labels, components, custom fields and any other field to set.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreatedIssue {
     #[serde(deserialize_with = "crate::utils::deserialize_id")]
     pub id: i64,
-    pub key: String,
+    pub key: IssueKey,
     #[serde(rename = "self")]
     pub self_link: String,
 }
@@ -80,18 +165,26 @@ pub struct IssueEditFields {
     pub status: Option<Status>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub assignee: Option<Option<User>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timetracking: Option<TimeTrackingEdit>,
+    /// Labels, components, custom fields and any other field to set.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Priority {
     pub name: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Status {
-    #[serde(deserialize_with = "crate::utils::deserialize_id")]
-    pub id: i64,
+    pub id: StatusId,
     pub name: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,7 +203,7 @@ pub struct IssueCreationMeta {
 pub struct ProjectIssueCreationMeta {
     #[serde(deserialize_with = "crate::utils::deserialize_id")]
     pub id: i64,
-    pub key: String,
+    pub key: ProjectKey,
     pub name: String,
     #[serde(rename = "issuetypes")]
     pub issue_types: Vec<IssueType>,
@@ -126,12 +219,28 @@ pub struct IssueType {
     pub fields: Option<Value>,
 }
 
+/// A field which can be set when creating an issue of some type, from
+/// `issue/createmeta/{project}/issuetypes/{id}`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueCreationField {
+    pub field_id: String,
+    pub key: String,
+    pub name: String,
+    pub required: bool,
+    pub schema: MetaFieldSchema,
+    pub allowed_values: Option<Vec<Value>>,
+    pub operations: Option<Vec<String>>,
+    pub has_default_value: Option<bool>,
+    pub default_value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct IssueEditMeta {
     pub fields: HashMap<String, IssueEditMetaField>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct IssueEditMetaField {
     pub required: bool,
     pub name: String,
@@ -143,7 +252,7 @@ pub struct IssueEditMetaField {
     pub default_value: Option<Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct MetaFieldSchema {
     #[serde(rename = "type")]
     pub schema_type: String,
@@ -153,10 +262,9 @@ pub struct MetaFieldSchema {
     pub custom_id: Option<isize>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct IssueTransitionDescriptor {
-    #[serde(deserialize_with = "crate::utils::deserialize_id")]
-    pub id: i64,
+    pub id: TransitionId,
     pub name: String,
     pub to: Status,
 }
@@ -170,3 +278,61 @@ pub struct IssueTransitionDescriptors {
 pub struct IssueTransition {
     pub transition: IssueTransitionDescriptor,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentCreation {
+    pub body: AtlassianDoc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    #[serde(deserialize_with = "crate::utils::deserialize_id")]
+    pub id: i64,
+    pub author: Option<User>,
+    pub body: AtlassianDoc,
+    pub created: JiraDateTime,
+    pub updated: JiraDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use ureq::serde_json::{self, json};
+
+    use super::*;
+
+    #[test]
+    fn nulls_and_unknown_fields_survive_a_round_trip() {
+        let value = json!({
+            "id": "10001",
+            "key": "ABC-1",
+            "self": "https://example.atlassian.net/rest/api/3/issue/10001",
+            "fields": {
+                "summary": "Odd one",
+                "description": null,
+                "priority": null,
+                "status": {
+                    "id": "1",
+                    "name": "To Do",
+                    "statusCategory": { "key": "new" }
+                },
+                "assignee": null,
+                "reporter": null,
+                "created": "2023-05-01T10:00:00.000+0000",
+                "updated": "2023-05-02T10:00:00.000+0000",
+                "project": { "id": "10000", "key": "ABC", "name": "Alphabet" },
+                "customfield_10010": ["a", "b"]
+            }
+        });
+
+        let issue: Issue = serde_json::from_value(value.clone()).unwrap();
+
+        assert_eq!(issue.fields.priority, None);
+        assert_eq!(issue.fields.reporter, None);
+        assert_eq!(issue.fields.extra["customfield_10010"], json!(["a", "b"]));
+
+        let mut expected = value;
+        // not sent back when empty, which Jira treats like null
+        expected["fields"].as_object_mut().unwrap().remove("parent");
+        assert_eq!(serde_json::to_value(&issue).unwrap(), expected);
+    }
+}
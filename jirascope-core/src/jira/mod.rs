@@ -1,21 +1,38 @@
+mod date;
 mod doc;
 mod error;
+mod filter;
+mod ids;
 mod issue;
 mod project;
+mod server;
+mod service_desk;
+mod time_tracking;
 mod user;
+pub use date::*;
 pub use doc::*;
 pub use error::*;
+pub use filter::*;
+pub use ids::*;
 pub use issue::*;
 pub use project::*;
+pub use server::*;
+pub use service_desk::*;
+pub use time_tracking::*;
 pub use user::*;
 
 use serde::{Deserialize, Serialize};
+use ureq::serde_json::{Map, Value};
 
+/// A page of results from endpoints which report `isLast`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Paginated<T> {
     pub start_at: usize,
     pub max_results: usize,
-    pub total: usize,
+    /// Not every endpoint counts its results.
+    #[serde(default)]
+    pub total: Option<usize>,
     pub is_last: bool,
     pub values: Vec<T>,
 }
@@ -27,10 +44,16 @@ pub struct WrappedId {
         deserialize_with = "crate::utils::deserialize_id"
     )]
     pub id: i64,
+    /// E.g. the key and a few fields of a parent issue.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl WrappedId {
     pub fn new(id: i64) -> Self {
-        Self { id }
+        Self {
+            id,
+            extra: Map::new(),
+        }
     }
 }
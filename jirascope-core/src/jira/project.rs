@@ -1,16 +1,22 @@
 use serde::{Deserialize, Serialize};
+use ureq::serde_json::{Map, Value};
 
-use super::User;
+use super::{AccountId, ProjectKey, User};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Project {
     // id is sometimes a string, sometimes a number.
     // if it's a string, parse to i64,
     // if it's a number, parse to i64,
-    #[serde(deserialize_with = "crate::utils::deserialize_id")]
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
     pub id: i64,
-    pub key: String,
+    pub key: ProjectKey,
     pub name: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -20,7 +26,7 @@ pub struct ProjectDetailed {
     // if it's a number, parse to i64,
     #[serde(deserialize_with = "crate::utils::deserialize_id")]
     pub id: i64,
-    pub key: String,
+    pub key: ProjectKey,
     pub name: String,
     pub description: String,
     pub lead: User,
@@ -33,6 +39,7 @@ impl ProjectDetailed {
             id: self.id,
             key: self.key,
             name: self.name,
+            extra: Map::new(),
         }
     }
 }
@@ -40,7 +47,7 @@ impl ProjectDetailed {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectCreated {
     pub id: i64,
-    pub key: String,
+    pub key: ProjectKey,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -209,13 +216,13 @@ pub const PROJECT_TEMPLATES: [ProjectTemplate; 37] = [
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectCreate {
-    pub key: String,
+    pub key: ProjectKey,
     pub name: String,
     pub description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
     #[serde(rename = "leadAccountId")]
-    pub lead_account_id: String,
+    pub lead_account_id: AccountId,
     #[serde(rename = "categoryId")]
     pub category_id: Option<i64>,
     #[serde(rename = "projectTypeKey")]
@@ -254,7 +261,7 @@ pub enum ProjectCreateDetails {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ProjectEdit {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub key: Option<String>,
+    pub key: Option<ProjectKey>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -263,7 +270,7 @@ pub struct ProjectEdit {
     pub url: Option<Option<String>>,
     #[serde(rename = "leadAccountId")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub lead_account_id: Option<String>,
+    pub lead_account_id: Option<AccountId>,
     #[serde(rename = "categoryId")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub category_id: Option<Option<i64>>,
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// Schema for the `/serverInfo` response.
+///
+/// Only the fields present on every Jira deployment are required, which is
+/// what lets us tell a Jira instance apart from any other JSON-speaking host.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ServerInfo {
+    #[serde(rename = "baseUrl")]
+    pub base_url: String,
+    pub version: String,
+    #[serde(rename = "serverTitle")]
+    pub server_title: String,
+    #[serde(rename = "deploymentType")]
+    pub deployment_type: Option<String>,
+}
@@ -0,0 +1,386 @@
+//! Models of Jira Service Management, served under `/rest/servicedeskapi`.
+//!
+//! Unlike the platform API, ids are always strings here, and dates come as
+//! a [`ServiceDeskDate`] in several formats at once.
+
+use serde::{Deserialize, Serialize};
+use ureq::serde_json::{Map, Value};
+
+use super::{AccountId, IssueKey, ProjectKey};
+
+/// A page of results from the service desk API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceDeskPage<T> {
+    pub start: usize,
+    pub limit: usize,
+    pub is_last_page: bool,
+    pub values: Vec<T>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceDesk {
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub id: i64,
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub project_id: i64,
+    pub project_key: ProjectKey,
+    pub project_name: String,
+}
+
+/// What customers can ask a service desk for, e.g. "Get IT help".
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestType {
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub help_text: Option<String>,
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub issue_type_id: i64,
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub service_desk_id: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A customer or agent, as the service desk API describes them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceDeskUser {
+    pub account_id: AccountId,
+    pub display_name: String,
+    #[serde(default)]
+    pub email_address: Option<String>,
+    #[serde(default)]
+    pub active: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceDeskDate {
+    pub iso8601: String,
+    /// Formatted for people, e.g. "Today 10:30 AM".
+    pub friendly: String,
+    pub epoch_millis: i64,
+}
+
+/// A request raised by a customer, backed by an issue.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomerRequest {
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub issue_id: i64,
+    pub issue_key: IssueKey,
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub request_type_id: i64,
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub service_desk_id: i64,
+    pub created_date: ServiceDeskDate,
+    #[serde(default)]
+    pub reporter: Option<ServiceDeskUser>,
+    #[serde(default)]
+    pub request_field_values: Vec<RequestFieldValue>,
+    #[serde(default)]
+    pub current_status: Option<RequestStatus>,
+}
+
+impl CustomerRequest {
+    /// The value of the field `field_id`, e.g. `summary`.
+    pub fn field(&self, field_id: &str) -> Option<&Value> {
+        self.request_field_values
+            .iter()
+            .find(|f| f.field_id == field_id)
+            .map(|f| &f.value)
+    }
+
+    pub fn summary(&self) -> &str {
+        self.field("summary")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestFieldValue {
+    pub field_id: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestStatus {
+    pub status: String,
+    /// `NEW`, `INDETERMINATE` or `DONE`.
+    #[serde(default)]
+    pub status_category: String,
+    #[serde(default)]
+    pub status_date: Option<ServiceDeskDate>,
+}
+
+/// What a customer request is raised with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomerRequestCreation {
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub service_desk_id: i64,
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub request_type_id: i64,
+    /// Field ids to values, at least `summary` and whatever else the request
+    /// type requires.
+    pub request_field_values: Map<String, Value>,
+    /// The account id of the customer the request is raised for, if not the
+    /// current user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raise_on_behalf_of: Option<AccountId>,
+}
+
+impl CustomerRequestCreation {
+    pub fn new(service_desk_id: i64, request_type_id: i64, summary: &str) -> Self {
+        let mut request_field_values = Map::new();
+        request_field_values.insert("summary".to_string(), summary.into());
+
+        CustomerRequestCreation {
+            service_desk_id,
+            request_type_id,
+            request_field_values,
+            raise_on_behalf_of: None,
+        }
+    }
+}
+
+/// A comment on a customer request. Comments which aren't public are only
+/// seen by agents.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestComment {
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub id: i64,
+    /// Plain text, the service desk API doesn't use Atlassian documents.
+    pub body: String,
+    pub public: bool,
+    #[serde(default)]
+    pub author: Option<ServiceDeskUser>,
+    #[serde(default)]
+    pub created: Option<ServiceDeskDate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RequestCommentCreation {
+    pub body: String,
+    pub public: bool,
+}
+
+/// An agents' queue of requests, defined by JQL.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Queue {
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub jql: String,
+    /// The fields shown as columns, and returned for the queue's issues.
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// Only counted when asked for.
+    #[serde(default)]
+    pub issue_count: Option<usize>,
+}
+
+/// An issue in a queue. Only the fields of the queue's columns are there.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QueueIssue {
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub id: i64,
+    pub key: IssueKey,
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+}
+
+impl QueueIssue {
+    pub fn summary(&self) -> &str {
+        self.fields
+            .get("summary")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+
+    pub fn status(&self) -> &str {
+        self.fields
+            .get("status")
+            .and_then(|status| status["name"].as_str())
+            .unwrap_or_default()
+    }
+}
+
+/// How a request does against one of its service level agreements.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Sla {
+    #[serde(
+        serialize_with = "crate::utils::serialize_id",
+        deserialize_with = "crate::utils::deserialize_id"
+    )]
+    pub id: i64,
+    /// E.g. "Time to first response".
+    pub name: String,
+    #[serde(default)]
+    pub ongoing_cycle: Option<SlaCycle>,
+    #[serde(default)]
+    pub completed_cycles: Vec<SlaCycle>,
+}
+
+impl Sla {
+    /// Whether the ongoing cycle, or else the last completed one, is breached.
+    pub fn breached(&self) -> bool {
+        self.ongoing_cycle
+            .as_ref()
+            .or(self.completed_cycles.last())
+            .map(|cycle| cycle.breached)
+            .unwrap_or(false)
+    }
+}
+
+/// One stretch of time an SLA measures, e.g. until the first response.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SlaCycle {
+    pub start_time: ServiceDeskDate,
+    /// Only set on completed cycles.
+    #[serde(default)]
+    pub stop_time: Option<ServiceDeskDate>,
+    #[serde(default)]
+    pub breach_time: Option<ServiceDeskDate>,
+    pub breached: bool,
+    #[serde(default)]
+    pub paused: bool,
+    pub goal_duration: SlaDuration,
+    pub elapsed_time: SlaDuration,
+    pub remaining_time: SlaDuration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SlaDuration {
+    /// Negative once the goal is missed.
+    pub millis: i64,
+    /// Formatted for people, e.g. "-2h 30m".
+    pub friendly: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use ureq::serde_json::{self, json};
+
+    use super::*;
+
+    fn date(friendly: &str) -> Value {
+        json!({
+            "iso8601": "2024-01-01T10:00:00+0000",
+            "jira": "2024-01-01T10:00:00.000+0000",
+            "friendly": friendly,
+            "epochMillis": 1704103200000i64,
+        })
+    }
+
+    #[test]
+    fn customer_requests_decode() {
+        let request: CustomerRequest = serde_json::from_value(json!({
+            "issueId": "10010",
+            "issueKey": "HELP-1",
+            "requestTypeId": "1",
+            "serviceDeskId": "2",
+            "createdDate": date("Today 10:00 AM"),
+            "reporter": { "accountId": "abc", "displayName": "Customer" },
+            "requestFieldValues": [
+                { "fieldId": "summary", "label": "What do you need?", "value": "VPN is down" }
+            ],
+            "currentStatus": { "status": "Waiting for support", "statusCategory": "NEW" },
+        }))
+        .unwrap();
+
+        assert_eq!(request.issue_id, 10010);
+        assert_eq!(request.service_desk_id, 2);
+        assert_eq!(request.summary(), "VPN is down");
+        assert_eq!(request.reporter.unwrap().display_name, "Customer");
+    }
+
+    #[test]
+    fn slas_tell_if_breached() {
+        let cycle = |breached: bool| {
+            json!({
+                "startTime": date("Today 10:00 AM"),
+                "breached": breached,
+                "goalDuration": { "millis": 14400000, "friendly": "4h" },
+                "elapsedTime": { "millis": 18000000, "friendly": "5h" },
+                "remainingTime": { "millis": -3600000, "friendly": "-1h" },
+            })
+        };
+
+        let sla: Sla = serde_json::from_value(json!({
+            "id": "1",
+            "name": "Time to resolution",
+            "ongoingCycle": cycle(true),
+            "completedCycles": [cycle(false)],
+        }))
+        .unwrap();
+        assert!(sla.breached());
+
+        let sla: Sla = serde_json::from_value(json!({
+            "id": "2",
+            "name": "Time to first response",
+            "completedCycles": [cycle(false)],
+        }))
+        .unwrap();
+        assert!(!sla.breached());
+    }
+}
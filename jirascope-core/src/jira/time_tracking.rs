@@ -0,0 +1,368 @@
+//! Time tracking: estimates, time spent, and Jira's `1w 2d 3h 30m` syntax.
+//!
+//! How long a day or a week is depends on the instance, by default a day is 8
+//! hours and a week 5 days. Parsing and formatting take the instance's
+//! [`TimeTrackingOptions`], durations themselves are kept in seconds.
+//!
+//! ```
+//! use jirascope_core::jira::{JiraDuration, TimeTrackingOptions};
+//!
+//! let options = TimeTrackingOptions::default();
+//! let duration = JiraDuration::parse("1w 2d 3h 30m", &options).unwrap();
+//! assert_eq!(duration.seconds(), ((5 + 2) * 8 * 60 + 3 * 60 + 30) * 60);
+//! assert_eq!(duration.format(&options), "1w 2d 3h 30m");
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ureq::serde_json::{Map, Value};
+
+/// Schema for the `/configuration/timetracking/options` response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeTrackingOptions {
+    pub working_hours_per_day: f64,
+    pub working_days_per_week: f64,
+    pub time_format: TimeFormat,
+    /// The unit of a number without one, like `3`.
+    pub default_unit: DurationUnit,
+}
+
+impl Default for TimeTrackingOptions {
+    /// Jira's defaults.
+    fn default() -> Self {
+        TimeTrackingOptions {
+            working_hours_per_day: 8.0,
+            working_days_per_week: 5.0,
+            time_format: TimeFormat::Pretty,
+            default_unit: DurationUnit::Minute,
+        }
+    }
+}
+
+impl TimeTrackingOptions {
+    fn unit_seconds(&self, unit: DurationUnit) -> f64 {
+        let day = self.working_hours_per_day * 3600.0;
+
+        match unit {
+            DurationUnit::Minute => 60.0,
+            DurationUnit::Hour => 3600.0,
+            DurationUnit::Day => day,
+            DurationUnit::Week => day * self.working_days_per_week,
+        }
+    }
+}
+
+/// The largest unit durations are shown in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeFormat {
+    /// Weeks and smaller, `1w 2d 3h`.
+    Pretty,
+    /// Days and smaller, `7d 3h`.
+    Days,
+    /// Hours and minutes, `59h`.
+    Hours,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationUnit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+}
+
+impl DurationUnit {
+    fn from_suffix(suffix: char) -> Option<DurationUnit> {
+        match suffix.to_ascii_lowercase() {
+            'm' => Some(DurationUnit::Minute),
+            'h' => Some(DurationUnit::Hour),
+            'd' => Some(DurationUnit::Day),
+            'w' => Some(DurationUnit::Week),
+            _ => None,
+        }
+    }
+
+    fn suffix(&self) -> char {
+        match self {
+            DurationUnit::Minute => 'm',
+            DurationUnit::Hour => 'h',
+            DurationUnit::Day => 'd',
+            DurationUnit::Week => 'w',
+        }
+    }
+}
+
+/// A length of working time, like an estimate or the time spent on an issue.
+///
+/// Serializes as seconds, which is how Jira sends time tracking fields
+/// alongside their formatted text.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct JiraDuration {
+    seconds: u64,
+}
+
+impl JiraDuration {
+    pub fn from_seconds(seconds: u64) -> JiraDuration {
+        JiraDuration { seconds }
+    }
+
+    pub fn from_minutes(minutes: u64) -> JiraDuration {
+        JiraDuration {
+            seconds: minutes * 60,
+        }
+    }
+
+    pub fn seconds(&self) -> u64 {
+        self.seconds
+    }
+
+    /// Parses `1w 2d 3h 30m`, or a plain number in the default unit. Units
+    /// may come in any order, spaces between them are optional and numbers
+    /// may have decimals, e.g. `1.5h`.
+    pub fn parse(text: &str, options: &TimeTrackingOptions) -> Result<JiraDuration, crate::Error> {
+        let invalid = || crate::Error::InvalidDuration {
+            value: text.to_string(),
+        };
+
+        let trimmed = text.trim();
+        if let Ok(number) = trimmed.parse::<f64>() {
+            return Self::from_units(number, options.default_unit, options).ok_or_else(invalid);
+        }
+
+        let mut seconds = 0;
+        let mut rest = trimmed;
+        while !rest.is_empty() {
+            let number_len = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .ok_or_else(invalid)?;
+            let number = rest[..number_len].parse::<f64>().map_err(|_| invalid())?;
+
+            let mut after = rest[number_len..].chars();
+            let unit = after
+                .next()
+                .and_then(DurationUnit::from_suffix)
+                .ok_or_else(invalid)?;
+
+            seconds += Self::from_units(number, unit, options)
+                .ok_or_else(invalid)?
+                .seconds;
+            rest = after.as_str().trim_start();
+        }
+
+        if trimmed.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(JiraDuration { seconds })
+    }
+
+    fn from_units(
+        number: f64,
+        unit: DurationUnit,
+        options: &TimeTrackingOptions,
+    ) -> Option<JiraDuration> {
+        let seconds = number * options.unit_seconds(unit);
+
+        if !seconds.is_finite() || seconds < 0.0 {
+            return None;
+        }
+
+        Some(JiraDuration::from_seconds(seconds.round() as u64))
+    }
+
+    /// Formats as `1w 2d 3h 30m`, with the largest unit the instance's
+    /// [`TimeFormat`] allows. Seconds are rounded to minutes, Jira doesn't
+    /// track anything shorter.
+    pub fn format(&self, options: &TimeTrackingOptions) -> String {
+        let units: &[DurationUnit] = match options.time_format {
+            TimeFormat::Pretty => &[
+                DurationUnit::Week,
+                DurationUnit::Day,
+                DurationUnit::Hour,
+                DurationUnit::Minute,
+            ],
+            TimeFormat::Days => &[DurationUnit::Day, DurationUnit::Hour, DurationUnit::Minute],
+            TimeFormat::Hours => &[DurationUnit::Hour, DurationUnit::Minute],
+        };
+
+        // in minutes, so that days of e.g. 7.5 hours come out whole
+        let mut rest = (self.seconds as f64 / 60.0).round();
+        let mut parts = Vec::new();
+        for &unit in units {
+            let minutes = options.unit_seconds(unit) / 60.0;
+            if minutes <= 0.0 {
+                continue;
+            }
+
+            let count = (rest / minutes).floor();
+            if count > 0.0 {
+                parts.push(format!("{}{}", count, unit.suffix()));
+                rest -= count * minutes;
+            }
+        }
+
+        if parts.is_empty() {
+            return "0m".to_string();
+        }
+
+        parts.join(" ")
+    }
+}
+
+/// Schema for the `timetracking` field of an issue.
+///
+/// Only the seconds are parsed. The texts Jira sends along, formatted with
+/// the instance's options, are kept in `extra` as they came and can be made
+/// again with [`JiraDuration::format`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeTracking {
+    #[serde(
+        rename = "originalEstimateSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub original_estimate: Option<JiraDuration>,
+    #[serde(
+        rename = "remainingEstimateSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub remaining_estimate: Option<JiraDuration>,
+    #[serde(
+        rename = "timeSpentSeconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub time_spent: Option<JiraDuration>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Estimates to set on an issue, see [`crate::jira::IssueEditFields`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeTrackingEdit {
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_minutes",
+        deserialize_with = "deserialize_minutes"
+    )]
+    pub original_estimate: Option<JiraDuration>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_minutes",
+        deserialize_with = "deserialize_minutes"
+    )]
+    pub remaining_estimate: Option<JiraDuration>,
+}
+
+/// Sent in minutes, which mean the same whatever the instance's options.
+fn serialize_minutes<S: Serializer>(
+    duration: &Option<JiraDuration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => {
+            serializer.serialize_str(&format!("{}m", (duration.seconds as f64 / 60.0).round()))
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+fn deserialize_minutes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<JiraDuration>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(minutes) => minutes
+            .strip_suffix('m')
+            .and_then(|minutes| minutes.parse().ok())
+            .map(|minutes| Some(JiraDuration::from_minutes(minutes)))
+            .ok_or_else(|| serde::de::Error::custom(format!("{:?} isn't in minutes", minutes))),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ureq::serde_json::{self, json};
+
+    use super::*;
+
+    #[test]
+    fn durations_follow_the_instance_options() {
+        let options = TimeTrackingOptions {
+            working_hours_per_day: 7.5,
+            working_days_per_week: 4.0,
+            time_format: TimeFormat::Pretty,
+            default_unit: DurationUnit::Hour,
+        };
+
+        let week = JiraDuration::parse("1w", &options).unwrap();
+        assert_eq!(week.seconds(), 30 * 3600);
+        assert_eq!(week.format(&options), "1w");
+
+        let duration = JiraDuration::parse("2d1.5h 30M", &options).unwrap();
+        assert_eq!(duration.format(&options), "2d 2h");
+        assert_eq!(
+            JiraDuration::parse("3", &options).unwrap().format(&options),
+            "3h"
+        );
+
+        let hours = TimeTrackingOptions {
+            time_format: TimeFormat::Hours,
+            ..options
+        };
+        assert_eq!(week.format(&hours), "30h");
+        assert_eq!(JiraDuration::default().format(&hours), "0m");
+    }
+
+    #[test]
+    fn invalid_durations_are_rejected() {
+        let options = TimeTrackingOptions::default();
+
+        for invalid in ["", " ", "1x", "w", "1w 2", "-1h", "1..5h", "1 hour"] {
+            assert!(
+                JiraDuration::parse(invalid, &options).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn estimates_are_read_in_seconds_and_sent_in_minutes() {
+        let value = json!({
+            "originalEstimate": "1d",
+            "originalEstimateSeconds": 28800,
+            "timeSpent": "30m",
+            "timeSpentSeconds": 1800
+        });
+        let time_tracking: TimeTracking = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(
+            time_tracking.original_estimate,
+            Some(JiraDuration::from_seconds(28800))
+        );
+        assert_eq!(time_tracking.remaining_estimate, None);
+        // snapshots keep the issue as Jira sent it
+        assert_eq!(serde_json::to_value(&time_tracking).unwrap(), value);
+
+        let edit = TimeTrackingEdit {
+            remaining_estimate: Some(JiraDuration::from_minutes(90)),
+            ..Default::default()
+        };
+        let sent = serde_json::to_value(&edit).unwrap();
+        assert_eq!(sent, json!({ "remainingEstimate": "90m" }));
+        // edits waiting in the outbox are read back
+        assert_eq!(
+            serde_json::from_value::<TimeTrackingEdit>(sent).unwrap(),
+            edit
+        );
+    }
+}
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use ureq::serde_json::{Map, Value};
+
+use super::AccountId;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct User {
@@ -6,7 +9,9 @@ pub struct User {
     pub display_name: String,
     pub active: bool,
     #[serde(rename = "accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
     #[serde(rename = "accountType")]
     pub account_type: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
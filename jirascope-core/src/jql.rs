@@ -0,0 +1,796 @@
+//! Building JQL queries without `format!`.
+//!
+//! Values are always quoted and escaped, field names only when they have to
+//! be, like `"Story Points"` or a field named like a reserved word. Clauses
+//! combined with [`Jql::and`] and [`Jql::or`] get the parentheses their
+//! precedence needs.
+//!
+//! ```
+//! use jirascope_core::jql::{current_user, updated, Jql};
+//!
+//! let jql = Jql::project("ABC")
+//!     .and(Jql::status().not_in(["Done", "Won't \"do\""]))
+//!     .and(Jql::assignee().eq(current_user()).or(Jql::assignee().is_empty()))
+//!     .order_by(updated().desc());
+//!
+//! assert_eq!(
+//!     jql.to_string(),
+//!     "project = \"ABC\" AND status NOT IN (\"Done\", \"Won't \\\"do\\\"\") \
+//!      AND (assignee = currentUser() OR assignee IS EMPTY) ORDER BY updated DESC"
+//! );
+//! ```
+//!
+//! Searches take raw JQL strings just as well, they're used as they are.
+
+use std::{
+    fmt::{Display, Formatter},
+    ops::Not,
+};
+
+use crate::jira::{IssueKey, JiraDate, ProjectKey};
+
+/// Words JQL reserves, which have to be quoted to be used as field names.
+const RESERVED_WORDS: &[&str] = &[
+    "a",
+    "abort",
+    "access",
+    "add",
+    "after",
+    "alias",
+    "all",
+    "alter",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "asc",
+    "at",
+    "audit",
+    "avg",
+    "before",
+    "begin",
+    "between",
+    "boolean",
+    "break",
+    "by",
+    "byte",
+    "catch",
+    "cf",
+    "char",
+    "character",
+    "check",
+    "checkpoint",
+    "collate",
+    "collation",
+    "column",
+    "commit",
+    "connect",
+    "continue",
+    "count",
+    "create",
+    "current",
+    "date",
+    "decimal",
+    "declare",
+    "decrement",
+    "default",
+    "defaults",
+    "define",
+    "delete",
+    "delimiter",
+    "desc",
+    "difference",
+    "distinct",
+    "divide",
+    "do",
+    "double",
+    "drop",
+    "else",
+    "empty",
+    "encoding",
+    "end",
+    "equals",
+    "escape",
+    "exclusive",
+    "exec",
+    "execute",
+    "exists",
+    "explain",
+    "false",
+    "fetch",
+    "file",
+    "field",
+    "first",
+    "float",
+    "for",
+    "from",
+    "function",
+    "go",
+    "goto",
+    "grant",
+    "greater",
+    "group",
+    "having",
+    "identified",
+    "if",
+    "immediate",
+    "in",
+    "increment",
+    "index",
+    "initial",
+    "inner",
+    "inout",
+    "input",
+    "insert",
+    "int",
+    "integer",
+    "intersect",
+    "intersection",
+    "into",
+    "is",
+    "isempty",
+    "isnull",
+    "join",
+    "last",
+    "left",
+    "less",
+    "like",
+    "limit",
+    "lock",
+    "long",
+    "max",
+    "min",
+    "minus",
+    "mode",
+    "modify",
+    "modulo",
+    "more",
+    "multiply",
+    "next",
+    "noaudit",
+    "not",
+    "notin",
+    "nowait",
+    "null",
+    "number",
+    "object",
+    "of",
+    "on",
+    "option",
+    "or",
+    "order",
+    "outer",
+    "output",
+    "power",
+    "previous",
+    "prior",
+    "privileges",
+    "public",
+    "raise",
+    "raw",
+    "remainder",
+    "rename",
+    "resume",
+    "return",
+    "returns",
+    "revoke",
+    "right",
+    "row",
+    "rowid",
+    "rownum",
+    "rows",
+    "select",
+    "session",
+    "set",
+    "share",
+    "size",
+    "sqrt",
+    "start",
+    "strict",
+    "string",
+    "subtract",
+    "sum",
+    "synonym",
+    "table",
+    "then",
+    "to",
+    "trans",
+    "transaction",
+    "trigger",
+    "true",
+    "uid",
+    "union",
+    "unique",
+    "update",
+    "user",
+    "validate",
+    "values",
+    "view",
+    "when",
+    "whenever",
+    "where",
+    "while",
+    "with",
+];
+
+/// A JQL query: an optional condition, and the order of the results.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Jql {
+    clause: Option<Clause>,
+    order_by: Vec<OrderBy>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Clause {
+    /// JQL written by hand, used as it is.
+    Raw(String),
+    Condition {
+        field: Field,
+        operator: &'static str,
+        operand: Option<Operand>,
+    },
+    And(Vec<Clause>),
+    Or(Vec<Clause>),
+    Not(Box<Clause>),
+}
+
+impl Jql {
+    /// JQL written by hand. It's used as it is, so combining one which has an
+    /// `ORDER BY` with other clauses makes invalid JQL.
+    pub fn raw(jql: impl Into<String>) -> Jql {
+        Jql::from_clause(Clause::Raw(jql.into()))
+    }
+
+    fn from_clause(clause: Clause) -> Jql {
+        Jql {
+            clause: Some(clause),
+            order_by: Vec::new(),
+        }
+    }
+
+    /// Any field, by its name like `"Story Points"` or id like `cf[10010]`.
+    pub fn field(name: impl Into<String>) -> Field {
+        Field(name.into())
+    }
+
+    /// Issues of the project `key`.
+    pub fn project(key: impl Into<Operand>) -> Jql {
+        project().eq(key)
+    }
+
+    pub fn key() -> Field {
+        key()
+    }
+
+    pub fn status() -> Field {
+        status()
+    }
+
+    pub fn assignee() -> Field {
+        assignee()
+    }
+
+    pub fn reporter() -> Field {
+        reporter()
+    }
+
+    pub fn issue_type() -> Field {
+        issue_type()
+    }
+
+    pub fn summary() -> Field {
+        summary()
+    }
+
+    /// Both conditions, results ordered by this query's order and then by
+    /// the other's.
+    pub fn and(self, other: Jql) -> Jql {
+        self.combine(other, false)
+    }
+
+    /// Either condition, results ordered as for [`Jql::and`].
+    pub fn or(self, other: Jql) -> Jql {
+        self.combine(other, true)
+    }
+
+    fn combine(mut self, other: Jql, or: bool) -> Jql {
+        self.clause = match (self.clause, other.clause) {
+            (Some(left), Some(right)) => {
+                let mut clauses = Vec::new();
+                for clause in [left, right] {
+                    // flatten `a AND b AND c`, whichever way it was built
+                    match clause {
+                        Clause::And(inner) if !or => clauses.extend(inner),
+                        Clause::Or(inner) if or => clauses.extend(inner),
+                        clause => clauses.push(clause),
+                    }
+                }
+                Some(if or {
+                    Clause::Or(clauses)
+                } else {
+                    Clause::And(clauses)
+                })
+            }
+            (left, right) => left.or(right),
+        };
+        self.order_by.extend(other.order_by);
+        self
+    }
+
+    /// Orders the results by `order`, after any order given before.
+    pub fn order_by(mut self, order: impl Into<OrderBy>) -> Jql {
+        self.order_by.push(order.into());
+        self
+    }
+}
+
+impl Not for Jql {
+    type Output = Jql;
+
+    /// Everything the condition doesn't match, keeping the order.
+    fn not(mut self) -> Jql {
+        self.clause = self.clause.map(|clause| Clause::Not(Box::new(clause)));
+        self
+    }
+}
+
+impl Display for Jql {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(ref clause) = self.clause {
+            write!(f, "{}", clause)?;
+        }
+
+        for (i, order) in self.order_by.iter().enumerate() {
+            match (i, &self.clause) {
+                (0, Some(_)) => f.write_str(" ORDER BY ")?,
+                (0, None) => f.write_str("ORDER BY ")?,
+                _ => f.write_str(", ")?,
+            }
+            write!(f, "{}", order)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&str> for Jql {
+    fn from(jql: &str) -> Self {
+        Jql::raw(jql)
+    }
+}
+
+impl From<&String> for Jql {
+    fn from(jql: &String) -> Self {
+        Jql::raw(jql.as_str())
+    }
+}
+
+impl From<String> for Jql {
+    fn from(jql: String) -> Self {
+        Jql::raw(jql)
+    }
+}
+
+impl From<&Jql> for Jql {
+    fn from(jql: &Jql) -> Self {
+        jql.clone()
+    }
+}
+
+impl Clause {
+    /// Writes `self` as part of `parent`, in parentheses if it would bind
+    /// differently without them.
+    fn fmt_within(&self, parent: &Clause, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let parenthesize = !matches!(
+            (parent, self),
+            (_, Clause::Condition { .. } | Clause::Not(_)) | (Clause::Or(_), Clause::And(_))
+        );
+
+        if parenthesize {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl Display for Clause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (clauses, separator) = match self {
+            Clause::Raw(jql) => return f.write_str(jql),
+            Clause::Condition {
+                field,
+                operator,
+                operand,
+            } => {
+                write!(f, "{} {}", field, operator)?;
+                if let Some(operand) = operand {
+                    write!(f, " {}", operand)?;
+                }
+                return Ok(());
+            }
+            Clause::Not(clause) => {
+                f.write_str("NOT ")?;
+                return clause.fmt_within(self, f);
+            }
+            Clause::And(clauses) => (clauses, " AND "),
+            Clause::Or(clauses) => (clauses, " OR "),
+        };
+
+        for (i, clause) in clauses.iter().enumerate() {
+            if i > 0 {
+                f.write_str(separator)?;
+            }
+            clause.fmt_within(self, f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A field to compare or order by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field(String);
+
+impl Field {
+    /// A custom field by its numeric id, `cf[10010]`.
+    pub fn custom(id: u64) -> Field {
+        Field(format!("cf[{}]", id))
+    }
+
+    fn condition(self, operator: &'static str, operand: Option<Operand>) -> Jql {
+        Jql::from_clause(Clause::Condition {
+            field: self,
+            operator,
+            operand,
+        })
+    }
+
+    pub fn eq(self, value: impl Into<Operand>) -> Jql {
+        self.condition("=", Some(value.into()))
+    }
+
+    pub fn ne(self, value: impl Into<Operand>) -> Jql {
+        self.condition("!=", Some(value.into()))
+    }
+
+    pub fn gt(self, value: impl Into<Operand>) -> Jql {
+        self.condition(">", Some(value.into()))
+    }
+
+    pub fn ge(self, value: impl Into<Operand>) -> Jql {
+        self.condition(">=", Some(value.into()))
+    }
+
+    pub fn lt(self, value: impl Into<Operand>) -> Jql {
+        self.condition("<", Some(value.into()))
+    }
+
+    pub fn le(self, value: impl Into<Operand>) -> Jql {
+        self.condition("<=", Some(value.into()))
+    }
+
+    /// Text search, `summary ~ "crash"`.
+    pub fn contains(self, text: impl Into<Operand>) -> Jql {
+        self.condition("~", Some(text.into()))
+    }
+
+    pub fn not_contains(self, text: impl Into<Operand>) -> Jql {
+        self.condition("!~", Some(text.into()))
+    }
+
+    pub fn is_in<V: Into<Operand>>(self, values: impl IntoIterator<Item = V>) -> Jql {
+        self.condition("IN", Some(Operand::list(values)))
+    }
+
+    pub fn not_in<V: Into<Operand>>(self, values: impl IntoIterator<Item = V>) -> Jql {
+        self.condition("NOT IN", Some(Operand::list(values)))
+    }
+
+    pub fn is_empty(self) -> Jql {
+        self.condition("IS EMPTY", None)
+    }
+
+    pub fn is_not_empty(self) -> Jql {
+        self.condition("IS NOT EMPTY", None)
+    }
+
+    pub fn asc(self) -> OrderBy {
+        OrderBy {
+            field: self,
+            direction: Some("ASC"),
+        }
+    }
+
+    pub fn desc(self) -> OrderBy {
+        OrderBy {
+            field: self,
+            direction: Some("DESC"),
+        }
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = &self.0;
+        let identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        let custom_id = name
+            .strip_prefix("cf[")
+            .and_then(|id| id.strip_suffix(']'))
+            .is_some_and(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()));
+
+        if custom_id || identifier && !RESERVED_WORDS.contains(&name.to_ascii_lowercase().as_str())
+        {
+            f.write_str(name)
+        } else {
+            write_quoted(name, f)
+        }
+    }
+}
+
+/// The right side of a condition: a value, a function call, or a list of
+/// those for `IN`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    /// Always quoted, `"-5m"`, `"ABC"` and `"Done"` alike.
+    Text(String),
+    Number(i64),
+    Function {
+        name: String,
+        args: Vec<String>,
+    },
+    List(Vec<Operand>),
+}
+
+impl Operand {
+    fn list<V: Into<Operand>>(values: impl IntoIterator<Item = V>) -> Operand {
+        Operand::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Text(text) => write_quoted(text, f),
+            Operand::Number(number) => write!(f, "{}", number),
+            Operand::Function { name, args } => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_quoted(arg, f)?;
+                }
+                f.write_str(")")
+            }
+            Operand::List(values) => {
+                f.write_str("(")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+impl From<&str> for Operand {
+    fn from(text: &str) -> Self {
+        Operand::Text(text.to_string())
+    }
+}
+
+impl From<String> for Operand {
+    fn from(text: String) -> Self {
+        Operand::Text(text)
+    }
+}
+
+impl From<i64> for Operand {
+    fn from(number: i64) -> Self {
+        Operand::Number(number)
+    }
+}
+
+impl From<&IssueKey> for Operand {
+    fn from(key: &IssueKey) -> Self {
+        Operand::Text(key.to_string())
+    }
+}
+
+impl From<IssueKey> for Operand {
+    fn from(key: IssueKey) -> Self {
+        Operand::Text(key.into())
+    }
+}
+
+impl From<&ProjectKey> for Operand {
+    fn from(key: &ProjectKey) -> Self {
+        Operand::Text(key.to_string())
+    }
+}
+
+impl From<ProjectKey> for Operand {
+    fn from(key: ProjectKey) -> Self {
+        Operand::Text(key.into())
+    }
+}
+
+impl From<JiraDate> for Operand {
+    fn from(date: JiraDate) -> Self {
+        Operand::Text(date.to_string())
+    }
+}
+
+/// A field to order the results by, see [`Jql::order_by`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderBy {
+    field: Field,
+    direction: Option<&'static str>,
+}
+
+impl From<Field> for OrderBy {
+    /// In the field's default direction.
+    fn from(field: Field) -> Self {
+        OrderBy {
+            field,
+            direction: None,
+        }
+    }
+}
+
+impl Display for OrderBy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.field)?;
+        if let Some(direction) = self.direction {
+            write!(f, " {}", direction)?;
+        }
+        Ok(())
+    }
+}
+
+/// Quotes `text` as a JQL string, escaping what needs to be.
+fn write_quoted(text: &str, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str("\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+pub fn project() -> Field {
+    Jql::field("project")
+}
+
+pub fn key() -> Field {
+    Jql::field("key")
+}
+
+pub fn status() -> Field {
+    Jql::field("status")
+}
+
+pub fn assignee() -> Field {
+    Jql::field("assignee")
+}
+
+pub fn reporter() -> Field {
+    Jql::field("reporter")
+}
+
+pub fn issue_type() -> Field {
+    Jql::field("issuetype")
+}
+
+pub fn summary() -> Field {
+    Jql::field("summary")
+}
+
+pub fn created() -> Field {
+    Jql::field("created")
+}
+
+pub fn updated() -> Field {
+    Jql::field("updated")
+}
+
+/// A JQL function like `startOfDay("-1")`, its arguments are quoted.
+pub fn function<A: Into<String>>(name: &str, args: impl IntoIterator<Item = A>) -> Operand {
+    Operand::Function {
+        name: name.to_string(),
+        args: args.into_iter().map(Into::into).collect(),
+    }
+}
+
+/// The user the query runs as.
+pub fn current_user() -> Operand {
+    function::<String>("currentUser", [])
+}
+
+pub fn now() -> Operand {
+    function::<String>("now", [])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_and_field_names_are_escaped() {
+        assert_eq!(
+            Jql::summary().contains("say \"hi\"\\\n").to_string(),
+            r#"summary ~ "say \"hi\"\\\n""#
+        );
+        assert_eq!(
+            Jql::field("Story Points").gt(3).to_string(),
+            "\"Story Points\" > 3"
+        );
+        assert_eq!(
+            Jql::field("order").eq("by").to_string(),
+            "\"order\" = \"by\""
+        );
+        assert_eq!(
+            Field::custom(10010).is_not_empty().to_string(),
+            "cf[10010] IS NOT EMPTY"
+        );
+        assert_eq!(
+            created()
+                .ge(function("startOfDay", ["-1"]))
+                .and(updated().lt(now()))
+                .to_string(),
+            "created >= startOfDay(\"-1\") AND updated < now()"
+        );
+    }
+
+    #[test]
+    fn clauses_are_parenthesized_by_precedence() {
+        let done = || Jql::status().eq("Done");
+        let mine = || Jql::assignee().eq(current_user());
+
+        assert_eq!(
+            done().or(mine()).and(Jql::project("ABC")).to_string(),
+            "(status = \"Done\" OR assignee = currentUser()) AND project = \"ABC\""
+        );
+        assert_eq!(
+            done().and(mine()).or(Jql::project("ABC")).to_string(),
+            "status = \"Done\" AND assignee = currentUser() OR project = \"ABC\""
+        );
+        assert_eq!(
+            (!done().or(mine())).to_string(),
+            "NOT (status = \"Done\" OR assignee = currentUser())"
+        );
+        assert_eq!(
+            Jql::raw("a = 1 OR b = 2").and(done()).to_string(),
+            "(a = 1 OR b = 2) AND status = \"Done\""
+        );
+    }
+
+    #[test]
+    fn order_by_follows_the_clauses() {
+        assert_eq!(
+            Jql::default().order_by(key().asc()).to_string(),
+            "ORDER BY key ASC"
+        );
+        assert_eq!(
+            Jql::project("ABC")
+                .order_by(updated().desc())
+                .and(Jql::default().order_by(key()))
+                .to_string(),
+            "project = \"ABC\" ORDER BY updated DESC, key"
+        );
+    }
+}
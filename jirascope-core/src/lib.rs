@@ -1,28 +1,94 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock, RwLock,
+    },
+    time::Instant,
+};
+
 use jira::{
-    CreatedIssue, IssueCreation, IssueCreationMeta, IssueEdit, IssueEditMeta, IssueEvent,
-    IssueTransition, IssueTransitionDescriptor, IssueTransitionDescriptors, Issues, Paginated,
-    ProjectCategory, ProjectCreate, ProjectCreated, ProjectDetailed, ProjectEdit,
-    ProjectIssueSecurityScheme, User,
+    ApproximateCount, ApproximateCountQuery, AtlassianDoc, Comment, CommentCreation, CreatedIssue,
+    CustomerRequest, CustomerRequestCreation, Filter, FilterDetails, FilterShare, IssueCreation,
+    IssueCreationField, IssueCreationMeta, IssueEdit, IssueEditMeta, IssueEvent, IssueIdOrKey,
+    IssueRef, IssueTransition, IssueTransitionDescriptor, IssueTransitionDescriptors, IssueType,
+    JiraDateTime, ProjectCategory, ProjectCreate, ProjectCreated, ProjectDetailed, ProjectEdit,
+    ProjectIdOrKey, ProjectIssueCreationMeta, ProjectIssueSecurityScheme, ProjectKey, Queue,
+    QueueIssue, RequestComment, RequestCommentCreation, RequestType, ServerInfo, ServiceDesk,
+    SharePermission, Sla, TimeTrackingOptions, User,
 };
 use serde::Serialize;
+use ureq::serde_json;
 
+#[cfg(feature = "async")]
+mod async_client;
 mod auth;
+mod cache;
 mod config;
+mod diff;
+mod dry_run;
 mod error;
+mod extract;
+mod fields;
+mod hooks;
 pub mod jira;
+pub mod jql;
+mod outbox;
+mod pagination;
+mod policy;
+mod response;
+mod snapshot;
+mod sync;
+#[cfg(test)]
+mod test_utils;
+mod trace;
 mod utils;
 
+#[cfg(feature = "async")]
+pub use async_client::{AsyncJirascope, AsyncPages};
 pub use auth::Auth;
 pub use config::Config;
-pub use error::Error;
-
+pub use diff::{display_value, Change, FieldChange, IssueDiff, TextChunk};
+pub use dry_run::DryRunRequest;
+pub use error::{Error, ErrorBody};
+pub use extract::{find_issue_keys, IssueKeyFinder, IssueKeyMatch};
+pub use fields::{Expand, FieldSet, IssueField};
+pub use hooks::{Hook, Veto};
+pub use jql::Jql;
+pub use outbox::{ItemStatus, Mutation, Outbox, OutboxItem, ReplayReport, OUTBOX_VERSION};
+pub use pagination::Pages;
+pub use policy::{Confirmation, Guarded, Operation, Policy, PolicyViolation};
+pub use snapshot::{Snapshot, SnapshotStore, SNAPSHOT_VERSION};
+pub use sync::{IssueSync, SyncChanges};
+
+pub use chrono;
 pub use ureq;
 
-use crate::jira::{FieldConfigurationScheme, Issue};
+/// Where Jira Service Management's API lives, next to the platform API.
+const SERVICE_DESK_API: &str = "/rest/servicedeskapi";
+
+use crate::{
+    cache::{CacheOutcome, HttpCache, Validators},
+    jira::{FieldConfigurationScheme, Issue},
+    pagination::PageStyle,
+    response::ApiResponse,
+};
 
+/// A Jira client.
+///
+/// All methods take `&self` and the client is `Send + Sync`, so it can be
+/// shared between threads, e.g. in an `Arc`. Requests made at the same time
+/// run in parallel over a shared connection pool.
 pub struct Jirascope {
     config: Config,
     pub auth: Auth,
+    agent: ureq::Agent,
+    server_info: OnceLock<ServerInfo>,
+    current_user: OnceLock<User>,
+    cache: HttpCache,
+    dry_run: AtomicBool,
+    dry_run_requests: Mutex<VecDeque<DryRunRequest>>,
+    hooks: RwLock<Vec<Arc<dyn Hook>>>,
 }
 
 #[cfg(feature = "test_server")]
@@ -34,15 +100,107 @@ pub struct Note {
 
 impl Jirascope {
     pub fn new(config: Config, auth: Auth) -> Jirascope {
-        Jirascope { config, auth }
+        Jirascope {
+            auth,
+            agent: ureq::AgentBuilder::new().build(),
+            server_info: OnceLock::new(),
+            current_user: OnceLock::new(),
+            cache: HttpCache::new(),
+            dry_run: AtomicBool::new(config.dry_run),
+            dry_run_requests: Mutex::new(VecDeque::new()),
+            hooks: RwLock::new(Vec::new()),
+            config,
+        }
     }
 
-    pub fn init(&mut self) -> Result<(), crate::Error> {
+    /// Check that the configured URL points at a Jira instance and that the
+    /// credentials are accepted, so misconfiguration surfaces here instead of
+    /// in the first unrelated request.
+    pub fn init(&self) -> Result<(), crate::Error> {
         self.auth.login(&self.config)?;
 
+        let server_info = self.server_info().map_err(|e| match e {
+            // either nothing answers at this path, or something that isn't Jira
+            crate::Error::NotFound(_) | crate::Error::Decode { .. } => crate::Error::NotJira {
+                url: self.config.api_url.clone(),
+            },
+            crate::Error::Transport(transport) => {
+                crate::Error::from_transport(&self.config.api_url, *transport)
+            }
+            e => e,
+        })?;
+
+        let current_user = self.myself()?;
+
+        // a second `init` talks to the same server, keep the first answers
+        let _ = self.server_info.set(server_info);
+        let _ = self.current_user.set(current_user);
+
+        Ok(())
+    }
+
+    /// Server information fetched by [`Jirascope::init`].
+    pub fn server(&self) -> Option<&ServerInfo> {
+        self.server_info.get()
+    }
+
+    /// The user the client is authenticated as, fetched by [`Jirascope::init`].
+    pub fn current_user(&self) -> Option<&User> {
+        self.current_user.get()
+    }
+
+    /// Switch dry-run mode, where changes are logged instead of sent.
+    pub fn set_dry_run(&self, dry_run: bool) {
+        self.dry_run.store(dry_run, Ordering::Relaxed);
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run.load(Ordering::Relaxed)
+    }
+
+    /// Add a hook run around every change, after the hooks added before.
+    pub fn add_hook(&self, hook: impl Hook + 'static) {
+        self.hooks.write().unwrap().push(Arc::new(hook));
+    }
+
+    /// The policy every change is checked against.
+    pub fn policy(&self) -> &Policy {
+        &self.config.policy
+    }
+
+    /// Check a bulk change of `count` single changes to issues of `projects`
+    /// before making them.
+    ///
+    /// The client can't tell single changes from parts of a bulk change, so
+    /// frontends making one ask here first. Each single change is still
+    /// checked when it's made.
+    pub fn authorize_bulk(
+        &self,
+        count: usize,
+        projects: &[&str],
+        confirmation: Option<&Confirmation>,
+    ) -> Result<(), crate::Error> {
+        let policy = self.policy();
+
+        policy.check(Operation::Bulk, None)?;
+        for project in projects {
+            policy.check(Operation::Bulk, Some(project))?;
+        }
+        policy.check_confirmed(
+            Guarded::Bulk,
+            Operation::Bulk,
+            &count.to_string(),
+            confirmation,
+        )?;
+
         Ok(())
     }
 
+    /// Requests held back by dry-run mode since the last call, oldest first.
+    pub fn take_dry_run_requests(&self) -> Vec<DryRunRequest> {
+        self.dry_run_requests.lock().unwrap().drain(..).collect()
+    }
+
     #[cfg(feature = "test_server")]
     pub fn register_note(&self, message: String) -> Result<Note, crate::Error> {
         let note = Note { id: None, message };
@@ -86,281 +244,1003 @@ impl Jirascope {
         Ok(note)
     }
 
-    pub fn get_users(&mut self) -> Result<Vec<User>, crate::Error> {
-        let users: Vec<User> = self.api_get("users/search")?.into_json()?;
+    pub fn server_info(&self) -> Result<ServerInfo, crate::Error> {
+        let server_info: ServerInfo = self.api_get("serverInfo")?.json()?;
+
+        Ok(server_info)
+    }
+
+    pub fn myself(&self) -> Result<User, crate::Error> {
+        let user: User = self.api_get("myself")?.json()?;
 
-        Ok(users)
+        Ok(user)
     }
 
-    pub fn get_projects(&mut self) -> Result<Vec<ProjectDetailed>, crate::Error> {
-        let response = self.api_get("project?expand=description,lead,url")?;
+    /// How long a working day and week are, to parse and format
+    /// [`jira::JiraDuration`]s with.
+    pub fn get_time_tracking_options(&self) -> Result<TimeTrackingOptions, crate::Error> {
+        let options: TimeTrackingOptions =
+            self.api_get("configuration/timetracking/options")?.json()?;
+
+        Ok(options)
+    }
+
+    pub fn get_users(&self) -> Pages<'_, User> {
+        Pages::new(self, "users/search", PageStyle::Array)
+    }
 
-        let projects: Vec<ProjectDetailed> = response.into_json()?;
+    pub fn get_projects(&self) -> Pages<'_, ProjectDetailed> {
+        Pages::new(
+            self,
+            "project/search?expand=description,lead,url",
+            PageStyle::Values,
+        )
+    }
 
-        Ok(projects)
+    pub fn get_issue(&self, issue_id: impl Into<IssueIdOrKey>) -> Result<Issue, crate::Error> {
+        self.get_issue_with(issue_id, &FieldSet::All, &[])
     }
 
-    pub fn get_issue<'a>(&mut self, issue_id: impl Into<&'a str>) -> Result<Issue, crate::Error> {
-        let response = self.api_get(format!("issue/{}", issue_id.into()).as_str())?;
+    /// An issue with only the given `fields`, and whatever `expand` asks for.
+    pub fn get_issue_with(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        fields: &FieldSet,
+        expand: &[Expand],
+    ) -> Result<Issue, crate::Error> {
+        let response = self.api_get(
+            format!(
+                "issue/{}?{}",
+                issue_id.into(),
+                fields::query(fields, expand)
+            )
+            .as_str(),
+        )?;
 
-        let issue = response.into_json()?;
+        let issue = response.json()?;
 
         Ok(issue)
     }
 
-    pub fn get_all_issues(&mut self) -> Result<Issues, crate::Error> {
-        let response = self.api_get("search")?;
+    pub fn get_all_issues(&self) -> Pages<'_, Issue> {
+        self.search_issues(sync::all_issues_jql())
+    }
 
-        let issues: Issues = response.into_json()?;
+    /// All issues matching `jql`, a [`Jql`] query or a raw JQL string.
+    ///
+    /// `search/jql` refuses unbounded queries, `jql` has to restrict the
+    /// results somehow, e.g. to some projects.
+    pub fn search_issues(&self, jql: impl Into<Jql>) -> Pages<'_, Issue> {
+        self.search_issues_with(jql, &FieldSet::Navigable, &[])
+    }
 
-        Ok(issues)
+    /// Like [`Jirascope::search_issues`], but only with the given `fields`,
+    /// and whatever `expand` asks for.
+    pub fn search_issues_with(
+        &self,
+        jql: impl Into<Jql>,
+        fields: &FieldSet,
+        expand: &[Expand],
+    ) -> Pages<'_, Issue> {
+        Pages::new(
+            self,
+            format!(
+                "search/jql?jql={}&{}",
+                utils::url_encode(&jql.into().to_string()),
+                fields::query(fields, expand)
+            ),
+            PageStyle::Cursor,
+        )
     }
 
-    pub fn get_issue_events(&mut self) -> Result<Vec<IssueEvent>, crate::Error> {
-        let response = self.api_get("events")?;
+    /// Ids and keys of all issues matching `jql`, without their fields.
+    /// Much cheaper than [`Jirascope::search_issues`] for large result sets.
+    pub fn search_issue_keys(&self, jql: impl Into<Jql>) -> Pages<'_, IssueRef> {
+        Pages::new(
+            self,
+            format!(
+                "search/jql?jql={}&fields=key",
+                utils::url_encode(&jql.into().to_string())
+            ),
+            PageStyle::Cursor,
+        )
+    }
 
-        let issue_events: Vec<IssueEvent> = response.into_json()?;
+    /// Roughly how many issues match `jql`. Issues changed in the last few
+    /// moments may be missing from the count.
+    pub fn approximate_issue_count(&self, jql: impl Into<Jql>) -> Result<usize, crate::Error> {
+        let query = ApproximateCountQuery {
+            jql: jql.into().to_string(),
+        };
+        // a POST which only reads, so it's sent even in dry-run mode
+        let response = self.api_request(
+            "POST",
+            "search/approximate-count",
+            Some(to_json_value(query)?),
+        )?;
 
-        Ok(issue_events)
+        let count: ApproximateCount = response.json()?;
+
+        Ok(count.count)
     }
 
-    pub fn get_issue_creation_meta(&mut self) -> Result<IssueCreationMeta, crate::Error> {
-        let response = self.api_get("issue/createmeta")?;
+    pub fn get_issue_events(&self) -> Pages<'_, IssueEvent> {
+        Pages::new(self, "events", PageStyle::Single)
+    }
 
-        let issue_events: IssueCreationMeta = response.into_json()?;
+    /// Creation metadata of every project, without fields.
+    #[deprecated(
+        note = "makes a request per project, use `get_issue_creation_types` and `get_issue_creation_fields`"
+    )]
+    pub fn get_issue_creation_meta(&self) -> Result<IssueCreationMeta, crate::Error> {
+        let projects = self
+            .get_projects()
+            .map(|project| {
+                let project = project?;
+
+                Ok(ProjectIssueCreationMeta {
+                    issue_types: self.get_issue_creation_types(&project.key).fetch_all()?,
+                    id: project.id,
+                    key: project.key,
+                    name: project.name,
+                })
+            })
+            .collect::<Result<_, crate::Error>>()?;
+
+        Ok(IssueCreationMeta { projects })
+    }
 
-        Ok(issue_events)
+    /// Issue types which can be created in a project.
+    pub fn get_issue_creation_types(
+        &self,
+        project: impl Into<ProjectIdOrKey>,
+    ) -> Pages<'_, IssueType> {
+        Pages::new(
+            self,
+            format!("issue/createmeta/{}/issuetypes", project.into()),
+            PageStyle::Total("issueTypes"),
+        )
     }
 
-    pub fn get_issue_edit_meta<'a>(
-        &mut self,
-        issue_id: impl Into<&'a str>,
+    /// Fields which can be set when creating an issue of a type in a project.
+    pub fn get_issue_creation_fields(
+        &self,
+        project: impl Into<ProjectIdOrKey>,
+        issue_type_id: &str,
+    ) -> Pages<'_, IssueCreationField> {
+        Pages::new(
+            self,
+            format!(
+                "issue/createmeta/{}/issuetypes/{}",
+                project.into(),
+                issue_type_id
+            ),
+            PageStyle::Total("fields"),
+        )
+    }
+
+    pub fn get_issue_edit_meta(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
     ) -> Result<IssueEditMeta, crate::Error> {
         let response = self.api_get(format!("issue/{}/editmeta", issue_id.into()).as_str())?;
 
-        let issue_events: IssueEditMeta = response.into_json()?;
+        let issue_events: IssueEditMeta = response.json()?;
 
         Ok(issue_events)
     }
 
-    pub fn create_issue(&mut self, issue: IssueCreation) -> Result<CreatedIssue, crate::Error> {
-        let response = self.api_post("issue", issue)?;
+    pub fn create_issue(&self, mut issue: IssueCreation) -> Result<CreatedIssue, crate::Error> {
+        let hooks = self.hooks();
+        for hook in &hooks {
+            hook.before_create(&mut issue)
+                .map_err(|veto| veto.into_error(Operation::CreateIssue))?;
+        }
+
+        self.authorize_in_project(Operation::CreateIssue, &issue.fields.project.key)?;
 
-        let created_issue: CreatedIssue = response.into_json()?;
+        let response = self.api_post("issue", &issue)?;
+
+        let created_issue: CreatedIssue = response.json()?;
+
+        self.after_hooks(&hooks, |hook| hook.after_create(&issue, &created_issue));
 
         Ok(created_issue)
     }
 
-    pub fn edit_issue<'a>(
-        &mut self,
-        issue_id: impl Into<&'a str>,
+    pub fn edit_issue(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        mut issue: IssueEdit,
+    ) -> Result<(), crate::Error> {
+        let issue_id = issue_id.into();
+
+        let hooks = self.hooks();
+        for hook in &hooks {
+            hook.before_edit(&issue_id, &mut issue)
+                .map_err(|veto| veto.into_error(Operation::EditIssue))?;
+        }
+
+        self.authorize_on_issue(Operation::EditIssue, &issue_id)?;
+
+        self.api_put(format!("issue/{}", issue_id).as_str(), &issue)?;
+
+        self.after_hooks(&hooks, |hook| hook.after_edit(&issue_id, &issue));
+
+        Ok(())
+    }
+
+    /// Edit an issue, unless it was updated since `expected_updated`, its
+    /// `updated` at the time the edit was made.
+    ///
+    /// `updated` is read again right before writing. On a mismatch nothing is
+    /// written and [`crate::Error::Conflict`] carries the current issue, so
+    /// the edit can be merged into it and retried with its `updated`.
+    ///
+    /// Jira has no conditional writes, a change landing between the check
+    /// and the edit still goes unnoticed.
+    pub fn edit_issue_if_unchanged(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        expected_updated: &JiraDateTime,
         issue: IssueEdit,
     ) -> Result<(), crate::Error> {
-        self.api_put(format!("issue/{}", issue_id.into()).as_str(), issue)?;
+        let issue_id = issue_id.into();
+
+        self.check_unchanged(&issue_id, expected_updated)?;
+        self.edit_issue(issue_id, issue)
+    }
+
+    /// Fail with [`crate::Error::Conflict`] if the issue was updated since
+    /// `expected_updated`.
+    pub(crate) fn check_unchanged(
+        &self,
+        issue_id: &IssueIdOrKey,
+        expected_updated: &JiraDateTime,
+    ) -> Result<(), crate::Error> {
+        let current = self.get_issue(issue_id)?;
+
+        // compared as instants, however the offset is written
+        if current.fields.updated != *expected_updated {
+            return Err(crate::Error::Conflict {
+                current: Box::new(current),
+            });
+        }
 
         Ok(())
     }
 
-    pub fn delete_issue<'a>(&mut self, issue_id: impl Into<&'a str>) -> Result<(), crate::Error> {
-        self.api_delete(format!("issue/{}", issue_id.into()).as_str())?;
+    /// Delete an issue. Issues with subtasks may need a [`Confirmation`],
+    /// see [`Jirascope::delete_issue_confirmed`].
+    pub fn delete_issue(&self, issue_id: impl Into<IssueIdOrKey>) -> Result<(), crate::Error> {
+        self.delete_issue_checked(&issue_id.into(), None)
+    }
+
+    /// Delete an issue, and its subtasks if it has any.
+    pub fn delete_issue_confirmed(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        confirmation: &Confirmation,
+    ) -> Result<(), crate::Error> {
+        self.delete_issue_checked(&issue_id.into(), Some(confirmation))
+    }
+
+    fn delete_issue_checked(
+        &self,
+        issue_id: &IssueIdOrKey,
+        confirmation: Option<&Confirmation>,
+    ) -> Result<(), crate::Error> {
+        let hooks = self.hooks();
+        for hook in &hooks {
+            hook.before_delete(issue_id)
+                .map_err(|veto| veto.into_error(Operation::DeleteIssue))?;
+        }
+
+        self.authorize_on_issue(Operation::DeleteIssue, issue_id)?;
+
+        let mut path = format!("issue/{}", issue_id);
+
+        if self
+            .policy()
+            .requires_confirmation(Guarded::DeleteIssueWithSubtasks)
+        {
+            let issue = self.issue_for_policy(issue_id)?;
+            let has_subtasks = issue
+                .fields
+                .extra
+                .get("subtasks")
+                .and_then(serde_json::Value::as_array)
+                .map(|subtasks| !subtasks.is_empty())
+                .unwrap_or(false);
+
+            if has_subtasks {
+                self.policy().check_confirmed(
+                    Guarded::DeleteIssueWithSubtasks,
+                    Operation::DeleteIssue,
+                    &issue.key,
+                    confirmation,
+                )?;
+                path.push_str("?deleteSubtasks=true");
+            }
+        }
+
+        self.api_delete(&path)?;
+
+        self.after_hooks(&hooks, |hook| hook.after_delete(issue_id));
 
         Ok(())
     }
 
     pub fn get_issue_transitions(
-        &mut self,
-        issue_id: &str,
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
     ) -> Result<IssueTransitionDescriptors, crate::Error> {
-        let response = self.api_get(format!("issue/{}/transitions", issue_id).as_str())?;
+        let response = self.api_get(format!("issue/{}/transitions", issue_id.into()).as_str())?;
 
-        let issue_transitions: IssueTransitionDescriptors = response.into_json()?;
+        let issue_transitions: IssueTransitionDescriptors = response.json()?;
 
         Ok(issue_transitions)
     }
 
     pub fn transition_issue(
-        &mut self,
-        issue_id: &str,
-        transition: IssueTransitionDescriptor,
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        mut transition: IssueTransitionDescriptor,
     ) -> Result<(), crate::Error> {
+        let issue_id = issue_id.into();
+
+        let hooks = self.hooks();
+        for hook in &hooks {
+            hook.before_transition(&issue_id, &mut transition)
+                .map_err(|veto| veto.into_error(Operation::TransitionIssue))?;
+        }
+
+        self.authorize_on_issue(Operation::TransitionIssue, &issue_id)?;
+
         let transition = IssueTransition { transition };
         self.api_post(
             format!("issue/{}/transitions", issue_id).as_str(),
-            transition,
+            &transition,
         )?;
 
+        self.after_hooks(&hooks, |hook| {
+            hook.after_transition(&issue_id, &transition.transition)
+        });
+
         Ok(())
     }
 
-    pub fn create_project(
-        &mut self,
-        project: ProjectCreate,
-    ) -> Result<ProjectCreated, crate::Error> {
+    pub fn add_comment(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        mut body: AtlassianDoc,
+    ) -> Result<Comment, crate::Error> {
+        let issue_id = issue_id.into();
+
+        let hooks = self.hooks();
+        for hook in &hooks {
+            hook.before_comment(&issue_id, &mut body)
+                .map_err(|veto| veto.into_error(Operation::AddComment))?;
+        }
+
+        self.authorize_on_issue(Operation::AddComment, &issue_id)?;
+
+        let response = self.api_post(
+            format!("issue/{}/comment", issue_id).as_str(),
+            CommentCreation { body },
+        )?;
+
+        let comment: Comment = response.json()?;
+
+        self.after_hooks(&hooks, |hook| hook.after_comment(&issue_id, &comment));
+
+        Ok(comment)
+    }
+
+    pub fn create_project(&self, project: ProjectCreate) -> Result<ProjectCreated, crate::Error> {
+        self.policy()
+            .check(Operation::CreateProject, Some(project.key.as_str()))?;
+
         let response = self.api_post("project", project)?;
 
-        let new_project: ProjectCreated = response.into_json()?;
+        let new_project: ProjectCreated = response.json()?;
 
         Ok(new_project)
     }
 
-    pub fn edit_project<'a>(
-        &mut self,
-        project_id: impl Into<&'a str>,
+    pub fn edit_project(
+        &self,
+        project_id: impl Into<ProjectIdOrKey>,
         project: ProjectEdit,
     ) -> Result<(), crate::Error> {
-        self.api_put(format!("project/{}", project_id.into()).as_str(), project)?;
+        let project_id = project_id.into();
+        self.policy().check(Operation::EditProject, None)?;
+        if self.policy().restricts_projects() {
+            let key = self.project_key(&project_id)?;
+            self.policy().check(Operation::EditProject, Some(&key))?;
+        }
+
+        self.api_put(format!("project/{}", project_id).as_str(), project)?;
 
         Ok(())
     }
 
-    pub fn delete_project<'a>(
-        &mut self,
-        project_id: impl Into<&'a str>,
+    /// Delete a project. Unless the policy says otherwise, this needs a
+    /// [`Confirmation`], see [`Jirascope::delete_project_confirmed`].
+    pub fn delete_project(
+        &self,
+        project_id: impl Into<ProjectIdOrKey>,
     ) -> Result<(), crate::Error> {
-        self.api_delete(format!("project/{}", project_id.into()).as_str())?;
+        self.delete_project_checked(&project_id.into(), None)
+    }
 
-        Ok(())
+    /// Delete a project, confirmed by its key.
+    pub fn delete_project_confirmed(
+        &self,
+        project_id: impl Into<ProjectIdOrKey>,
+        confirmation: &Confirmation,
+    ) -> Result<(), crate::Error> {
+        self.delete_project_checked(&project_id.into(), Some(confirmation))
     }
 
-    pub fn get_project_categories(&mut self) -> Result<Vec<ProjectCategory>, crate::Error> {
-        let response = self.api_get("projectCategory")?;
+    fn delete_project_checked(
+        &self,
+        project_id: &ProjectIdOrKey,
+        confirmation: Option<&Confirmation>,
+    ) -> Result<(), crate::Error> {
+        let policy = self.policy();
+        policy.check(Operation::DeleteProject, None)?;
+
+        if policy.restricts_projects() || policy.requires_confirmation(Guarded::DeleteProject) {
+            let key = self.project_key(project_id)?;
+            policy.check(Operation::DeleteProject, Some(&key))?;
+            policy.check_confirmed(
+                Guarded::DeleteProject,
+                Operation::DeleteProject,
+                &key,
+                confirmation,
+            )?;
+        }
 
-        let project_categories: Vec<ProjectCategory> = response.into_json()?;
+        self.api_delete(format!("project/{}", project_id).as_str())?;
 
-        Ok(project_categories)
+        Ok(())
     }
 
-    pub fn get_issue_security_schemes(
-        &mut self,
-    ) -> Result<Vec<ProjectIssueSecurityScheme>, crate::Error> {
-        let response = self.api_get("issuesecurityschemes")?;
+    /// Filters the current user marked as favourite.
+    pub fn get_favourite_filters(&self) -> Pages<'_, Filter> {
+        Pages::new(self, "filter/favourite", PageStyle::Single)
+    }
 
-        let issue_security_schemes: Vec<ProjectIssueSecurityScheme> = response.into_json()?;
+    /// Filters owned by the current user.
+    pub fn get_my_filters(&self) -> Pages<'_, Filter> {
+        Pages::new(self, "filter/my", PageStyle::Single)
+    }
 
-        Ok(issue_security_schemes)
+    /// Filters visible to the current user whose name contains `name`,
+    /// ignoring case.
+    pub fn search_filters(&self, name: &str) -> Pages<'_, Filter> {
+        Pages::new(
+            self,
+            format!(
+                "filter/search?filterName={}&expand=description,favourite,jql,owner,sharePermissions",
+                utils::url_encode(name)
+            ),
+            PageStyle::Values,
+        )
     }
 
-    pub fn get_field_configuration_schemes(
-        &mut self,
-    ) -> Result<Vec<FieldConfigurationScheme>, crate::Error> {
-        let field_configuration_schemes: Vec<FieldConfigurationScheme> =
-            self.api_get_depaginated("fieldconfigurationscheme")?;
+    pub fn get_filter(&self, filter_id: i64) -> Result<Filter, crate::Error> {
+        let filter = self
+            .api_get(format!("filter/{}", filter_id).as_str())?
+            .json()?;
 
-        Ok(field_configuration_schemes)
+        Ok(filter)
     }
 
-    fn api_get(&mut self, path: &str) -> Result<ureq::Response, crate::Error> {
-        match self
-            .auth
-            .auth(ureq::get(
-                format!("{}/rest/api/3/{}", &self.config.api_url, path).as_str(),
-            ))
-            .call()
+    /// The filter called `name`. A filter whose name only contains `name`
+    /// is taken if it's the only one.
+    pub fn get_filter_by_name(&self, name: &str) -> Result<Filter, crate::Error> {
+        let mut filters = self.search_filters(name).fetch_all()?;
+
+        if let Some(i) = filters
+            .iter()
+            .position(|f| f.name.eq_ignore_ascii_case(name))
         {
-            Ok(response) => Ok(response),
-            Err(error) => match error {
-                ureq::Error::Status(code, response) => {
-                    Err(crate::Error::Jira(code, response.into_json()?))
-                }
-                ureq::Error::Transport(e) => {
-                    Err(crate::Error::Ureq(Box::new(ureq::Error::Transport(e))))
-                }
-            },
+            return Ok(filters.swap_remove(i));
+        }
+
+        match filters.len() {
+            1 => Ok(filters.remove(0)),
+            0 => Err(crate::Error::jirascope(format!(
+                "Filter {} not found",
+                name
+            ))),
+            _ => Err(crate::Error::jirascope(format!(
+                "Several filters match {}: {}",
+                name,
+                filters
+                    .iter()
+                    .map(|f| f.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
         }
     }
 
-    fn api_get_depaginated<T>(&mut self, path: &str) -> Result<Vec<T>, crate::Error>
-    where
-        for<'a> T: serde::Deserialize<'a>,
-    {
-        let mut results: Vec<T> = Vec::new();
-        let mut start_at = 0;
-        let max_results = 50;
+    /// The issues `filter` finds, see [`Jirascope::search_issues`].
+    pub fn run_filter(&self, filter: &Filter) -> Pages<'_, Issue> {
+        self.search_issues(&filter.jql)
+    }
+
+    pub fn create_filter(&self, filter: FilterDetails) -> Result<Filter, crate::Error> {
+        self.policy().check(Operation::CreateFilter, None)?;
 
-        loop {
-            let response = self.api_get(
-                format!("{}?startAt={}&maxResults={}", path, start_at, max_results).as_str(),
-            )?;
+        let filter = self.api_post("filter", filter)?.json()?;
+
+        Ok(filter)
+    }
+
+    pub fn edit_filter(
+        &self,
+        filter_id: i64,
+        filter: FilterDetails,
+    ) -> Result<Filter, crate::Error> {
+        self.policy().check(Operation::EditFilter, None)?;
+
+        let filter = self
+            .api_put(format!("filter/{}", filter_id).as_str(), filter)?
+            .json()?;
+
+        Ok(filter)
+    }
 
-            let paginated: Paginated<T> = response.into_json()?;
+    pub fn delete_filter(&self, filter_id: i64) -> Result<(), crate::Error> {
+        self.policy().check(Operation::DeleteFilter, None)?;
 
-            results.extend(paginated.values);
+        self.api_delete(format!("filter/{}", filter_id).as_str())?;
 
-            if paginated.is_last {
-                break;
+        Ok(())
+    }
+
+    pub fn get_filter_share_permissions(&self, filter_id: i64) -> Pages<'_, SharePermission> {
+        Pages::new(
+            self,
+            format!("filter/{}/permission", filter_id),
+            PageStyle::Single,
+        )
+    }
+
+    /// Share a filter. Returns who it's shared with now.
+    pub fn share_filter(
+        &self,
+        filter_id: i64,
+        share: FilterShare,
+    ) -> Result<Vec<SharePermission>, crate::Error> {
+        self.policy().check(Operation::EditFilter, None)?;
+
+        let permissions = self
+            .api_post(format!("filter/{}/permission", filter_id).as_str(), share)?
+            .json()?;
+
+        Ok(permissions)
+    }
+
+    /// Stop sharing a filter with the holder of the permission `permission_id`.
+    pub fn unshare_filter(&self, filter_id: i64, permission_id: i64) -> Result<(), crate::Error> {
+        self.policy().check(Operation::EditFilter, None)?;
+
+        self.api_delete(format!("filter/{}/permission/{}", filter_id, permission_id).as_str())?;
+
+        Ok(())
+    }
+
+    pub fn get_service_desks(&self) -> Pages<'_, ServiceDesk> {
+        Pages::new(
+            self,
+            format!("{}/servicedesk", SERVICE_DESK_API),
+            PageStyle::ServiceDesk,
+        )
+    }
+
+    pub fn get_service_desk(&self, service_desk_id: i64) -> Result<ServiceDesk, crate::Error> {
+        let service_desk = self
+            .api_get(format!("{}/servicedesk/{}", SERVICE_DESK_API, service_desk_id).as_str())?
+            .json()?;
+
+        Ok(service_desk)
+    }
+
+    pub fn get_request_types(&self, service_desk_id: i64) -> Pages<'_, RequestType> {
+        Pages::new(
+            self,
+            format!(
+                "{}/servicedesk/{}/requesttype",
+                SERVICE_DESK_API, service_desk_id
+            ),
+            PageStyle::ServiceDesk,
+        )
+    }
+
+    /// The requests of a service desk the current user can see.
+    pub fn get_customer_requests(&self, service_desk_id: i64) -> Pages<'_, CustomerRequest> {
+        Pages::new(
+            self,
+            format!(
+                "{}/request?serviceDeskId={}&requestOwnership=ALL_REQUESTS",
+                SERVICE_DESK_API, service_desk_id
+            ),
+            PageStyle::ServiceDesk,
+        )
+    }
+
+    pub fn get_customer_request(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+    ) -> Result<CustomerRequest, crate::Error> {
+        let request = self
+            .api_get(format!("{}/request/{}", SERVICE_DESK_API, issue_id.into()).as_str())?
+            .json()?;
+
+        Ok(request)
+    }
+
+    /// Raise a request. Hooks don't see it, it isn't an [`IssueCreation`].
+    pub fn create_customer_request(
+        &self,
+        request: CustomerRequestCreation,
+    ) -> Result<CustomerRequest, crate::Error> {
+        self.policy().check(Operation::CreateIssue, None)?;
+        if self.policy().restricts_projects() {
+            let service_desk = self.get_service_desk(request.service_desk_id)?;
+            self.policy()
+                .check(Operation::CreateIssue, Some(&service_desk.project_key))?;
+        }
+
+        let request = self
+            .api_post(format!("{}/request", SERVICE_DESK_API).as_str(), request)?
+            .json()?;
+
+        Ok(request)
+    }
+
+    /// Public and internal comments on a request, see [`RequestComment::public`].
+    pub fn get_request_comments(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+    ) -> Pages<'_, RequestComment> {
+        Pages::new(
+            self,
+            format!(
+                "{}/request/{}/comment?public=true&internal=true",
+                SERVICE_DESK_API,
+                issue_id.into()
+            ),
+            PageStyle::ServiceDesk,
+        )
+    }
+
+    /// Comment on a request, for the customer to see if `public`, otherwise
+    /// only for agents. Hooks don't see it, the body isn't an [`AtlassianDoc`].
+    pub fn add_request_comment(
+        &self,
+        issue_id: impl Into<IssueIdOrKey>,
+        body: &str,
+        public: bool,
+    ) -> Result<RequestComment, crate::Error> {
+        let issue_id = issue_id.into();
+        self.authorize_on_issue(Operation::AddComment, &issue_id)?;
+
+        let comment = self
+            .api_post(
+                format!("{}/request/{}/comment", SERVICE_DESK_API, issue_id).as_str(),
+                RequestCommentCreation {
+                    body: body.to_string(),
+                    public,
+                },
+            )?
+            .json()?;
+
+        Ok(comment)
+    }
+
+    /// The queues of a service desk, with how many issues each has.
+    pub fn get_queues(&self, service_desk_id: i64) -> Pages<'_, Queue> {
+        Pages::new(
+            self,
+            format!(
+                "{}/servicedesk/{}/queue?includeCount=true",
+                SERVICE_DESK_API, service_desk_id
+            ),
+            PageStyle::ServiceDesk,
+        )
+    }
+
+    pub fn get_queue_issues(&self, service_desk_id: i64, queue_id: i64) -> Pages<'_, QueueIssue> {
+        Pages::new(
+            self,
+            format!(
+                "{}/servicedesk/{}/queue/{}/issue",
+                SERVICE_DESK_API, service_desk_id, queue_id
+            ),
+            PageStyle::ServiceDesk,
+        )
+    }
+
+    /// How a request does against the SLAs of its service desk.
+    pub fn get_request_slas(&self, issue_id: impl Into<IssueIdOrKey>) -> Pages<'_, Sla> {
+        Pages::new(
+            self,
+            format!("{}/request/{}/sla", SERVICE_DESK_API, issue_id.into()),
+            PageStyle::ServiceDesk,
+        )
+    }
+
+    pub fn get_project_categories(&self) -> Pages<'_, ProjectCategory> {
+        Pages::new(self, "projectCategory", PageStyle::Single)
+    }
+
+    pub fn get_issue_security_schemes(&self) -> Pages<'_, ProjectIssueSecurityScheme> {
+        Pages::new(self, "issuesecurityschemes", PageStyle::Single)
+    }
+
+    pub fn get_field_configuration_schemes(&self) -> Pages<'_, FieldConfigurationScheme> {
+        Pages::new(self, "fieldconfigurationscheme", PageStyle::Values)
+    }
+
+    /// The hooks as they are now. They are run from a copy, so that a hook
+    /// can use the client, even to add another hook.
+    fn hooks(&self) -> Vec<Arc<dyn Hook>> {
+        self.hooks.read().unwrap().clone()
+    }
+
+    /// Run the after hooks of a change, unless dry-run mode held it back.
+    fn after_hooks(&self, hooks: &[Arc<dyn Hook>], run: impl Fn(&dyn Hook)) {
+        if self.dry_run() {
+            return;
+        }
+
+        for hook in hooks {
+            run(hook.as_ref());
+        }
+    }
+
+    /// Check a change to an issue. Its project is only looked up when the
+    /// policy cares about projects.
+    fn authorize_on_issue(
+        &self,
+        operation: Operation,
+        issue_id: &IssueIdOrKey,
+    ) -> Result<(), crate::Error> {
+        self.policy().check(operation, None)?;
+
+        if !self.policy().restricts_projects() {
+            return Ok(());
+        }
+
+        let project = match issue_id.key() {
+            Some(key) => key.project(),
+            None => self.issue_for_policy(issue_id)?.fields.project.key,
+        };
+        self.policy().check(operation, Some(&project))?;
+
+        Ok(())
+    }
+
+    fn authorize_in_project(
+        &self,
+        operation: Operation,
+        project_key: &ProjectKey,
+    ) -> Result<(), crate::Error> {
+        self.policy().check(operation, None)?;
+
+        if !self.policy().restricts_projects() {
+            return Ok(());
+        }
+
+        self.policy().check(operation, Some(project_key))?;
+
+        Ok(())
+    }
+
+    /// Just what the policy needs to know about an issue.
+    fn issue_for_policy(&self, issue_id: &IssueIdOrKey) -> Result<Issue, crate::Error> {
+        let mut fields = FieldSet::lean();
+        if let FieldSet::Only(ref mut fields) = fields {
+            fields.push(IssueField::Other("subtasks".to_string()));
+        }
+
+        self.get_issue_with(issue_id, &fields, &[])
+    }
+
+    /// The key of a project given by key or id.
+    fn project_key(&self, project_id: &ProjectIdOrKey) -> Result<ProjectKey, crate::Error> {
+        let id = match project_id {
+            ProjectIdOrKey::Key(key) => return Ok(key.clone()),
+            ProjectIdOrKey::Id(id) => *id,
+        };
+
+        for project in self.get_projects() {
+            let project = project?;
+            if project.id == id {
+                return Ok(project.key);
             }
+        }
+
+        Err(crate::Error::jirascope(format!(
+            "Project {} not found",
+            project_id
+        )))
+    }
 
-            start_at += max_results;
+    /// The URL of `path`, relative to the platform API unless it's absolute,
+    /// like `/rest/servicedeskapi/request`.
+    fn url(&self, path: &str) -> String {
+        if path.starts_with('/') {
+            format!("{}{}", &self.config.api_url, path)
+        } else {
+            format!("{}/rest/api/3/{}", &self.config.api_url, path)
         }
+    }
+
+    fn api_get(&self, path: &str) -> Result<ApiResponse, crate::Error> {
+        self.api_request("GET", path, None)
+    }
+
+    fn api_post(&self, path: &str, body: impl Serialize) -> Result<ApiResponse, crate::Error> {
+        self.api_change("POST", path, Some(to_json_value(body)?))
+    }
+
+    fn api_delete(&self, path: &str) -> Result<ApiResponse, crate::Error> {
+        self.api_change("DELETE", path, None)
+    }
 
-        Ok(results)
+    fn api_put(&self, path: &str, body: impl Serialize) -> Result<ApiResponse, crate::Error> {
+        self.api_change("PUT", path, Some(to_json_value(body)?))
     }
 
-    fn api_post(
-        &mut self,
+    /// Send a request which changes something, unless in dry-run mode.
+    fn api_change(
+        &self,
+        method: &str,
         path: &str,
-        body: impl Serialize,
-    ) -> Result<ureq::Response, crate::Error> {
-        match self
-            .auth
-            .auth(ureq::post(
-                format!("{}/rest/api/3/{}", &self.config.api_url, path).as_str(),
-            ))
-            .send_json(body)
-        {
-            Ok(response) => Ok(response),
-            Err(error) => match error {
-                ureq::Error::Status(code, response) => {
-                    Err(crate::Error::Jira(code, response.into_json()?))
-                }
-                ureq::Error::Transport(e) => {
-                    Err(crate::Error::Ureq(Box::new(ureq::Error::Transport(e))))
-                }
-            },
+        body: Option<serde_json::Value>,
+    ) -> Result<ApiResponse, crate::Error> {
+        if !self.dry_run() {
+            return self.api_request(method, path, body);
+        }
+
+        let response = dry_run::response(method, path, body.as_ref());
+        let request = DryRunRequest {
+            method: method.to_string(),
+            url: self.url(path),
+            body,
+        };
+        tracing::info!("dry run, not sending {}", request);
+
+        let mut requests = self.dry_run_requests.lock().unwrap();
+        if requests.len() == dry_run::MAX_KEPT_REQUESTS {
+            requests.pop_front();
         }
+        requests.push_back(request);
+
+        Ok(response)
     }
 
-    fn api_delete(&mut self, path: &str) -> Result<ureq::Response, crate::Error> {
-        match self
-            .auth
-            .auth(ureq::delete(
-                format!("{}/rest/api/3/{}", &self.config.api_url, path).as_str(),
-            ))
-            .call()
-        {
-            Ok(response) => Ok(response),
-            Err(error) => match error {
-                ureq::Error::Status(code, response) => {
-                    Err(crate::Error::Jira(code, response.into_json()?))
-                }
-                ureq::Error::Transport(e) => {
-                    Err(crate::Error::Ureq(Box::new(ureq::Error::Transport(e))))
-                }
-            },
+    fn api_request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<ApiResponse, crate::Error> {
+        if method != "GET" || !self.config.http_cache {
+            return self.send(method, path, body, None);
+        }
+
+        let (response, outcome) = self
+            .cache
+            .get(path, |validators| self.send(method, path, None, validators))?;
+
+        if outcome != CacheOutcome::Miss {
+            tracing::debug!(?outcome, "GET {} served from cache", path);
         }
+
+        Ok(response)
     }
 
-    fn api_put(
-        &mut self,
+    fn send(
+        &self,
+        method: &str,
         path: &str,
-        body: impl Serialize,
-    ) -> Result<ureq::Response, crate::Error> {
-        match self
-            .auth
-            .auth(ureq::put(
-                format!("{}/rest/api/3/{}", &self.config.api_url, path).as_str(),
-            ))
-            .send_json(body)
-        {
-            Ok(response) => Ok(response),
-            Err(error) => match error {
-                ureq::Error::Status(code, response) => {
-                    Err(crate::Error::Jira(code, response.into_json()?))
-                }
-                ureq::Error::Transport(e) => {
-                    Err(crate::Error::Ureq(Box::new(ureq::Error::Transport(e))))
-                }
-            },
+        body: Option<serde_json::Value>,
+        validators: Option<&Validators>,
+    ) -> Result<ApiResponse, crate::Error> {
+        let span = tracing::debug_span!(
+            "jira_request",
+            method,
+            path,
+            status = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+            response_size = tracing::field::Empty,
+        );
+        let _entered = span.enter();
+
+        let url = self.url(path);
+        let mut request = self.auth.auth(self.agent.request(method, &url));
+
+        if let Some(validators) = validators {
+            if let Some(etag) = &validators.etag {
+                request = request.set("If-None-Match", etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.set("If-Modified-Since", last_modified);
+            }
+        }
+
+        if self.config.dump_bodies {
+            tracing::trace!(
+                headers = ?trace::redacted_headers(&request),
+                body = %body
+                    .as_ref()
+                    .map(|body| trace::redact_body(&body.to_string()))
+                    .unwrap_or_default(),
+                "{} {}",
+                method,
+                url,
+            );
+        }
+
+        let start = Instant::now();
+        let result = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+        let result = match result {
+            Ok(response) => ApiResponse::from_ureq(response),
+            Err(error) => Err(error.into()),
+        };
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        let (status, response_body) = match &result {
+            Ok(response) => (Some(response.status), Some(response.body.as_str())),
+            Err(error) => (error.status(), error.body().map(|body| body.raw.as_str())),
+        };
+
+        if let Some(status) = status {
+            span.record("status", status);
+        }
+        if let Some(response_body) = response_body {
+            span.record("response_size", response_body.len());
+        }
+        span.record("latency_ms", latency_ms);
+
+        match &result {
+            Ok(_) => tracing::debug!("{} {} finished", method, path),
+            Err(error) => tracing::debug!(%error, "{} {} failed", method, path),
+        }
+
+        if self.config.dump_bodies {
+            if let Some(response_body) = response_body {
+                tracing::trace!(body = %trace::redact_body(response_body), "response");
+            }
         }
+
+        result
+    }
+}
+
+fn to_json_value(body: impl Serialize) -> Result<serde_json::Value, crate::Error> {
+    serde_json::to_value(body).map_err(|e| crate::Error::jirascope(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_can_be_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Jirascope>();
     }
 }
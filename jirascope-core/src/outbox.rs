@@ -0,0 +1,669 @@
+//! Durable queue of changes that couldn't be sent.
+//!
+//! Changes made while the server can't be reached are appended to the outbox
+//! and written to disk, then replayed in order once it's back. Each item
+//! remembers the `updated` timestamp of the issue it was made against, so a
+//! replay won't silently overwrite what someone else changed in the meantime.
+
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    fs::{File, OpenOptions},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use ureq::serde_json;
+
+use crate::{
+    jira::{AtlassianDoc, IssueEdit, IssueKey, IssueTransitionDescriptor, JiraDateTime},
+    utils::{file_name_for, normalize_api_url, write_atomic},
+    Config, Jirascope,
+};
+
+/// Bumped whenever the outbox layout changes.
+/// Unlike a snapshot, an outbox of another version is an error, not dropped.
+pub const OUTBOX_VERSION: u32 = 1;
+
+/// A change to an issue which can be sent later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mutation {
+    EditIssue {
+        issue_key: IssueKey,
+        edit: Box<IssueEdit>, // IssueEdit is Big
+    },
+    TransitionIssue {
+        issue_key: IssueKey,
+        transition: IssueTransitionDescriptor,
+    },
+    AddComment {
+        issue_key: IssueKey,
+        body: AtlassianDoc,
+    },
+}
+
+impl Mutation {
+    pub fn issue_key(&self) -> &IssueKey {
+        match self {
+            Mutation::EditIssue { issue_key, .. }
+            | Mutation::TransitionIssue { issue_key, .. }
+            | Mutation::AddComment { issue_key, .. } => issue_key,
+        }
+    }
+
+    /// Send the change to the server.
+    pub fn apply(&self, jirascope: &Jirascope) -> Result<(), crate::Error> {
+        match self {
+            Mutation::EditIssue { issue_key, edit } => {
+                jirascope.edit_issue(issue_key, edit.as_ref().clone())
+            }
+            Mutation::TransitionIssue {
+                issue_key,
+                transition,
+            } => jirascope.transition_issue(issue_key, transition.clone()),
+            Mutation::AddComment { issue_key, body } => {
+                jirascope.add_comment(issue_key, body.clone()).map(|_| ())
+            }
+        }
+    }
+
+    /// Send the change, unless the issue was updated since
+    /// `expected_updated`, which fails with [`crate::Error::Conflict`].
+    pub fn apply_if_unchanged(
+        &self,
+        jirascope: &Jirascope,
+        expected_updated: &JiraDateTime,
+    ) -> Result<(), crate::Error> {
+        match self {
+            Mutation::EditIssue { issue_key, edit } => jirascope.edit_issue_if_unchanged(
+                issue_key,
+                expected_updated,
+                edit.as_ref().clone(),
+            ),
+            _ => {
+                jirascope.check_unchanged(&self.issue_key().into(), expected_updated)?;
+                self.apply(jirascope)
+            }
+        }
+    }
+}
+
+impl Display for Mutation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mutation::EditIssue { issue_key, .. } => write!(f, "Edit {}", issue_key),
+            Mutation::TransitionIssue {
+                issue_key,
+                transition,
+            } => write!(f, "Transition {} to {}", issue_key, transition.to.name),
+            Mutation::AddComment { issue_key, .. } => write!(f, "Comment on {}", issue_key),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ItemStatus {
+    /// Waiting to be sent.
+    Pending,
+    /// The issue changed on the server since the change was made.
+    Conflict { current_updated: JiraDateTime },
+    /// The server rejected the change.
+    Failed { message: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxItem {
+    pub id: u64,
+    pub mutation: Mutation,
+    /// `updated` of the issue the change was made against.
+    /// `None` sends the change without checking for conflicts.
+    pub base_updated: Option<JiraDateTime>,
+    /// Seconds since the Unix epoch.
+    pub queued_at: u64,
+    pub status: ItemStatus,
+}
+
+/// What a [`Outbox::replay`] did.
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    /// Items sent and removed from the outbox.
+    pub applied: usize,
+    /// Items left in the outbox because they, or an earlier change to the
+    /// same issue, need attention.
+    pub blocked: usize,
+    /// Set if the server couldn't be reached, replay stopped there.
+    pub error: Option<crate::Error>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OutboxFile {
+    version: u32,
+    api_url: String,
+    next_id: u64,
+    items: Vec<OutboxItem>,
+}
+
+/// Queued changes for one Jira instance.
+///
+/// Items for the same issue are sent strictly in order. Once an item
+/// conflicts or is rejected, later changes to that issue wait until it's
+/// retried, forced or discarded.
+///
+/// Several processes may share an outbox file. Every change locks it and
+/// starts from what's on disk, so none of them overwrites the others' items,
+/// and only one of them replays at a time, the others wait for it.
+#[derive(Debug)]
+pub struct Outbox {
+    path: Option<PathBuf>,
+    api_url: String,
+    next_id: u64,
+    items: Vec<OutboxItem>,
+}
+
+impl Outbox {
+    /// An outbox which is lost when dropped.
+    pub fn in_memory(api_url: impl Into<String>) -> Outbox {
+        Outbox {
+            path: None,
+            api_url: api_url.into(),
+            next_id: 1,
+            items: Vec::new(),
+        }
+    }
+
+    /// Open the outbox kept in `cache_dir`, creating it on first write.
+    pub fn open(
+        cache_dir: impl Into<PathBuf>,
+        api_url: impl Into<String>,
+    ) -> Result<Outbox, crate::Error> {
+        let api_url = api_url.into();
+        let path = cache_dir
+            .into()
+            .join(format!("outbox-{}.json", file_name_for(&api_url)));
+
+        let mut outbox = Outbox {
+            path: Some(path),
+            ..Outbox::in_memory(api_url)
+        };
+        outbox.reload()?;
+
+        Ok(outbox)
+    }
+
+    /// The outbox in [`Config::cache_dir`], or an in-memory one without it.
+    pub fn from_config(config: &Config) -> Result<Outbox, crate::Error> {
+        match config.cache_dir {
+            Some(ref dir) => Outbox::open(dir, config.api_url.clone()),
+            None => Ok(Outbox::in_memory(config.api_url.clone())),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn items(&self) -> &[OutboxItem] {
+        &self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Whether a change to `issue_key` would have to wait for earlier ones.
+    pub fn has_pending_for(&self, issue_key: &str) -> bool {
+        self.items
+            .iter()
+            .any(|item| item.mutation.issue_key() == issue_key)
+    }
+
+    /// Queue `mutation`, returning the id of the new item.
+    pub fn push(
+        &mut self,
+        mutation: Mutation,
+        base_updated: Option<JiraDateTime>,
+    ) -> Result<u64, crate::Error> {
+        let _lock = self.lock()?;
+        self.reload()?;
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.items.push(OutboxItem {
+            id,
+            mutation,
+            base_updated,
+            queued_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            status: ItemStatus::Pending,
+        });
+
+        self.save()?;
+
+        Ok(id)
+    }
+
+    /// Drop an item without sending it.
+    pub fn discard(&mut self, id: u64) -> Result<Option<OutboxItem>, crate::Error> {
+        let _lock = self.lock()?;
+        self.reload()?;
+
+        let index = match self.items.iter().position(|item| item.id == id) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+
+        let item = self.items.remove(index);
+        self.save()?;
+
+        Ok(Some(item))
+    }
+
+    /// Make a conflicting or rejected item pending again.
+    /// Conflicts are checked again on the next replay.
+    pub fn retry(&mut self, id: u64) -> Result<bool, crate::Error> {
+        self.update(id, |item| item.status = ItemStatus::Pending)
+    }
+
+    /// Make an item pending and send it on the next replay even if the issue
+    /// changed on the server.
+    pub fn force(&mut self, id: u64) -> Result<bool, crate::Error> {
+        self.update(id, |item| {
+            item.status = ItemStatus::Pending;
+            item.base_updated = None;
+        })
+    }
+
+    /// Send pending items in order.
+    ///
+    /// Stops at the first error that looks like a connectivity problem,
+    /// leaving that item and everything after it queued. Does nothing in
+    /// dry-run mode, where items would look sent and be dropped.
+    pub fn replay(&mut self, jirascope: &Jirascope) -> ReplayReport {
+        let mut report = ReplayReport::default();
+
+        if jirascope.dry_run() {
+            report.blocked = self.items.len();
+            return report;
+        }
+
+        // held until the end, so no other process sends the same items
+        let _lock = match self.lock() {
+            Ok(lock) => lock,
+            Err(e) => {
+                report.error = Some(e);
+                return report;
+            }
+        };
+        if let Err(e) = self.reload() {
+            report.error = Some(e);
+            return report;
+        }
+        let mut blocked_keys = HashSet::new();
+        let mut i = 0;
+
+        while i < self.items.len() {
+            let key = self.items[i].mutation.issue_key().to_string();
+
+            if blocked_keys.contains(&key) || self.items[i].status != ItemStatus::Pending {
+                blocked_keys.insert(key);
+                report.blocked += 1;
+                i += 1;
+                continue;
+            }
+
+            let result = match self.items[i].base_updated {
+                Some(ref base_updated) => self.items[i]
+                    .mutation
+                    .apply_if_unchanged(jirascope, base_updated),
+                None => self.items[i].mutation.apply(jirascope),
+            };
+
+            match result {
+                Ok(()) => {
+                    let item = self.items.remove(i);
+                    report.applied += 1;
+
+                    if let Err(e) = self.rebase(jirascope, &item) {
+                        report.error = Some(e);
+                        break;
+                    }
+                }
+                Err(crate::Error::Conflict { current }) => {
+                    self.items[i].status = ItemStatus::Conflict {
+                        current_updated: current.fields.updated,
+                    };
+                    blocked_keys.insert(key);
+                    report.blocked += 1;
+                    i += 1;
+                }
+                Err(e) if e.is_retryable() => {
+                    report.error = Some(e);
+                    break;
+                }
+                Err(e) => {
+                    self.items[i].status = ItemStatus::Failed {
+                        message: e.to_string(),
+                    };
+                    blocked_keys.insert(key);
+                    report.blocked += 1;
+                    i += 1;
+                }
+            }
+        }
+
+        // whatever is left after an interruption still waits
+        if report.error.is_some() {
+            report.blocked = self.items.len();
+        }
+
+        if let Err(e) = self.save() {
+            report.error.get_or_insert(e);
+        }
+
+        report
+    }
+
+    /// Later changes to the same issue were made on top of `applied`, move
+    /// them onto the server's new `updated` so they don't conflict with it.
+    fn rebase(&mut self, jirascope: &Jirascope, applied: &OutboxItem) -> Result<(), crate::Error> {
+        let key = applied.mutation.issue_key();
+
+        let needs_rebase = applied.base_updated.is_some()
+            && self.items.iter().any(|item| {
+                item.mutation.issue_key() == key && item.base_updated == applied.base_updated
+            });
+
+        if !needs_rebase {
+            return Ok(());
+        }
+
+        let updated = match jirascope.get_issue(key) {
+            Ok(issue) => issue.fields.updated,
+            Err(e) if e.is_retryable() => return Err(e),
+            // the conflict check will report it
+            Err(_) => return Ok(()),
+        };
+
+        for item in self.items.iter_mut() {
+            if item.mutation.issue_key() == key && item.base_updated == applied.base_updated {
+                item.base_updated = Some(updated);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update(&mut self, id: u64, f: impl FnOnce(&mut OutboxItem)) -> Result<bool, crate::Error> {
+        let _lock = self.lock()?;
+        self.reload()?;
+
+        match self.items.iter_mut().find(|item| item.id == id) {
+            Some(item) => f(item),
+            None => return Ok(false),
+        }
+
+        self.save()?;
+
+        Ok(true)
+    }
+
+    /// The lock is on a file next to the outbox, which is replaced rather
+    /// than written to. It's released when the returned file is dropped.
+    fn lock(&self) -> Result<Option<File>, crate::Error> {
+        let path = match self.path {
+            Some(ref path) => path.with_extension("lock"),
+            None => return Ok(None),
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        file.lock()?;
+
+        Ok(Some(file))
+    }
+
+    /// Replace the items with what's on disk, which other processes may have
+    /// changed since.
+    fn reload(&mut self) -> Result<(), crate::Error> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        // never drop queued changes silently, unlike the snapshot
+        let file: OutboxFile = serde_json::from_str(&content).map_err(|e| {
+            crate::Error::jirascope(format!("Unreadable outbox {}: {}", path.display(), e))
+        })?;
+
+        if file.version != OUTBOX_VERSION {
+            return Err(crate::Error::jirascope(format!(
+                "Outbox {} has version {}, expected {}",
+                path.display(),
+                file.version,
+                OUTBOX_VERSION
+            )));
+        }
+
+        // a save would overwrite the other instance's changes
+        if normalize_api_url(&file.api_url) != normalize_api_url(&self.api_url) {
+            return Err(crate::Error::jirascope(format!(
+                "Outbox {} belongs to {}, not {}",
+                path.display(),
+                file.api_url,
+                self.api_url
+            )));
+        }
+
+        self.next_id = self.next_id.max(file.next_id);
+        self.items = file.items;
+
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), crate::Error> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+
+        let file = OutboxFile {
+            version: OUTBOX_VERSION,
+            api_url: self.api_url.clone(),
+            next_id: self.next_id,
+            items: self.items.clone(),
+        };
+
+        let content =
+            serde_json::to_string(&file).map_err(|e| crate::Error::jirascope(e.to_string()))?;
+
+        write_atomic(path, content.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jira::IssueEditFields, Auth};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("jirascope-outbox-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn edit(key: &str, summary: &str) -> Mutation {
+        Mutation::EditIssue {
+            issue_key: key.parse().unwrap(),
+            edit: Box::new(IssueEdit {
+                fields: IssueEditFields {
+                    summary: Some(summary.to_string()),
+                    ..Default::default()
+                },
+            }),
+        }
+    }
+
+    #[test]
+    fn items_survive_reopening() {
+        let dir = temp_dir("reopen");
+        let url = "https://example.atlassian.net";
+        let updated: JiraDateTime = "2023-05-02T10:00:00.000+0000".parse().unwrap();
+
+        let mut outbox = Outbox::open(&dir, url).unwrap();
+        let first = outbox.push(edit("ABC-1", "First"), None).unwrap();
+        outbox.push(edit("ABC-2", "Second"), Some(updated)).unwrap();
+        outbox.discard(first).unwrap();
+
+        let mut outbox = Outbox::open(&dir, url).unwrap();
+        assert_eq!(outbox.items().len(), 1);
+        assert_eq!(outbox.items()[0].mutation.issue_key(), "ABC-2");
+        assert_eq!(outbox.items()[0].base_updated, Some(updated));
+
+        // ids aren't reused after a restart
+        assert!(outbox.push(edit("ABC-3", "Third"), None).unwrap() > first + 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_outbox_is_an_error() {
+        let dir = temp_dir("unreadable");
+        let url = "https://example.atlassian.net";
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("outbox-example_atlassian_net.json"),
+            "{\"version\":1,",
+        )
+        .unwrap();
+
+        assert!(Outbox::open(&dir, url).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn outbox_belongs_to_one_instance() {
+        let dir = temp_dir("instance");
+        let url = "https://example.atlassian.net";
+
+        let mut outbox = Outbox::open(&dir, url).unwrap();
+        outbox.push(edit("ABC-1", "First"), None).unwrap();
+
+        // a trailing slash names the same instance
+        let outbox = Outbox::open(&dir, format!("{}/", url)).unwrap();
+        assert_eq!(outbox.items().len(), 1);
+
+        // another instance whose outbox would have the same file name
+        assert!(Outbox::open(&dir, "http://example.atlassian.net").is_err());
+        assert_eq!(Outbox::open(&dir, url).unwrap().items().len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn force_skips_the_conflict_check() {
+        let mut outbox = Outbox::in_memory("https://example.atlassian.net");
+        let id = outbox
+            .push(
+                edit("ABC-1", "First"),
+                Some("2023-05-01T10:00:00.000+0000".parse().unwrap()),
+            )
+            .unwrap();
+        outbox.items[0].status = ItemStatus::Conflict {
+            current_updated: "2023-05-02T10:00:00.000+0000".parse().unwrap(),
+        };
+
+        assert!(outbox.force(id).unwrap());
+        assert_eq!(outbox.items()[0].status, ItemStatus::Pending);
+        assert!(outbox.items()[0].base_updated.is_none());
+        assert!(!outbox.force(id + 1).unwrap());
+    }
+
+    #[test]
+    fn replay_keeps_items_while_unreachable() {
+        // nothing listens on port 1
+        let jirascope = Jirascope::new(
+            Config::new("http://127.0.0.1:1"),
+            Auth::new("username", "api_token"),
+        );
+        let mut outbox = Outbox::in_memory("http://127.0.0.1:1");
+        outbox.push(edit("ABC-1", "First"), None).unwrap();
+        outbox.push(edit("ABC-1", "Second"), None).unwrap();
+
+        let report = outbox.replay(&jirascope);
+
+        assert_eq!(report.applied, 0);
+        assert_eq!(report.blocked, 2);
+        assert!(report.error.unwrap().is_retryable());
+        assert!(outbox
+            .items()
+            .iter()
+            .all(|item| item.status == ItemStatus::Pending));
+    }
+
+    #[test]
+    fn blocked_items_hold_back_their_issue_only() {
+        let jirascope = Jirascope::new(
+            Config::new("http://127.0.0.1:1"),
+            Auth::new("username", "api_token"),
+        );
+        let mut outbox = Outbox::in_memory("http://127.0.0.1:1");
+        outbox.push(edit("ABC-1", "First"), None).unwrap();
+        outbox.push(edit("ABC-1", "Second"), None).unwrap();
+        outbox.push(edit("ABC-2", "Other"), None).unwrap();
+        outbox.items[0].status = ItemStatus::Failed {
+            message: "Rejected".to_string(),
+        };
+
+        let report = outbox.replay(&jirascope);
+
+        // ABC-1 is held back, ABC-2 is attempted and hits the network
+        assert!(report.error.is_some());
+        assert_eq!(outbox.items()[1].status, ItemStatus::Pending);
+        assert_eq!(outbox.items().len(), 3);
+    }
+
+    #[test]
+    fn processes_sharing_an_outbox_keep_each_others_items() {
+        let dir = temp_dir("shared");
+        let url = "https://example.atlassian.net";
+
+        let mut first = Outbox::open(&dir, url).unwrap();
+        let mut second = Outbox::open(&dir, url).unwrap();
+        let one = first.push(edit("ABC-1", "First"), None).unwrap();
+        let two = second.push(edit("ABC-2", "Second"), None).unwrap();
+        let three = first.push(edit("ABC-3", "Third"), None).unwrap();
+        assert!(one < two && two < three);
+
+        second.discard(one).unwrap();
+        first.retry(three).unwrap();
+
+        let ids = |outbox: &Outbox| outbox.items().iter().map(|i| i.id).collect::<Vec<_>>();
+        assert_eq!(ids(&first), [two, three]);
+        assert_eq!(ids(&Outbox::open(&dir, url).unwrap()), [two, three]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
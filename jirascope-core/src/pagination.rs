@@ -0,0 +1,460 @@
+//! Lazy iteration over paginated endpoints.
+//!
+//! Jira doesn't paginate consistently. Depending on the endpoint a page is
+//! an object with `startAt`, `total` and the items under an endpoint specific
+//! key, an object with `isLast` and the items under `values`, a bare array
+//! which is paged with `startAt` and `maxResults`, or an object carrying a
+//! `nextPageToken` for the following page. The service desk API pages with
+//! `start` and `limit` instead. [`Pages`] hides all of that behind one
+//! iterator.
+
+use std::collections::VecDeque;
+
+use serde::de::DeserializeOwned;
+use ureq::serde_json::{self, Value};
+
+use crate::{
+    jira::{JqlSearchResults, Paginated, ServiceDeskPage},
+    response::ApiResponse,
+    utils, Jirascope,
+};
+
+/// How an endpoint splits its results into pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PageStyle {
+    /// `startAt`, `total` and the items under the given key, like `issues`
+    /// for `search` or `issueTypes` for `issue/createmeta/{project}/issuetypes`.
+    Total(&'static str),
+    /// `isLast` and the items under `values`, like `project/search`.
+    Values,
+    /// A bare array, paged with `startAt` and `maxResults`, like
+    /// `users/search`. The last page is the first one which isn't full.
+    Array,
+    /// `nextPageToken` and the items under `issues`, like `search/jql`.
+    Cursor,
+    /// `isLastPage` and the items under `values`, paged with `start` and
+    /// `limit`, like everything under `/rest/servicedeskapi`.
+    ServiceDesk,
+    /// Not paginated at all, everything comes in a single response.
+    Single,
+}
+
+impl PageStyle {
+    fn default_page_size(self) -> usize {
+        match self {
+            PageStyle::Cursor => 100,
+            PageStyle::Total(_)
+            | PageStyle::Values
+            | PageStyle::Array
+            | PageStyle::ServiceDesk
+            | PageStyle::Single => 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Cursor {
+    Offset(usize),
+    Token(Option<String>),
+    Done,
+}
+
+#[derive(Debug)]
+struct Page<T> {
+    items: Vec<T>,
+    next: Cursor,
+}
+
+/// Results of a list endpoint, fetched a page at a time as they're iterated.
+///
+/// Nothing is requested until the first item is asked for. A failed request
+/// is yielded as an error and ends the iteration.
+///
+/// ```no_run
+/// # fn example(jirascope: &jirascope_core::Jirascope) -> Result<(), jirascope_core::Error> {
+/// for user in jirascope.get_users().with_page_size(100) {
+///     println!("{}", user?.display_name);
+/// }
+///
+/// let first_ten = jirascope.search_issues("project = ABC ORDER BY created DESC").with_limit(10).fetch_all()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Pages<'a, T> {
+    jirascope: &'a Jirascope,
+    pager: Pager<T>,
+}
+
+impl<'a, T: DeserializeOwned> Pages<'a, T> {
+    pub(crate) fn new(jirascope: &'a Jirascope, path: impl Into<String>, style: PageStyle) -> Self {
+        Pages {
+            jirascope,
+            pager: Pager {
+                path: path.into(),
+                style,
+                page_size: style.default_page_size(),
+                limit: None,
+                cursor: match style {
+                    PageStyle::Cursor => Cursor::Token(None),
+                    _ => Cursor::Offset(0),
+                },
+                buffer: VecDeque::new(),
+                yielded: 0,
+                full_page: None,
+            },
+        }
+    }
+
+    /// Ask for `page_size` items per request. Jira caps it per endpoint, and
+    /// silently returns smaller pages when asked for more.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.pager = self.pager.with_page_size(page_size);
+        self
+    }
+
+    /// Stop after `limit` items, without fetching pages past them.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.pager = self.pager.with_limit(limit);
+        self
+    }
+
+    /// Fetch every remaining page.
+    pub fn fetch_all(self) -> Result<Vec<T>, crate::Error> {
+        self.collect()
+    }
+
+    /// The paging state without the client, for [`crate::AsyncPages`].
+    #[cfg(feature = "async")]
+    pub(crate) fn into_pager(self) -> Pager<T> {
+        self.pager
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for Pages<'_, T> {
+    type Item = Result<T, crate::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pager.needs_page() {
+            if let Err(e) = self.pager.fetch_page(self.jirascope) {
+                return Some(Err(e));
+            }
+        }
+
+        self.pager.pop().map(Ok)
+    }
+}
+
+/// Where a [`Pages`] is at, and the items fetched but not yet yielded.
+#[derive(Debug)]
+pub(crate) struct Pager<T> {
+    path: String,
+    style: PageStyle,
+    page_size: usize,
+    limit: Option<usize>,
+    cursor: Cursor,
+    buffer: VecDeque<T>,
+    yielded: usize,
+    /// How many items the server puts in a page which isn't the last, once
+    /// a page came back. It may be fewer than asked for.
+    full_page: Option<usize>,
+}
+
+impl<T: DeserializeOwned> Pager<T> {
+    pub(crate) fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    pub(crate) fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        self.limit.map(|limit| limit.saturating_sub(self.yielded))
+    }
+
+    /// Whether the next item has to be fetched first.
+    pub(crate) fn needs_page(&self) -> bool {
+        self.remaining() != Some(0) && self.buffer.is_empty() && self.cursor != Cursor::Done
+    }
+
+    /// Fetch the next page. A failed request ends the iteration.
+    pub(crate) fn fetch_page(&mut self, jirascope: &Jirascope) -> Result<(), crate::Error> {
+        let result = self.try_fetch_page(jirascope);
+
+        if result.is_err() {
+            self.cursor = Cursor::Done;
+            self.buffer.clear();
+        }
+
+        result
+    }
+
+    fn try_fetch_page(&mut self, jirascope: &Jirascope) -> Result<(), crate::Error> {
+        let page_size = match self.remaining() {
+            Some(remaining) => self.page_size.min(remaining),
+            None => self.page_size,
+        };
+
+        let separator = if self.path.contains('?') { '&' } else { '?' };
+        let path = match (&self.cursor, self.style) {
+            (_, PageStyle::Single) => self.path.clone(),
+            (Cursor::Offset(start), PageStyle::ServiceDesk) => format!(
+                "{}{}start={}&limit={}",
+                self.path, separator, start, page_size
+            ),
+            (Cursor::Offset(start_at), _) => format!(
+                "{}{}startAt={}&maxResults={}",
+                self.path, separator, start_at, page_size
+            ),
+            (Cursor::Token(Some(token)), _) => format!(
+                "{}{}maxResults={}&nextPageToken={}",
+                self.path,
+                separator,
+                page_size,
+                utils::url_encode(token)
+            ),
+            (Cursor::Token(None), _) => {
+                format!("{}{}maxResults={}", self.path, separator, page_size)
+            }
+            (Cursor::Done, _) => return Ok(()),
+        };
+
+        let response = jirascope.api_get(&path)?;
+        let page = parse_page(self.style, &self.cursor, self.full_page, &response)?;
+
+        let count = page.items.len();
+        if count > 0 {
+            self.full_page = Some(self.full_page.map_or(count, |full| full.max(count)));
+        }
+        self.buffer.extend(page.items);
+        self.cursor = page.next;
+
+        Ok(())
+    }
+
+    /// The next fetched item, if there is one and the limit allows it.
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        if self.remaining() == Some(0) {
+            return None;
+        }
+
+        let item = self.buffer.pop_front()?;
+        self.yielded += 1;
+
+        Some(item)
+    }
+}
+
+/// Jira caps page sizes without saying so, a page shorter than the ones
+/// before it is the last, but a short first page may be a capped one.
+fn parse_page<T: DeserializeOwned>(
+    style: PageStyle,
+    cursor: &Cursor,
+    full_page: Option<usize>,
+    response: &ApiResponse,
+) -> Result<Page<T>, crate::Error> {
+    let start_at = match cursor {
+        Cursor::Offset(start_at) => *start_at,
+        _ => 0,
+    };
+    // an empty page means there's nothing more, whatever the page claims
+    let after = |count: usize, last: bool| {
+        if count == 0 || last {
+            Cursor::Done
+        } else {
+            Cursor::Offset(start_at + count)
+        }
+    };
+
+    let page = match style {
+        PageStyle::Total(key) => {
+            let mut page: serde_json::Map<String, Value> = response.json()?;
+            let items: Vec<T> = response.decode(page.remove(key).unwrap_or_default())?;
+            let count = items.len();
+            let last = match page.get("total").and_then(Value::as_u64) {
+                Some(total) => (start_at + count) as u64 >= total,
+                None => full_page.is_some_and(|full| count < full),
+            };
+
+            Page {
+                next: after(count, last),
+                items,
+            }
+        }
+        PageStyle::Values => {
+            let page: Paginated<T> = response.json()?;
+
+            Page {
+                next: after(page.values.len(), page.is_last),
+                items: page.values,
+            }
+        }
+        PageStyle::Array => {
+            let items: Vec<T> = response.json()?;
+
+            Page {
+                next: after(
+                    items.len(),
+                    full_page.is_some_and(|full| items.len() < full),
+                ),
+                items,
+            }
+        }
+        PageStyle::ServiceDesk => {
+            let page: ServiceDeskPage<T> = response.json()?;
+
+            Page {
+                next: after(page.values.len(), page.is_last_page),
+                items: page.values,
+            }
+        }
+        PageStyle::Cursor => {
+            let page: JqlSearchResults<T> = response.json()?;
+
+            Page {
+                next: match page.next_page_token {
+                    Some(token) if !page.issues.is_empty() && page.is_last != Some(true) => {
+                        Cursor::Token(Some(token))
+                    }
+                    _ => Cursor::Done,
+                },
+                items: page.issues,
+            }
+        }
+        PageStyle::Single => Page {
+            items: response.json()?,
+            next: Cursor::Done,
+        },
+    };
+
+    Ok(page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str) -> ApiResponse {
+        ApiResponse {
+            status: 200,
+            body: body.to_string(),
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    fn parse(style: PageStyle, cursor: Cursor, body: &str) -> Page<u32> {
+        parse_page(style, &cursor, Some(2), &response(body)).unwrap()
+    }
+
+    #[test]
+    fn offset_styles_advance_by_item_count() {
+        let page = parse(
+            PageStyle::Total("issues"),
+            Cursor::Offset(2),
+            r#"{"startAt": 2, "maxResults": 2, "total": 5, "issues": [3, 4]}"#,
+        );
+        assert_eq!(page.items, vec![3, 4]);
+        assert_eq!(page.next, Cursor::Offset(4));
+
+        let page = parse(
+            PageStyle::Values,
+            Cursor::Offset(0),
+            r#"{"startAt": 0, "maxResults": 2, "isLast": false, "values": [1, 2]}"#,
+        );
+        assert_eq!(page.next, Cursor::Offset(2));
+
+        let page = parse(PageStyle::Array, Cursor::Offset(0), "[1, 2]");
+        assert_eq!(page.next, Cursor::Offset(2));
+
+        let page = parse(
+            PageStyle::ServiceDesk,
+            Cursor::Offset(2),
+            r#"{"size": 2, "start": 2, "limit": 2, "isLastPage": false, "values": [3, 4]}"#,
+        );
+        assert_eq!(page.next, Cursor::Offset(4));
+    }
+
+    #[test]
+    fn last_pages_are_detected() {
+        let page = parse(
+            PageStyle::Total("issues"),
+            Cursor::Offset(4),
+            r#"{"startAt": 4, "maxResults": 2, "total": 5, "issues": [5]}"#,
+        );
+        assert_eq!(page.next, Cursor::Done);
+
+        let page = parse(
+            PageStyle::Values,
+            Cursor::Offset(0),
+            r#"{"startAt": 0, "maxResults": 2, "isLast": true, "values": [1, 2]}"#,
+        );
+        assert_eq!(page.next, Cursor::Done);
+
+        let page = parse(PageStyle::Array, Cursor::Offset(2), "[3]");
+        assert_eq!(page.next, Cursor::Done);
+
+        let page = parse(
+            PageStyle::ServiceDesk,
+            Cursor::Offset(0),
+            r#"{"size": 2, "start": 0, "limit": 2, "isLastPage": true, "values": [1, 2]}"#,
+        );
+        assert_eq!(page.next, Cursor::Done);
+
+        // no total, so a short page is the last one
+        let page = parse(
+            PageStyle::Total("issueTypes"),
+            Cursor::Offset(0),
+            r#"{"startAt": 0, "maxResults": 2, "issueTypes": [1]}"#,
+        );
+        assert_eq!(page.items, vec![1]);
+        assert_eq!(page.next, Cursor::Done);
+
+        // claims more, but an empty page would repeat forever
+        let page = parse(
+            PageStyle::Total("issues"),
+            Cursor::Offset(4),
+            r#"{"startAt": 4, "maxResults": 2, "total": 10, "issues": []}"#,
+        );
+        assert_eq!(page.next, Cursor::Done);
+    }
+
+    #[test]
+    fn short_first_pages_may_be_capped() {
+        for (style, body) in [
+            (PageStyle::Array, "[1, 2]"),
+            (
+                PageStyle::Total("issueTypes"),
+                r#"{"startAt": 0, "maxResults": 2, "issueTypes": [1, 2]}"#,
+            ),
+        ] {
+            let page: Page<u32> =
+                parse_page(style, &Cursor::Offset(0), None, &response(body)).unwrap();
+            assert_eq!(page.next, Cursor::Offset(2));
+        }
+
+        let page: Page<u32> =
+            parse_page(PageStyle::Array, &Cursor::Offset(2), None, &response("[]")).unwrap();
+        assert_eq!(page.next, Cursor::Done);
+    }
+
+    #[test]
+    fn cursor_follows_next_page_token() {
+        let page = parse(
+            PageStyle::Cursor,
+            Cursor::Token(None),
+            r#"{"issues": [1, 2], "nextPageToken": "abc", "isLast": false}"#,
+        );
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.next, Cursor::Token(Some("abc".to_string())));
+
+        let page = parse(
+            PageStyle::Cursor,
+            Cursor::Token(Some("abc".to_string())),
+            r#"{"issues": [3]}"#,
+        );
+        assert_eq!(page.next, Cursor::Done);
+    }
+}
@@ -0,0 +1,288 @@
+//! Guard rails for changes.
+//!
+//! Every change the client makes is checked against its [`Policy`] first.
+//! The policy can forbid changes altogether, limit them to some projects,
+//! and hold back dangerous operations until the user explicitly confirms
+//! them with a [`Confirmation`].
+
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// A kind of change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    CreateIssue,
+    EditIssue,
+    TransitionIssue,
+    AddComment,
+    DeleteIssue,
+    CreateProject,
+    EditProject,
+    DeleteProject,
+    CreateFilter,
+    /// Editing a filter, sharing it included.
+    EditFilter,
+    DeleteFilter,
+    /// Several changes made at once, e.g. by a script.
+    Bulk,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operation::CreateIssue => "create issue",
+            Operation::EditIssue => "edit issue",
+            Operation::TransitionIssue => "transition issue",
+            Operation::AddComment => "add comment",
+            Operation::DeleteIssue => "delete issue",
+            Operation::CreateProject => "create project",
+            Operation::EditProject => "edit project",
+            Operation::DeleteProject => "delete project",
+            Operation::CreateFilter => "create filter",
+            Operation::EditFilter => "edit filter",
+            Operation::DeleteFilter => "delete filter",
+            Operation::Bulk => "bulk change",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Operations which can be made to need a [`Confirmation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Guarded {
+    DeleteProject,
+    /// Deleting an issue which has subtasks, which deletes them too.
+    DeleteIssueWithSubtasks,
+    Bulk,
+}
+
+/// The user's explicit go-ahead for a guarded operation.
+///
+/// Frontends make one from what the user typed when asked to confirm, which
+/// has to be the target of the operation: the project key when deleting a
+/// project, the issue key when deleting an issue with subtasks, or the number
+/// of changes for a bulk change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Confirmation {
+    target: String,
+}
+
+impl Confirmation {
+    pub fn new(target: impl Into<String>) -> Confirmation {
+        Confirmation {
+            target: target.into(),
+        }
+    }
+
+    fn confirms(&self, target: &str) -> bool {
+        self.target.trim().eq_ignore_ascii_case(target)
+    }
+}
+
+/// Which changes the client may make.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// Forbid every change.
+    pub read_only: bool,
+    /// Keys of the projects changes are allowed in, `None` allows all.
+    pub allowed_projects: Option<Vec<String>>,
+    /// Keys of projects no changes are allowed in, whatever
+    /// `allowed_projects` says.
+    pub denied_projects: Vec<String>,
+    /// Operations which need a [`Confirmation`].
+    pub confirm: Vec<Guarded>,
+}
+
+impl Default for Policy {
+    /// Everything is allowed, but deletions of projects and of issues with
+    /// subtasks and bulk changes need confirming.
+    fn default() -> Self {
+        Policy {
+            read_only: false,
+            allowed_projects: None,
+            denied_projects: Vec::new(),
+            confirm: vec![
+                Guarded::DeleteProject,
+                Guarded::DeleteIssueWithSubtasks,
+                Guarded::Bulk,
+            ],
+        }
+    }
+}
+
+/// Why the policy blocked a change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    ReadOnly {
+        operation: Operation,
+    },
+    ProjectNotAllowed {
+        operation: Operation,
+        project: String,
+    },
+    /// Retry with a [`Confirmation`] of `target`.
+    ConfirmationRequired {
+        operation: Operation,
+        target: String,
+    },
+}
+
+impl Display for PolicyViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyViolation::ReadOnly { operation } => {
+                write!(f, "Can't {}, jirascope is read-only", operation)
+            }
+            PolicyViolation::ProjectNotAllowed { operation, project } => {
+                write!(
+                    f,
+                    "Can't {}, changes to {} aren't allowed",
+                    operation, project
+                )
+            }
+            PolicyViolation::ConfirmationRequired { operation, target } => {
+                write!(f, "Confirm {} by entering {}", operation, target)
+            }
+        }
+    }
+}
+
+impl Policy {
+    /// Whether checking an operation needs to know its project.
+    pub(crate) fn restricts_projects(&self) -> bool {
+        self.allowed_projects.is_some() || !self.denied_projects.is_empty()
+    }
+
+    pub fn requires_confirmation(&self, guarded: Guarded) -> bool {
+        !self.read_only && self.confirm.contains(&guarded)
+    }
+
+    /// Check whether `operation` may be done in `project`, `None` when it
+    /// isn't known or doesn't matter.
+    pub fn check(
+        &self,
+        operation: Operation,
+        project: Option<&str>,
+    ) -> Result<(), PolicyViolation> {
+        if self.read_only {
+            return Err(PolicyViolation::ReadOnly { operation });
+        }
+
+        let project = match project {
+            Some(project) => project,
+            None => return Ok(()),
+        };
+
+        let denied = self
+            .denied_projects
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(project));
+        let allowed = match self.allowed_projects {
+            Some(ref allowed) => allowed.iter().any(|p| p.eq_ignore_ascii_case(project)),
+            None => true,
+        };
+
+        if denied || !allowed {
+            return Err(PolicyViolation::ProjectNotAllowed {
+                operation,
+                project: project.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check a guarded operation on `target`, see [`Confirmation`].
+    pub fn check_confirmed(
+        &self,
+        guarded: Guarded,
+        operation: Operation,
+        target: &str,
+        confirmation: Option<&Confirmation>,
+    ) -> Result<(), PolicyViolation> {
+        if !self.requires_confirmation(guarded)
+            || confirmation.map(|c| c.confirms(target)) == Some(true)
+        {
+            return Ok(());
+        }
+
+        Err(PolicyViolation::ConfirmationRequired {
+            operation,
+            target: target.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_only_blocks_everything() {
+        let policy = Policy {
+            read_only: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            policy.check(Operation::AddComment, None),
+            Err(PolicyViolation::ReadOnly {
+                operation: Operation::AddComment
+            })
+        );
+    }
+
+    #[test]
+    fn projects_are_allowed_and_denied() {
+        let policy = Policy {
+            allowed_projects: Some(vec!["ABC".to_string(), "DEF".to_string()]),
+            denied_projects: vec!["DEF".to_string()],
+            ..Default::default()
+        };
+
+        assert!(policy.check(Operation::EditIssue, Some("abc")).is_ok());
+        assert!(policy.check(Operation::EditIssue, Some("DEF")).is_err());
+        assert!(policy.check(Operation::EditIssue, Some("XYZ")).is_err());
+        assert!(Policy::default()
+            .check(Operation::EditIssue, Some("XYZ"))
+            .is_ok());
+    }
+
+    #[test]
+    fn guarded_operations_need_a_matching_confirmation() {
+        let policy = Policy::default();
+        let check = |confirmation: Option<&Confirmation>| {
+            policy.check_confirmed(
+                Guarded::DeleteProject,
+                Operation::DeleteProject,
+                "ABC",
+                confirmation,
+            )
+        };
+
+        assert!(matches!(
+            check(None),
+            Err(PolicyViolation::ConfirmationRequired { .. })
+        ));
+        assert!(check(Some(&Confirmation::new("XYZ"))).is_err());
+        assert!(check(Some(&Confirmation::new("ABC"))).is_ok());
+
+        let policy = Policy {
+            confirm: Vec::new(),
+            ..Default::default()
+        };
+        assert!(policy
+            .check_confirmed(
+                Guarded::DeleteProject,
+                Operation::DeleteProject,
+                "ABC",
+                None
+            )
+            .is_ok());
+    }
+}
@@ -0,0 +1,166 @@
+use serde::de::DeserializeOwned;
+use serde_path_to_error::{Path, Segment};
+use ureq::serde_json::{self, Value};
+
+/// How much of an undecodable body is kept in [`crate::Error::Decode`].
+const BODY_SNIPPET_LEN: usize = 512;
+
+/// A successful response with its body already read.
+///
+/// Reading the body up front lets decoding failures report the status and
+/// what the server actually sent, instead of a bare `InvalidData` IO error.
+#[derive(Debug, Clone)]
+pub(crate) struct ApiResponse {
+    pub status: u16,
+    pub body: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl ApiResponse {
+    pub fn from_ureq(response: ureq::Response) -> Result<ApiResponse, crate::Error> {
+        let status = response.status();
+        let etag = response.header("ETag").map(str::to_string);
+        let last_modified = response.header("Last-Modified").map(str::to_string);
+        let body = response.into_string()?;
+
+        Ok(ApiResponse {
+            status,
+            body,
+            etag,
+            last_modified,
+        })
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, crate::Error> {
+        let deserializer = &mut serde_json::Deserializer::from_str(&self.body);
+
+        serde_path_to_error::deserialize(deserializer).map_err(|error| {
+            // only parsed again once something went wrong
+            let root = serde_json::from_str(&self.body).ok();
+            self.decode_error(error, root.as_ref())
+        })
+    }
+
+    /// Decode `value`, a part of the already parsed body, reporting errors
+    /// like [`ApiResponse::json`] does.
+    pub fn decode<T: DeserializeOwned>(&self, value: Value) -> Result<T, crate::Error> {
+        serde_path_to_error::deserialize(&value)
+            .map_err(|error| self.decode_error(error, Some(&value)))
+    }
+
+    fn decode_error(
+        &self,
+        error: serde_path_to_error::Error<serde_json::Error>,
+        root: Option<&Value>,
+    ) -> crate::Error {
+        let path = error.path().clone();
+
+        crate::Error::Decode {
+            status: self.status,
+            body_snippet: snippet(&self.body),
+            // an empty path is the whole body, nothing worth pointing at
+            path: path.iter().next().map(|_| path.to_string()),
+            issue_key: root.and_then(|root| issue_key_at(root, &path)),
+            source: error.into_inner(),
+        }
+    }
+}
+
+/// Key of the innermost issue along `path`, so a bad field in a page of
+/// search results points at the issue it's in.
+fn issue_key_at(root: &Value, path: &Path) -> Option<String> {
+    let mut value = root;
+    let mut issue_key = None;
+
+    for segment in path.iter() {
+        if value.get("fields").is_some() {
+            if let Some(key) = value.get("key").and_then(Value::as_str) {
+                issue_key = Some(key.to_string());
+            }
+        }
+
+        let next = match segment {
+            Segment::Seq { index } => value.get(*index),
+            Segment::Map { key } => value.get(key),
+            _ => None,
+        };
+        value = match next {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    issue_key
+}
+
+pub(crate) fn snippet(body: &str) -> String {
+    match body.char_indices().nth(BODY_SNIPPET_LEN) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::jira::IssueRef;
+
+    use super::*;
+
+    #[test]
+    fn decode_error_keeps_status_and_body() {
+        let response = ApiResponse {
+            status: 200,
+            body: "<html>Bad Gateway</html>".to_string(),
+            etag: None,
+            last_modified: None,
+        };
+
+        match response.json::<Vec<String>>() {
+            Err(crate::Error::Decode {
+                status,
+                body_snippet,
+                ..
+            }) => {
+                assert_eq!(status, 200);
+                assert_eq!(body_snippet, "<html>Bad Gateway</html>");
+            }
+            other => panic!("Expected decode error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_error_names_issue_and_path() {
+        let response = ApiResponse {
+            status: 200,
+            body: r#"{"issues": [
+                {"id": "1", "key": "ABC-1", "fields": {}},
+                {"id": "two", "key": "ABC-2", "fields": {}}
+            ]}"#
+            .to_string(),
+            etag: None,
+            last_modified: None,
+        };
+
+        match response.json::<HashMap<String, Vec<IssueRef>>>() {
+            Err(crate::Error::Decode {
+                path, issue_key, ..
+            }) => {
+                assert_eq!(path.as_deref(), Some("issues[1].id"));
+                assert_eq!(issue_key.as_deref(), Some("ABC-2"));
+            }
+            other => panic!("Expected decode error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn snippet_truncates_on_char_boundary() {
+        let body = "ż".repeat(BODY_SNIPPET_LEN + 10);
+        let snippet = snippet(&body);
+
+        assert_eq!(snippet.chars().count(), BODY_SNIPPET_LEN + 3);
+        assert!(snippet.ends_with("..."));
+    }
+}
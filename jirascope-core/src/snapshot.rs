@@ -0,0 +1,178 @@
+//! On-disk snapshot of projects and issues.
+//!
+//! Lets a frontend show the last known state immediately on startup, and
+//! keep showing it while the server can't be reached.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use ureq::serde_json;
+
+use crate::{
+    jira::{Issue, ProjectDetailed},
+    utils::{file_name_for, normalize_api_url, write_atomic},
+    Config,
+};
+
+/// Bumped whenever the snapshot layout or the cached models change.
+/// Snapshots with a different version are ignored and overwritten.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    /// The instance the snapshot was taken from.
+    pub api_url: String,
+    /// Seconds since the Unix epoch.
+    pub saved_at: u64,
+    pub projects: Vec<ProjectDetailed>,
+    pub issues: Vec<Issue>,
+}
+
+/// A snapshot file for one Jira instance inside the cache directory.
+///
+/// Several processes may share the cache directory, the snapshot is always
+/// replaced atomically so readers never see a partial write.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    path: PathBuf,
+    api_url: String,
+}
+
+impl SnapshotStore {
+    pub fn new(cache_dir: impl Into<PathBuf>, api_url: impl Into<String>) -> SnapshotStore {
+        let api_url = api_url.into();
+        let path = cache_dir
+            .into()
+            .join(format!("snapshot-{}.json", file_name_for(&api_url)));
+
+        SnapshotStore { path, api_url }
+    }
+
+    /// The store configured by [`Config::cache_dir`], if any.
+    pub fn from_config(config: &Config) -> Option<SnapshotStore> {
+        config
+            .cache_dir
+            .as_ref()
+            .map(|dir| SnapshotStore::new(dir, config.api_url.clone()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the snapshot, if there is a usable one.
+    ///
+    /// Snapshots of another version or instance are treated as missing.
+    pub fn load(&self) -> Result<Option<Snapshot>, crate::Error> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        // an unreadable snapshot is as good as none, it will be overwritten
+        let snapshot = match serde_json::from_str::<Snapshot>(&content) {
+            Ok(snapshot) => snapshot,
+            Err(_) => return Ok(None),
+        };
+
+        if snapshot.version != SNAPSHOT_VERSION
+            || normalize_api_url(&snapshot.api_url) != normalize_api_url(&self.api_url)
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(snapshot))
+    }
+
+    pub fn save(&self, projects: &[ProjectDetailed], issues: &[Issue]) -> Result<(), crate::Error> {
+        let snapshot = Snapshot {
+            version: SNAPSHOT_VERSION,
+            api_url: self.api_url.clone(),
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            projects: projects.to_vec(),
+            issues: issues.to_vec(),
+        };
+
+        let content =
+            serde_json::to_string(&snapshot).map_err(|e| crate::Error::jirascope(e.to_string()))?;
+
+        write_atomic(&self.path, content.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ureq::serde_json::json;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "jirascope-snapshot-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn issue() -> Issue {
+        crate::test_utils::issue("ABC-1", json!({ "parent": { "id": "10000" } }))
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = temp_dir("roundtrip");
+        let store = SnapshotStore::new(&dir, "https://example.atlassian.net");
+
+        assert!(store.load().unwrap().is_none());
+
+        store.save(&[], &[issue()]).unwrap();
+
+        let snapshot = store.load().unwrap().unwrap();
+        assert_eq!(snapshot.issues, vec![issue()]);
+        assert!(snapshot.projects.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn other_instance_is_ignored() {
+        let dir = temp_dir("instance");
+        let store = SnapshotStore::new(&dir, "https://example.atlassian.net");
+        store.save(&[], &[issue()]).unwrap();
+
+        // same file name after sanitizing, different instance
+        let other = SnapshotStore::new(&dir, "http://example.atlassian.net");
+        assert_eq!(other.path(), store.path());
+        assert!(other.load().unwrap().is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn other_version_is_ignored() {
+        let dir = temp_dir("version");
+        let store = SnapshotStore::new(&dir, "https://example.atlassian.net");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            store.path(),
+            r#"{"version":1,"api_url":"https://example.atlassian.net","saved_at":0,"projects":[],"issues":[]}"#,
+        )
+        .unwrap();
+
+        assert!(store.load().unwrap().is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,281 @@
+//! Incremental synchronization of issues.
+//!
+//! The first sync downloads everything. Later ones only ask for issues
+//! updated since the previous sync, and merge them into the cached set.
+//! Deleted issues never show up as updated, so every now and then the keys
+//! of all issues are fetched, which is much cheaper than the issues
+//! themselves, and whatever is missing is dropped.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    jira::{Issue, IssueKey},
+    jql, FieldSet, Jirascope, Jql,
+};
+
+/// Extra time asked for on top of the time since the last sync, covering
+/// request latency and issues updated while the last sync was running.
+const SYNC_SKEW: Duration = Duration::from_secs(60);
+
+/// How often deletions are looked for.
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Every issue there is. `search/jql` refuses queries without any
+/// restriction, but every issue belongs to some project.
+pub(crate) fn all_issues_jql() -> Jql {
+    jql::project().is_not_empty().order_by(jql::key().asc())
+}
+
+/// Keys which changed during a sync.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncChanges {
+    /// Issues which weren't in the cached set.
+    pub added: Vec<IssueKey>,
+    /// Issues whose content changed.
+    pub updated: Vec<IssueKey>,
+    /// Issues which no longer exist, or can't be seen anymore.
+    pub removed: Vec<IssueKey>,
+}
+
+impl SyncChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+
+    /// Whether `key` was added, updated or removed.
+    pub fn contains(&self, key: &str) -> bool {
+        self.added
+            .iter()
+            .chain(self.updated.iter())
+            .chain(self.removed.iter())
+            .any(|k| k == key)
+    }
+}
+
+/// Keeps a set of issues up to date with the server.
+///
+/// Only the time of the last sync is remembered, the issues themselves are
+/// owned by the caller. Elapsed time is measured locally and sent as a
+/// relative JQL date (`-5m`), which the server resolves with its own clock,
+/// so neither clock skew nor time zones can make a sync miss updates.
+#[derive(Debug)]
+pub struct IssueSync {
+    last_sync: Option<Instant>,
+    last_reconcile: Option<Instant>,
+    skew: Duration,
+    reconcile_interval: Duration,
+    fields: FieldSet,
+}
+
+impl Default for IssueSync {
+    fn default() -> Self {
+        IssueSync::new()
+    }
+}
+
+impl IssueSync {
+    pub fn new() -> IssueSync {
+        IssueSync {
+            last_sync: None,
+            last_reconcile: None,
+            skew: SYNC_SKEW,
+            reconcile_interval: RECONCILE_INTERVAL,
+            fields: FieldSet::Navigable,
+        }
+    }
+
+    pub fn with_skew(mut self, skew: Duration) -> IssueSync {
+        self.skew = skew;
+        self
+    }
+
+    pub fn with_reconcile_interval(mut self, reconcile_interval: Duration) -> IssueSync {
+        self.reconcile_interval = reconcile_interval;
+        self
+    }
+
+    /// Fetch only `fields` of each issue. Changing them later only affects
+    /// issues fetched from then on, [`IssueSync::reset`] refetches the rest.
+    pub fn with_fields(mut self, fields: FieldSet) -> IssueSync {
+        self.fields = fields;
+        self
+    }
+
+    /// Forget the last sync, so the next one downloads everything again.
+    pub fn reset(&mut self) {
+        self.last_sync = None;
+        self.last_reconcile = None;
+    }
+
+    /// Bring `issues` up to date, returning what changed.
+    ///
+    /// On error `issues` is left as it was and the next sync covers the same
+    /// period again.
+    pub fn sync(
+        &mut self,
+        jirascope: &Jirascope,
+        issues: &mut Vec<Issue>,
+    ) -> Result<SyncChanges, crate::Error> {
+        let started = Instant::now();
+
+        let last_sync = match self.last_sync {
+            Some(last_sync) => last_sync,
+            None => {
+                let fetched = jirascope
+                    .search_issues_with(all_issues_jql(), &self.fields, &[])
+                    .fetch_all()?;
+                let changes = replace_all(issues, fetched);
+
+                self.last_sync = Some(started);
+                self.last_reconcile = Some(started);
+
+                return Ok(changes);
+            }
+        };
+
+        let jql = jql::updated()
+            .ge(format!(
+                "-{}m",
+                relative_minutes(started - last_sync, self.skew)
+            ))
+            .order_by(jql::key().asc());
+        let fetched = jirascope
+            .search_issues_with(jql, &self.fields, &[])
+            .fetch_all()?;
+
+        let reconcile = self
+            .last_reconcile
+            .map(|last| started - last >= self.reconcile_interval)
+            .unwrap_or(true);
+
+        let existing = if reconcile {
+            let keys = jirascope.search_issue_keys(all_issues_jql()).fetch_all()?;
+            Some(keys.into_iter().map(|r| r.key).collect::<HashSet<_>>())
+        } else {
+            None
+        };
+
+        let mut changes = merge(issues, fetched);
+
+        if let Some(existing) = existing {
+            changes.removed = remove_missing(issues, &existing);
+            self.last_reconcile = Some(started);
+        }
+
+        self.last_sync = Some(started);
+
+        Ok(changes)
+    }
+}
+
+/// Minutes to look back, rounded up so nothing falls between two syncs.
+fn relative_minutes(elapsed: Duration, skew: Duration) -> u64 {
+    (elapsed + skew).as_secs().div_ceil(60)
+}
+
+fn replace_all(issues: &mut Vec<Issue>, fetched: Vec<Issue>) -> SyncChanges {
+    let existing = fetched
+        .iter()
+        .map(|i| i.key.clone())
+        .collect::<HashSet<_>>();
+
+    let removed = remove_missing(issues, &existing);
+    let mut changes = merge(issues, fetched);
+    changes.removed = removed;
+
+    changes
+}
+
+fn merge(issues: &mut Vec<Issue>, fetched: Vec<Issue>) -> SyncChanges {
+    let mut changes = SyncChanges::default();
+    let mut index = issues
+        .iter()
+        .enumerate()
+        .map(|(i, issue)| (issue.key.clone(), i))
+        .collect::<HashMap<_, _>>();
+
+    for issue in fetched {
+        match index.get(&issue.key) {
+            Some(&i) if issues[i] != issue => {
+                changes.updated.push(issue.key.clone());
+                issues[i] = issue;
+            }
+            // fetched again because of the skew, nothing new
+            Some(_) => {}
+            None => {
+                changes.added.push(issue.key.clone());
+                index.insert(issue.key.clone(), issues.len());
+                issues.push(issue);
+            }
+        }
+    }
+
+    changes
+}
+
+fn remove_missing(issues: &mut Vec<Issue>, existing: &HashSet<IssueKey>) -> Vec<IssueKey> {
+    let mut removed = Vec::new();
+
+    issues.retain(|i| {
+        let keep = existing.contains(&i.key);
+        if !keep {
+            removed.push(i.key.clone());
+        }
+        keep
+    });
+
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use ureq::serde_json::json;
+
+    use super::*;
+
+    fn issue(key: &str, summary: &str) -> Issue {
+        crate::test_utils::issue(key, json!({ "summary": summary }))
+    }
+
+    #[test]
+    fn merge_reports_added_and_updated() {
+        let mut issues = vec![issue("ABC-1", "First"), issue("ABC-2", "Second")];
+
+        let changes = merge(
+            &mut issues,
+            vec![
+                issue("ABC-1", "First"),
+                issue("ABC-2", "Second, renamed"),
+                issue("ABC-3", "Third"),
+            ],
+        );
+
+        assert_eq!(changes.added, vec!["ABC-3"]);
+        assert_eq!(changes.updated, vec!["ABC-2"]);
+        assert!(changes.removed.is_empty());
+        assert_eq!(issues.len(), 3);
+        assert_eq!(issues[1].fields.summary, "Second, renamed");
+    }
+
+    #[test]
+    fn replace_all_reports_removed() {
+        let mut issues = vec![issue("ABC-1", "First"), issue("ABC-2", "Second")];
+
+        let changes = replace_all(&mut issues, vec![issue("ABC-2", "Second")]);
+
+        assert_eq!(changes.removed, vec!["ABC-1"]);
+        assert!(changes.added.is_empty());
+        assert!(changes.updated.is_empty());
+        assert_eq!(issues, vec![issue("ABC-2", "Second")]);
+    }
+
+    #[test]
+    fn relative_minutes_round_up() {
+        assert_eq!(relative_minutes(Duration::from_secs(30), SYNC_SKEW), 2);
+        assert_eq!(relative_minutes(Duration::from_secs(60), SYNC_SKEW), 2);
+        assert_eq!(relative_minutes(Duration::ZERO, Duration::ZERO), 0);
+    }
+}
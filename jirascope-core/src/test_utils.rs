@@ -0,0 +1,36 @@
+//! Fixtures shared by the unit tests.
+
+use ureq::serde_json::{self, json, Value};
+
+use crate::jira::Issue;
+
+/// An issue of project `ABC` with its required fields set, `fields` replace
+/// or add to them.
+pub(crate) fn issue(key: &str, fields: Value) -> Issue {
+    let mut base = json!({
+        "summary": "First issue",
+        "description": null,
+        "priority": { "name": "Medium" },
+        "status": { "id": "1", "name": "To Do" },
+        "assignee": null,
+        "reporter": {
+            "displayName": "Jane",
+            "active": true,
+            "accountId": "123",
+            "accountType": "atlassian"
+        },
+        "created": "2023-05-01T10:00:00.000+0000",
+        "updated": "2023-05-02T10:00:00.000+0000",
+        "project": { "id": "10000", "key": "ABC", "name": "Alphabet" }
+    });
+    base.as_object_mut()
+        .unwrap()
+        .extend(fields.as_object().unwrap().clone());
+
+    serde_json::from_value(json!({
+        "id": "10001",
+        "key": key,
+        "fields": base
+    }))
+    .unwrap()
+}
@@ -1,6 +1,13 @@
+use std::{
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
 use serde::Deserialize;
 use ureq::serde_json;
 
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 /// Deserialize a Jira ID.
 /// Sometimes Jira IDs are strings, sometimes they are integers.
 /// Convert both to i64.
@@ -30,6 +37,70 @@ where
     serializer.serialize_str(&id.to_string())
 }
 
+/// Percent-encode a query parameter value.
+pub(crate) fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+/// The instance URL without what doesn't change which instance it names,
+/// for comparing URLs written in different ways.
+pub(crate) fn normalize_api_url(api_url: &str) -> &str {
+    api_url.trim().trim_end_matches('/')
+}
+
+/// Turn an instance URL into something usable as part of a file name.
+/// Different URLs may share a name, so the URL is kept in the file too.
+pub(crate) fn file_name_for(api_url: &str) -> String {
+    normalize_api_url(api_url)
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Replace the file at `path` with `content`.
+///
+/// Writes go to a private temporary file which is then renamed over `path`,
+/// so readers only ever see a complete file and concurrent writers, even
+/// from other processes, can't interleave.
+pub(crate) fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let temp_path = path.with_file_name(temp_name);
+
+    if let Err(e) = std::fs::write(&temp_path, content) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    if let Err(e) = std::fs::rename(&temp_path, path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,6 +118,15 @@ mod tests {
         assert!(deserialize_id(serde_json::Value::Bool(true)).is_err());
     }
 
+    #[test]
+    fn test_url_encode() {
+        assert_eq!(
+            url_encode("updated >= \"-5m\" ORDER BY key"),
+            "updated%20%3E%3D%20%22-5m%22%20ORDER%20BY%20key"
+        );
+        assert_eq!(url_encode("zażółć"), "za%C5%BC%C3%B3%C5%82%C4%87");
+    }
+
     #[test]
     fn test_serialize_id() {
         let mut buf = Vec::new();
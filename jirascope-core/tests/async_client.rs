@@ -0,0 +1,134 @@
+#![cfg(feature = "async")]
+
+use std::net::TcpListener;
+
+use jirascope_core::{
+    jira::{IssueEdit, IssueEditFields, IssueKey},
+    AsyncJirascope, Auth, Config, Error,
+};
+
+fn start_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(jirascope_test_server::serve(listener));
+
+    url
+}
+
+fn key(key: &str) -> IssueKey {
+    key.parse().unwrap()
+}
+
+fn client(url: &str) -> AsyncJirascope {
+    AsyncJirascope::new(Config::new(url), Auth::new("test@example.com", "token"))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn init_fetches_server_and_user() {
+    let jirascope = client(&start_server());
+
+    jirascope.init().await.unwrap();
+
+    assert_eq!(
+        jirascope.server().unwrap().server_title,
+        "Jirascope test server"
+    );
+    assert_eq!(jirascope.current_user().unwrap().display_name, "Test User");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requests_run_concurrently() {
+    let jirascope = client(&start_server());
+
+    let (projects, issues, issue) = tokio::join!(
+        jirascope.get_projects().fetch_all(),
+        jirascope.search_issues("ORDER BY key ASC").fetch_all(),
+        jirascope.get_issue(key("TEST-2")),
+    );
+
+    assert_eq!(projects.unwrap()[0].key, "TEST");
+    assert_eq!(
+        issues.unwrap().len(),
+        jirascope_test_server::jira::ISSUE_COUNT
+    );
+    assert_eq!(issue.unwrap().fields.summary, "Test issue 2");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pages_are_fetched_as_awaited() {
+    let jirascope = client(&start_server());
+
+    let mut issues = jirascope
+        .search_issues("ORDER BY key ASC")
+        .with_page_size(2)
+        .with_limit(3);
+    let mut keys = Vec::new();
+    while let Some(issue) = issues.next().await {
+        keys.push(issue.unwrap().key);
+    }
+
+    assert_eq!(keys, [key("TEST-1"), key("TEST-2"), key("TEST-3")]);
+    assert!(issues.next().await.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dropping_a_pending_next_keeps_the_page() {
+    let jirascope = client(&start_server());
+
+    let mut issues = jirascope
+        .search_issues("ORDER BY key ASC")
+        .with_page_size(2)
+        .with_limit(3);
+    let mut keys = Vec::new();
+    // polls `next()` once, starting the first request, then drops it
+    tokio::select! {
+        biased;
+        issue = issues.next() => keys.push(issue.unwrap().unwrap().key),
+        _ = std::future::ready(()) => {}
+    }
+    while let Some(issue) = issues.next().await {
+        keys.push(issue.unwrap().key);
+    }
+
+    assert_eq!(keys, [key("TEST-1"), key("TEST-2"), key("TEST-3")]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn edits_are_applied() {
+    let jirascope = client(&start_server());
+
+    let edit = IssueEdit {
+        fields: IssueEditFields {
+            summary: Some("Renamed".to_string()),
+            ..Default::default()
+        },
+    };
+    jirascope.edit_issue(key("TEST-1"), edit).await.unwrap();
+
+    let issue = jirascope.get_issue(key("TEST-1")).await.unwrap();
+    assert_eq!(issue.fields.summary, "Renamed");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn errors_keep_their_type() {
+    let jirascope = client(&start_server());
+
+    match jirascope.get_issue(key("TEST-404")).await {
+        Err(Error::NotFound(_)) => {}
+        other => panic!("Expected not found, got {:?}", other),
+    }
+
+    let edit = IssueEdit {
+        fields: IssueEditFields {
+            summary: Some(String::new()),
+            ..Default::default()
+        },
+    };
+    match jirascope.edit_issue(key("TEST-1"), edit).await {
+        Err(Error::Validation { field_errors, .. }) => {
+            assert!(field_errors.contains_key("summary"));
+        }
+        other => panic!("Expected validation error, got {:?}", other),
+    }
+}
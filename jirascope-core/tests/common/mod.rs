@@ -0,0 +1,36 @@
+use std::net::TcpListener;
+
+use jirascope_core::{jira::IssueKey, Auth, Config, Jirascope};
+
+/// Start a test server, and a client talking to it. The server stops when
+/// the runtime is dropped.
+pub fn start_server() -> (tokio::runtime::Runtime, Jirascope) {
+    start_server_with(|_| {})
+}
+
+/// Like [`start_server`], with the client configured by `configure`.
+#[allow(dead_code)] // not every test uses it
+pub fn start_server_with(
+    configure: impl FnOnce(&mut Config),
+) -> (tokio::runtime::Runtime, Jirascope) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.spawn(jirascope_test_server::serve(listener));
+
+    let mut config = Config::new(url);
+    config.http_cache = false;
+    configure(&mut config);
+
+    (
+        runtime,
+        Jirascope::new(config, Auth::new("test@example.com", "token")),
+    )
+}
+
+/// An issue key of the test server, e.g. `TEST-1`.
+#[allow(dead_code)] // not every test uses it
+pub fn key(key: &str) -> IssueKey {
+    key.parse().unwrap()
+}
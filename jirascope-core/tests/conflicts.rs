@@ -0,0 +1,79 @@
+mod common;
+
+use common::{key, start_server};
+use jirascope_core::{
+    jira::{IssueEdit, IssueEditFields, JiraDateTime},
+    Error, ItemStatus, Mutation, Outbox,
+};
+
+/// When the test server's issues were created, and last updated.
+fn created() -> JiraDateTime {
+    // the same instant, written with another offset
+    "2023-05-01T12:00:00.000+0200".parse().unwrap()
+}
+
+fn summary(summary: &str) -> IssueEdit {
+    IssueEdit {
+        fields: IssueEditFields {
+            summary: Some(summary.to_string()),
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn stale_edits_are_rejected_with_the_current_issue() {
+    let (_server, jirascope) = start_server();
+
+    jirascope
+        .edit_issue_if_unchanged(key("TEST-1"), &created(), summary("First"))
+        .unwrap();
+
+    let current =
+        match jirascope.edit_issue_if_unchanged(key("TEST-1"), &created(), summary("Second")) {
+            Err(Error::Conflict { current }) => current,
+            other => panic!("expected a conflict, got {:?}", other),
+        };
+    assert_eq!(current.fields.summary, "First");
+
+    // merged into the current issue, the retry goes through
+    jirascope
+        .edit_issue_if_unchanged(key("TEST-1"), &current.fields.updated, summary("Second"))
+        .unwrap();
+    assert_eq!(
+        jirascope.get_issue(key("TEST-1")).unwrap().fields.summary,
+        "Second"
+    );
+}
+
+#[test]
+fn outbox_marks_stale_edits_as_conflicts() {
+    let (_server, jirascope) = start_server();
+
+    jirascope
+        .edit_issue(key("TEST-2"), summary("Changed"))
+        .unwrap();
+
+    let mut outbox = Outbox::in_memory("test");
+    outbox
+        .push(
+            Mutation::EditIssue {
+                issue_key: key("TEST-2"),
+                edit: Box::new(summary("Stale")),
+            },
+            Some(created()),
+        )
+        .unwrap();
+
+    let report = outbox.replay(&jirascope);
+
+    assert_eq!(report.applied, 0);
+    assert!(matches!(
+        outbox.items()[0].status,
+        ItemStatus::Conflict { .. }
+    ));
+    assert_eq!(
+        jirascope.get_issue(key("TEST-2")).unwrap().fields.summary,
+        "Changed"
+    );
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::{key, start_server};
+use jirascope_core::{
+    jira::{IssueEdit, IssueEditFields},
+    Mutation, Outbox,
+};
+
+#[test]
+fn dry_run_sends_nothing() {
+    let (_server, jirascope) = start_server();
+    jirascope.set_dry_run(true);
+
+    let edit = IssueEdit {
+        fields: IssueEditFields {
+            summary: Some("Not sent".to_string()),
+            ..Default::default()
+        },
+    };
+    jirascope.edit_issue(key("TEST-1"), edit).unwrap();
+    jirascope.delete_issue(key("TEST-2")).unwrap();
+
+    // reads still go through
+    let issue = jirascope.get_issue(key("TEST-1")).unwrap();
+    assert_eq!(issue.fields.summary, "Test issue 1");
+    assert!(jirascope.get_issue(key("TEST-2")).is_ok());
+
+    let requests = jirascope.take_dry_run_requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].method, "PUT");
+    assert!(requests[0].url.ends_with("/rest/api/3/issue/TEST-1"));
+    assert_eq!(
+        requests[0].body.as_ref().unwrap()["fields"]["summary"],
+        "Not sent"
+    );
+    assert_eq!(requests[1].method, "DELETE");
+    assert!(jirascope.take_dry_run_requests().is_empty());
+}
+
+#[test]
+fn dry_run_keeps_the_outbox() {
+    let (_server, jirascope) = start_server();
+    let mut outbox = Outbox::in_memory("test");
+    outbox
+        .push(
+            Mutation::EditIssue {
+                issue_key: key("TEST-1"),
+                edit: Box::new(IssueEdit {
+                    fields: IssueEditFields {
+                        summary: Some("Queued".to_string()),
+                        ..Default::default()
+                    },
+                }),
+            },
+            None,
+        )
+        .unwrap();
+    jirascope.set_dry_run(true);
+
+    let report = outbox.replay(&jirascope);
+
+    assert_eq!(report.applied, 0);
+    assert_eq!(report.blocked, 1);
+    assert_eq!(outbox.items().len(), 1);
+    assert!(jirascope.take_dry_run_requests().is_empty());
+
+    jirascope.set_dry_run(false);
+    assert_eq!(outbox.replay(&jirascope).applied, 1);
+    assert!(outbox.is_empty());
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{key, start_server};
+use jirascope_core::{Expand, FieldSet, IssueField};
+
+#[test]
+fn lean_search_leaves_out_descriptions() {
+    let (_server, jirascope) = start_server();
+
+    let issues = jirascope
+        .search_issues_with("project = TEST", &FieldSet::lean(), &[])
+        .fetch_all()
+        .unwrap();
+    assert!(issues.iter().all(|i| i.fields.description.is_none()));
+
+    let issue = jirascope.get_issue(key("TEST-1")).unwrap();
+    assert!(issue.fields.description.is_some());
+}
+
+#[test]
+fn expand_adds_rendered_fields() {
+    let (_server, jirascope) = start_server();
+
+    let mut fields = FieldSet::lean();
+    if let FieldSet::Only(fields) = &mut fields {
+        fields.push(IssueField::Description);
+    }
+
+    let issue = jirascope
+        .get_issue_with(key("TEST-2"), &fields, &[Expand::RenderedFields])
+        .unwrap();
+
+    assert!(issue.fields.description.is_some());
+    assert_eq!(
+        issue.rendered_fields.unwrap()["description"],
+        "<p>Description of issue 2</p>"
+    );
+}
+
+#[test]
+fn dates_are_parsed() {
+    let (_server, jirascope) = start_server();
+
+    let issue = jirascope.get_issue(key("TEST-1")).unwrap();
+
+    assert_eq!(issue.fields.duedate, Some("2023-05-10".parse().unwrap()));
+    assert_eq!(issue.fields.resolutiondate, None);
+    assert!(issue.fields.created <= issue.fields.updated);
+}
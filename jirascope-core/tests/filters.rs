@@ -0,0 +1,66 @@
+mod common;
+
+use common::start_server;
+use jirascope_core::{
+    jira::{FilterDetails, FilterShare},
+    Error,
+};
+
+#[test]
+fn filters_are_found_by_name_and_run() {
+    let (_server, jirascope) = start_server();
+
+    let favourites = jirascope.get_favourite_filters().fetch_all().unwrap();
+    assert_eq!(favourites.len(), 1);
+    assert_eq!(favourites[0].name, "Open test issues");
+
+    let filter = jirascope.get_filter_by_name("open test").unwrap();
+    assert_eq!(filter, favourites[0]);
+    assert!(jirascope.get_filter_by_name("nothing like it").is_err());
+
+    // the test server ignores JQL
+    let issues = jirascope.run_filter(&filter).fetch_all().unwrap();
+    assert_eq!(issues.len(), 3);
+}
+
+#[test]
+fn filters_are_created_shared_and_deleted() {
+    let (_server, jirascope) = start_server();
+
+    let filter = jirascope
+        .create_filter(FilterDetails {
+            name: "Bugs".to_string(),
+            jql: Some("issuetype = Bug".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(
+        jirascope.get_filter(filter.id).unwrap().jql,
+        "issuetype = Bug"
+    );
+    assert_eq!(jirascope.get_my_filters().fetch_all().unwrap().len(), 2);
+
+    let permissions = jirascope
+        .share_filter(filter.id, "project:10000".parse().unwrap())
+        .unwrap();
+    assert_eq!(permissions[0].to_string(), "project TEST");
+    jirascope
+        .share_filter(filter.id, FilterShare::Global)
+        .unwrap();
+
+    jirascope
+        .unshare_filter(filter.id, permissions[0].id.unwrap())
+        .unwrap();
+    let permissions = jirascope
+        .get_filter_share_permissions(filter.id)
+        .fetch_all()
+        .unwrap();
+    assert_eq!(permissions.len(), 1);
+    assert_eq!(permissions[0].to_string(), "everyone");
+
+    jirascope.delete_filter(filter.id).unwrap();
+    assert!(matches!(
+        jirascope.get_filter(filter.id),
+        Err(Error::NotFound(_))
+    ));
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use common::{key, start_server};
+use jirascope_core::{
+    jira::{IssueEdit, IssueEditFields, IssueIdOrKey},
+    Error, Hook, Operation, Veto,
+};
+
+fn summary(summary: &str) -> IssueEdit {
+    IssueEdit {
+        fields: IssueEditFields {
+            summary: Some(summary.to_string()),
+            ..Default::default()
+        },
+    }
+}
+
+/// Tags summaries, keeps TEST-2 from being deleted and counts edits.
+struct Conventions {
+    edits: Arc<AtomicUsize>,
+}
+
+impl Hook for Conventions {
+    fn before_edit(&self, _issue_id: &IssueIdOrKey, edit: &mut IssueEdit) -> Result<(), Veto> {
+        if let Some(ref mut summary) = edit.fields.summary {
+            summary.insert_str(0, "[team] ");
+        }
+
+        Ok(())
+    }
+
+    fn after_edit(&self, _issue_id: &IssueIdOrKey, _edit: &IssueEdit) {
+        self.edits.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn before_delete(&self, issue_id: &IssueIdOrKey) -> Result<(), Veto> {
+        match issue_id.key() {
+            Some(key) if key == "TEST-2" => Err(Veto::new("TEST-2 is kept for reference")),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn hooks_change_and_veto_changes() {
+    let (_server, jirascope) = start_server();
+    let edits = Arc::new(AtomicUsize::new(0));
+    jirascope.add_hook(Conventions {
+        edits: edits.clone(),
+    });
+
+    jirascope
+        .edit_issue(key("TEST-1"), summary("Renamed"))
+        .unwrap();
+    assert_eq!(
+        jirascope.get_issue(key("TEST-1")).unwrap().fields.summary,
+        "[team] Renamed"
+    );
+    assert_eq!(edits.load(Ordering::SeqCst), 1);
+
+    match jirascope.delete_issue(key("TEST-2")) {
+        Err(Error::Vetoed { operation, reason }) => {
+            assert_eq!(operation, Operation::DeleteIssue);
+            assert_eq!(reason, "TEST-2 is kept for reference");
+        }
+        other => panic!("expected a veto, got {:?}", other),
+    }
+    assert!(jirascope.get_issue(key("TEST-2")).is_ok());
+
+    // nothing is changed in dry-run mode, so nothing happened after it
+    jirascope.set_dry_run(true);
+    jirascope
+        .edit_issue(key("TEST-1"), summary("Again"))
+        .unwrap();
+    assert_eq!(edits.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::start_server;
+use jirascope_core::{jira::ProjectIdOrKey, Jql};
+use jirascope_test_server::jira::{ISSUE_COUNT, MAX_USER_PAGE, USER_COUNT};
+
+#[test]
+fn small_pages_fetch_everything() {
+    let (_server, jirascope) = start_server();
+
+    let users = jirascope.get_users().with_page_size(2).fetch_all().unwrap();
+    assert_eq!(users.len(), USER_COUNT);
+    assert_eq!(users[USER_COUNT - 1].display_name, "User 5");
+
+    let issues = jirascope
+        .search_issues("ORDER BY key ASC")
+        .with_page_size(2)
+        .fetch_all()
+        .unwrap();
+    assert_eq!(issues.len(), ISSUE_COUNT);
+
+    let projects = jirascope.get_projects().fetch_all().unwrap();
+    assert_eq!(projects[0].key, "TEST");
+}
+
+#[test]
+fn capped_pages_are_not_mistaken_for_the_last() {
+    let (_server, jirascope) = start_server();
+
+    let users = jirascope
+        .get_users()
+        .with_page_size(MAX_USER_PAGE + 1)
+        .fetch_all()
+        .unwrap();
+    assert_eq!(users.len(), USER_COUNT);
+}
+
+#[test]
+fn limit_stops_early() {
+    let (_server, jirascope) = start_server();
+
+    let users = jirascope
+        .get_users()
+        .with_page_size(2)
+        .with_limit(3)
+        .fetch_all()
+        .unwrap();
+    assert_eq!(
+        users
+            .iter()
+            .map(|u| u.display_name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["User 1", "User 2", "User 3"]
+    );
+
+    assert_eq!(jirascope.get_users().with_limit(0).count(), 0);
+}
+
+#[test]
+fn creation_meta_is_fetched_per_project() {
+    let (_server, jirascope) = start_server();
+    let project: ProjectIdOrKey = "TEST".parse().unwrap();
+
+    let issue_types = jirascope
+        .get_issue_creation_types(&project)
+        .with_page_size(2)
+        .fetch_all()
+        .unwrap();
+    assert_eq!(issue_types.len(), 3);
+
+    let fields = jirascope
+        .get_issue_creation_fields(&project, &issue_types[0].id)
+        .fetch_all()
+        .unwrap();
+    assert_eq!(fields[0].field_id, "summary");
+
+    #[allow(deprecated)]
+    let meta = jirascope.get_issue_creation_meta().unwrap();
+    assert_eq!(meta.projects[0].key, "TEST");
+    assert_eq!(meta.projects[0].issue_types.len(), 3);
+}
+
+#[test]
+fn approximate_count() {
+    let (_server, jirascope) = start_server();
+
+    assert_eq!(
+        jirascope.approximate_issue_count("project = TEST").unwrap(),
+        ISSUE_COUNT
+    );
+    assert_eq!(
+        jirascope
+            .approximate_issue_count(Jql::project("TEST"))
+            .unwrap(),
+        ISSUE_COUNT
+    );
+}
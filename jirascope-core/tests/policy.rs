@@ -0,0 +1,68 @@
+mod common;
+
+use common::{key, start_server, start_server_with};
+use jirascope_core::{
+    jira::{IssueEdit, IssueEditFields, IssueIdOrKey},
+    Confirmation, Error, Operation, PolicyViolation,
+};
+
+fn summary(summary: &str) -> IssueEdit {
+    IssueEdit {
+        fields: IssueEditFields {
+            summary: Some(summary.to_string()),
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn read_only_and_project_lists_block_changes() {
+    let (_server, jirascope) = start_server_with(|config| config.policy.read_only = true);
+
+    assert!(matches!(
+        jirascope.edit_issue(key("TEST-1"), summary("Blocked")),
+        Err(Error::PolicyViolation(PolicyViolation::ReadOnly {
+            operation: Operation::EditIssue
+        }))
+    ));
+
+    let (_server, jirascope) = start_server_with(|config| {
+        config.policy.allowed_projects = Some(vec!["OTHER".to_string()]);
+    });
+
+    // numeric ids are looked up to find their project
+    for issue in ["TEST-1", "10001"] {
+        assert!(matches!(
+            jirascope.edit_issue(issue.parse::<IssueIdOrKey>().unwrap(), summary("Blocked")),
+            Err(Error::PolicyViolation(
+                PolicyViolation::ProjectNotAllowed { .. }
+            ))
+        ));
+    }
+    assert_eq!(
+        jirascope.get_issue(key("TEST-1")).unwrap().fields.summary,
+        "Test issue 1"
+    );
+}
+
+#[test]
+fn deleting_an_issue_with_subtasks_needs_confirming() {
+    let (_server, jirascope) = start_server();
+
+    match jirascope.delete_issue(key("TEST-1")) {
+        Err(Error::PolicyViolation(PolicyViolation::ConfirmationRequired { target, .. })) => {
+            assert_eq!(target, "TEST-1")
+        }
+        other => panic!("expected a confirmation request, got {:?}", other),
+    }
+
+    jirascope.delete_issue(key("TEST-2")).unwrap();
+    jirascope
+        .delete_issue_confirmed(key("TEST-1"), &Confirmation::new("TEST-1"))
+        .unwrap();
+
+    assert!(matches!(
+        jirascope.get_issue(key("TEST-3")),
+        Err(Error::NotFound(_))
+    ));
+}
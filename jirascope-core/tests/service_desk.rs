@@ -0,0 +1,89 @@
+mod common;
+
+use common::{key, start_server};
+use jirascope_core::{jira::CustomerRequestCreation, Error};
+
+#[test]
+fn queues_list_requests_with_their_slas() {
+    let (_server, jirascope) = start_server();
+
+    let service_desks = jirascope.get_service_desks().fetch_all().unwrap();
+    assert_eq!(service_desks.len(), 1);
+    assert_eq!(service_desks[0].project_key, "HELP");
+
+    let queues = jirascope
+        .get_queues(service_desks[0].id)
+        .fetch_all()
+        .unwrap();
+    assert_eq!(queues[0].issue_count, Some(1));
+
+    let issues = jirascope
+        .get_queue_issues(service_desks[0].id, queues[0].id)
+        .fetch_all()
+        .unwrap();
+    assert_eq!(issues[0].key, "HELP-1");
+    assert_eq!(issues[0].summary(), "VPN is down");
+    assert_eq!(issues[0].status(), "Waiting for support");
+
+    let slas = jirascope
+        .get_request_slas(key("HELP-1"))
+        .fetch_all()
+        .unwrap();
+    assert_eq!(
+        slas.iter().map(|sla| sla.breached()).collect::<Vec<_>>(),
+        vec![true, false]
+    );
+}
+
+#[test]
+fn requests_are_raised_and_commented() {
+    let (_server, jirascope) = start_server();
+
+    let service_desk = jirascope.get_service_desk(1).unwrap();
+    let request_types = jirascope
+        .get_request_types(service_desk.id)
+        .fetch_all()
+        .unwrap();
+
+    let request = jirascope
+        .create_customer_request(CustomerRequestCreation::new(
+            service_desk.id,
+            request_types[0].id,
+            "Printer on fire",
+        ))
+        .unwrap();
+    assert_eq!(request.issue_key, "HELP-2");
+    assert_eq!(
+        jirascope
+            .get_customer_requests(service_desk.id)
+            .fetch_all()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    jirascope
+        .add_request_comment(&request.issue_key, "Put it out first", false)
+        .unwrap();
+    let comments = jirascope
+        .get_request_comments(&request.issue_key)
+        .fetch_all()
+        .unwrap();
+    assert_eq!(comments.len(), 1);
+    assert!(!comments[0].public);
+
+    // HELP-1 has a public and an internal comment
+    let comments = jirascope
+        .get_request_comments(key("HELP-1"))
+        .fetch_all()
+        .unwrap();
+    assert_eq!(
+        comments.iter().map(|c| c.public).collect::<Vec<_>>(),
+        vec![true, false]
+    );
+
+    assert!(matches!(
+        jirascope.get_customer_request(key("HELP-9")),
+        Err(Error::NotFound(_))
+    ));
+}
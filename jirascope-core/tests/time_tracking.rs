@@ -0,0 +1,44 @@
+mod common;
+
+use common::{key, start_server};
+use jirascope_core::jira::{IssueEdit, IssueEditFields, JiraDuration, TimeTrackingEdit};
+
+#[test]
+fn estimates_use_the_instance_options() {
+    let (_server, jirascope) = start_server();
+    let options = jirascope.get_time_tracking_options().unwrap();
+
+    // the test server's days are 6 hours long
+    let estimate = jirascope
+        .get_issue(key("TEST-1"))
+        .unwrap()
+        .fields
+        .timetracking
+        .unwrap()
+        .original_estimate
+        .unwrap();
+    assert_eq!(estimate.format(&options), "1d");
+
+    let edit = IssueEdit {
+        fields: IssueEditFields {
+            timetracking: Some(TimeTrackingEdit {
+                remaining_estimate: Some(JiraDuration::parse("1d 30m", &options).unwrap()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    };
+    jirascope.edit_issue(key("TEST-1"), edit).unwrap();
+
+    let time_tracking = jirascope
+        .get_issue(key("TEST-1"))
+        .unwrap()
+        .fields
+        .timetracking
+        .unwrap();
+    assert_eq!(
+        time_tracking.remaining_estimate.unwrap().seconds(),
+        (6 * 60 + 30) * 60
+    );
+    assert_eq!(time_tracking.original_estimate, Some(estimate));
+}
@@ -232,15 +232,24 @@ fn issues(env: &Env) -> Result<Value<'_>> {
 
     let time = std::time::Instant::now();
     for _ in 0..100 {
-        get_jirascope().get_all_issues()?;
+        // a single page, like the requests below
+        get_jirascope()
+            .get_all_issues()
+            .with_page_size(50)
+            .with_limit(50)
+            .fetch_all()?;
     }
     let elapsed = time.elapsed();
     rows_verbose.push(("Rust", "ureq", format!("{:?}", elapsed)));
     rows_micro.push(("Rust, ureq", elapsed.as_micros()));
 
     let args = vec![
-        "https://jirascope-testing.atlassian.net/rest/api/3/search"
-            .to_string()
+        concat!(
+            "https://jirascope-testing.atlassian.net/rest/api/3/search/jql",
+            "?jql=project%20IS%20NOT%20EMPTY%20ORDER%20BY%20key%20ASC",
+            "&fields=*navigable&maxResults=50"
+        )
+        .to_string()
             .into_lisp(env)?,
         get_jirascope().auth.get_basic_auth().into_lisp(env)?,
     ];
@@ -0,0 +1,96 @@
+//! Saved filters in the tree buffer. Every favourite filter is a section of
+//! the tree, its button shows or hides the issues the filter finds.
+
+use emacs::{defun, Env, Result, Value};
+use jirascope_core::jira::Filter;
+
+use crate::{
+    concurrent::{self, workthread_spawn},
+    state::{self, get_state, get_state_mut, StateChanges},
+    utils,
+};
+
+fn prompt_filter(env: &Env) -> Option<Filter> {
+    let state = get_state();
+    let filters = state.filters();
+
+    let index = utils::prompt_select_index(
+        env,
+        "Choose filter: ",
+        filters
+            .iter()
+            .map(|f| f.name.clone())
+            .collect::<Vec<_>>()
+            .as_slice(),
+    )?;
+
+    Some(filters[index].clone())
+}
+
+/// Run `filter` on a work thread and show its issues in the tree.
+fn show(filter: Filter) {
+    workthread_spawn(move || {
+        let issues = state::run_filter(&filter);
+
+        concurrent::push_command(Box::new(move |env| {
+            match issues {
+                Ok(issues) => {
+                    let mut state = get_state_mut();
+                    state.show_filter(filter.id, issues);
+                    redraw(env, &state);
+                }
+                Err(err) => {
+                    env.message(format!("Failed to run filter {}: {}", filter.name, err))?;
+                }
+            }
+
+            Ok(())
+        }));
+    });
+}
+
+fn redraw(env: &Env, state: &state::State) {
+    state::update_buffers(
+        env,
+        state,
+        &StateChanges {
+            filters: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[defun]
+fn button_action(env: &Env, button: Value<'_>) -> Result<()> {
+    let button_content = env.call("button-label", [button])?.into_rust::<String>()?;
+
+    let filter = match get_state().get_filter(&button_content) {
+        Some(filter) => filter,
+        None => return Ok(()),
+    };
+
+    let mut state = get_state_mut();
+    if state.hide_filter(filter.id) {
+        redraw(env, &state);
+    } else {
+        drop(state);
+        env.message(format!("Running filter {}...", filter.name))?;
+        show(filter);
+    }
+
+    Ok(())
+}
+
+#[defun]
+fn run_interactive(env: &Env) -> Result<()> {
+    let filter = prompt_filter(env);
+
+    if filter.is_none() {
+        return Ok(());
+    }
+
+    state::open(env)?;
+    show(filter.unwrap());
+
+    Ok(())
+}
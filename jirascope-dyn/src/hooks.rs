@@ -0,0 +1,126 @@
+//! The Lisp hook variables around changes, `jirascope-before-create-functions`
+//! and the others defined in jirascope.el.
+//!
+//! Hook functions get the change as a plist: the body of the request sent to
+//! Jira, plus `:key` of the issue.
+//!
+//! Before hooks run on the Emacs thread, before a change is handed to a work
+//! thread, so their functions are free to prompt. After hooks are a
+//! [`Hook`] of the client, so they also run for changes sent later from the
+//! outbox. They are queued to run on the Emacs thread.
+
+use emacs::{Env, Result};
+use jirascope_core::{
+    jira::{
+        Comment, CreatedIssue, IssueCreation, IssueEdit, IssueIdOrKey, IssueTransition,
+        IssueTransitionDescriptor,
+    },
+    Hook,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::concurrent;
+
+pub(crate) static BEFORE_CREATE: &str = "jirascope-before-create-functions";
+pub(crate) static BEFORE_EDIT: &str = "jirascope-before-edit-functions";
+pub(crate) static BEFORE_TRANSITION: &str = "jirascope-before-transition-functions";
+pub(crate) static BEFORE_COMMENT: &str = "jirascope-before-comment-functions";
+pub(crate) static BEFORE_DELETE: &str = "jirascope-before-delete-functions";
+
+/// Pass `change` through the functions of the before hook `hook`.
+///
+/// A function signalling an error stops the change, the error is returned.
+pub(crate) fn run_before<T: Serialize + DeserializeOwned>(
+    env: &Env,
+    hook: &str,
+    issue_key: Option<&str>,
+    change: T,
+) -> Result<T> {
+    let changed = env.call(
+        "jirascope--run-before-hook",
+        (env.intern(hook)?, payload(issue_key, &change)?),
+    )?;
+
+    // nil when the hook is empty
+    if changed.is_not_nil() {
+        let mut changed: Value = serde_json::from_str(&changed.into_rust::<String>()?)?;
+        if let Some(changed) = changed.as_object_mut() {
+            changed.remove("key");
+        }
+
+        return Ok(serde_json::from_value(changed)?);
+    }
+
+    Ok(change)
+}
+
+/// Runs the after hooks.
+pub(crate) struct LispHooks;
+
+impl Hook for LispHooks {
+    fn after_create(&self, issue: &IssueCreation, created: &CreatedIssue) {
+        run_after(
+            "jirascope-after-create-functions",
+            Some(created.key.to_string()),
+            issue,
+        );
+    }
+
+    fn after_edit(&self, issue_id: &IssueIdOrKey, edit: &IssueEdit) {
+        run_after("jirascope-after-edit-functions", Some(issue_id.to_string()), edit);
+    }
+
+    fn after_transition(&self, issue_id: &IssueIdOrKey, transition: &IssueTransitionDescriptor) {
+        run_after(
+            "jirascope-after-transition-functions",
+            Some(issue_id.to_string()),
+            &IssueTransition {
+                transition: transition.clone(),
+            },
+        );
+    }
+
+    fn after_comment(&self, issue_id: &IssueIdOrKey, comment: &Comment) {
+        run_after("jirascope-after-comment-functions", Some(issue_id.to_string()), comment);
+    }
+
+    fn after_delete(&self, issue_id: &IssueIdOrKey) {
+        run_after(
+            "jirascope-after-delete-functions",
+            Some(issue_id.to_string()),
+            &Map::new(),
+        );
+    }
+}
+
+fn run_after(hook: &'static str, issue_key: Option<String>, change: &impl Serialize) {
+    // the change was made already, there is nobody to report a failure to
+    let payload = match payload(issue_key.as_deref(), change) {
+        Ok(payload) => payload,
+        Err(_) => return,
+    };
+
+    concurrent::push_command(Box::new(move |env| {
+        env.call("jirascope--run-after-hook", (env.intern(hook)?, payload))?;
+
+        Ok(())
+    }));
+}
+
+/// The change as JSON, for jirascope.el to turn into a plist.
+fn payload(
+    issue_key: Option<&str>,
+    change: &impl Serialize,
+) -> std::result::Result<String, serde_json::Error> {
+    let mut payload = match serde_json::to_value(change)? {
+        Value::Object(fields) => fields,
+        _ => Map::new(),
+    };
+
+    if let Some(issue_key) = issue_key {
+        payload.insert("key".to_string(), issue_key.into());
+    }
+
+    serde_json::to_string(&payload)
+}
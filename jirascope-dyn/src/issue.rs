@@ -1,21 +1,81 @@
+use std::sync::OnceLock;
+
 use emacs::{defun, Env, Result, Value};
-use jirascope_core::jira::{
-    AtlassianDoc, Issue, IssueCreation, IssueCreationFields, IssueEdit, IssueTransitionDescriptor,
-    WrappedId,
+use jirascope_core::{
+    jira::{
+        AtlassianDoc, CommentCreation, Issue, IssueCreation, IssueCreationFields, IssueEdit,
+        IssueKey, IssueTransition, IssueTransitionDescriptor, JiraDuration, TimeTrackingEdit,
+        TimeTrackingOptions, WrappedId,
+    },
+    display_value, Change, Confirmation, FieldChange, IssueDiff, IssueKeyFinder, Mutation,
+    PolicyViolation, TextChunk,
 };
+use serde_json::Map;
 
 use crate::{
-    concurrent::{self, workthread_spawn}, get_jirascope, project,
+    concurrent::{self, workthread_spawn}, get_jirascope, hooks, outbox, project,
     state::{self, get_state, ConflictCell, get_state_mut},
     utils::{
-        self, close_jirascope_diff_buffer, current_buffer_face_println, current_buffer_println,
+        self, close_jirascope_diff_buffer, current_buffer_face_print, current_buffer_face_println,
+        current_buffer_print, current_buffer_println,
         get_jirascope_buffer_content, open_jirascope_buffer, open_jirascope_diff_buffer,
-        prompt_force_change, signal_result, signal_result_async, with_buffer,
-        ScopeCleaner, JIRASCOPE_FACE_DIFF_ALERT, JIRASCOPE_FACE_DIFF_NEW, JIRASCOPE_FACE_DIFF_OLD,
+        prompt_force_change, signal_result_async, with_buffer,
+        ScopeCleaner, JIRASCOPE_FACE_DIFF_ADDED, JIRASCOPE_FACE_DIFF_ALERT,
+        JIRASCOPE_FACE_DIFF_REMOVED,
     },
     JIRASCOPE_DIFF_BUFFER_NAME,
 };
 
+// fetched when first needed, how long a working day is rarely changes
+static TIME_TRACKING_OPTIONS: OnceLock<TimeTrackingOptions> = OnceLock::new();
+
+fn time_tracking_options() -> Result<&'static TimeTrackingOptions> {
+    if let Some(options) = TIME_TRACKING_OPTIONS.get() {
+        return Ok(options);
+    }
+
+    let options = get_jirascope().get_time_tracking_options()?;
+
+    Ok(TIME_TRACKING_OPTIONS.get_or_init(|| options))
+}
+
+/// Estimates are printed even if empty, to be filled in when editing. Nothing
+/// is printed if time tracking is off.
+fn print_time_tracking(env: &Env, issue: &Issue) -> Result<()> {
+    let time_tracking = match issue.fields.timetracking {
+        Some(ref time_tracking) => time_tracking,
+        None => return Ok(()),
+    };
+
+    let options = time_tracking_options()?;
+    let format =
+        |duration: Option<JiraDuration>| duration.map(|d| d.format(options)).unwrap_or_default();
+
+    current_buffer_println(
+        env,
+        &format!(
+            "Original estimate: {}",
+            format(time_tracking.original_estimate)
+        ),
+    )?;
+    current_buffer_println(
+        env,
+        &format!(
+            "Remaining estimate: {}",
+            format(time_tracking.remaining_estimate)
+        ),
+    )?;
+
+    if time_tracking.time_spent.is_some() {
+        current_buffer_println(
+            env,
+            &format!("Time spent: {}", format(time_tracking.time_spent)),
+        )?;
+    }
+
+    Ok(())
+}
+
 fn prompt_issue(env: &Env) -> Option<Issue> {
     // let user choose issue
     let state = get_state();
@@ -34,8 +94,8 @@ fn prompt_issue(env: &Env) -> Option<Issue> {
     Some(issues[index].clone())
 }
 
-fn prompt_issue_transition(env: &Env, issue_key: &str) -> Option<IssueTransitionDescriptor> {
-    let mut jirascope = get_jirascope();
+fn prompt_issue_transition(env: &Env, issue_key: &IssueKey) -> Option<IssueTransitionDescriptor> {
+    let jirascope = get_jirascope();
     // let user choose issue status
     let mut issue_transitions = jirascope
         .get_issue_transitions(issue_key)
@@ -91,13 +151,9 @@ fn create_interactive(env: &Env) -> Result<Value<'_>> {
     drop(state);
 
     // let user choose issue type
-    let create_meta = get_jirascope().get_issue_creation_meta()?;
-    let mut issue_types = create_meta
-        .projects
-        .into_iter()
-        .find(|p| p.id == project.id)
-        .unwrap()
-        .issue_types;
+    let mut issue_types = get_jirascope()
+        .get_issue_creation_types(&project.key)
+        .fetch_all()?;
 
     let index = utils::prompt_select_index(
         env,
@@ -150,9 +206,12 @@ fn create_interactive(env: &Env) -> Result<Value<'_>> {
             priority: None,
             assignee: None,
             parent: parent.map(WrappedId::new),
+            extra: Default::default(),
         },
     };
 
+    let issue_creation = hooks::run_before(env, hooks::BEFORE_CREATE, None, issue_creation)?;
+
     workthread_spawn(move || {
         let result = get_jirascope().create_issue(issue_creation);
 
@@ -208,15 +267,30 @@ fn edit_interactive(env: &Env) -> Result<Value<'_>> {
     )
     .map(|d| AtlassianDoc::from_markdown(&d));
 
+    let issue_edit = hooks::run_before(
+        env,
+        hooks::BEFORE_EDIT,
+        Some(issue.key.as_str()),
+        issue_edit,
+    )?;
+
     workthread_spawn(move || {
         if !get_state_mut().try_return_issue(&*issue.key) {
             concurrent::push_command(Box::new(move |env| {
                 env.message("Issue changed since last access. Please check the diff buffer.")?;
 
                 if prompt_force_change(env, "Issue changed since last access")? {
-                    let result = get_jirascope().edit_issue(&*issue.key, issue_edit);
-
-                    signal_result(env, result, "Issue edited.", "Failed to edit issue.")?;
+                    let base_updated = get_state().get_issue(&issue.key).map(|i| i.fields.updated);
+
+                    outbox::submit(
+                        Mutation::EditIssue {
+                            issue_key: issue.key,
+                            edit: Box::new(issue_edit),
+                        },
+                        base_updated,
+                        "Issue edited.",
+                        "Failed to edit issue.",
+                    );
                 }
 
                 Ok(())
@@ -224,9 +298,15 @@ fn edit_interactive(env: &Env) -> Result<Value<'_>> {
             return;
         }
 
-        let result = get_jirascope().edit_issue(&*issue.key, issue_edit);
-
-        signal_result_async(result, "Issue edited.", "Failed to edit issue.");
+        outbox::submit(
+            Mutation::EditIssue {
+                issue_key: issue.key,
+                edit: Box::new(issue_edit),
+            },
+            Some(issue.fields.updated),
+            "Issue edited.",
+            "Failed to edit issue.",
+        );
         // make sure the guard is moved into the closure
         drop(guard);
     });
@@ -238,7 +318,38 @@ fn edit_interactive(env: &Env) -> Result<Value<'_>> {
 fn button_action(env: &Env, button: Value<'_>) -> Result<()> {
     let button_content = env.call("button-label", [button])?.into_rust::<String>()?;
 
-    edit_graphical(env, button_content)
+    edit_graphical(env, button_content.parse()?)
+}
+
+/// Turn the keys of known projects between `start` and `end` into issue
+/// buttons, e.g. in a commit message or a code comment.
+#[defun]
+fn buttonize_keys(env: &Env, start: Value<'_>, end: Value<'_>) -> Result<()> {
+    let text = env
+        .call("buffer-substring-no-properties", [start, end])?
+        .into_rust::<String>()?;
+    let finder =
+        IssueKeyFinder::with_projects(get_state().projects().iter().map(|p| p.key.clone()));
+
+    // buffer positions count characters, the spans count bytes
+    let mut position = start.into_rust::<i64>()?;
+    let mut offset = 0;
+    for found in finder.find(&text) {
+        position += text[offset..found.span.start].chars().count() as i64;
+        offset = found.span.start;
+
+        env.call(
+            "make-button",
+            (
+                position,
+                position + found.span.len() as i64,
+                env.intern(":type")?,
+                env.intern("jirascope-issue-button")?,
+            ),
+        )?;
+    }
+
+    Ok(())
 }
 
 #[defun]
@@ -252,17 +363,16 @@ fn edit_graphical_interactive(env: &Env) -> Result<()> {
     edit_graphical(env, issue.unwrap().key)
 }
 
-fn edit_graphical(env: &Env, issue_key: String) -> Result<()> {
-    let issue = get_state().get_issue(&issue_key);
-
-    if issue.is_none() {
+fn edit_graphical(env: &Env, issue_key: IssueKey) -> Result<()> {
+    if get_state().get_issue(&issue_key).is_none() {
         return Ok(());
     }
 
-    let issue = issue.unwrap();
+    // the state only keeps what the tree shows, fetch the description too
+    let issue = get_jirascope().get_issue(&issue_key)?;
 
     get_state_mut().return_issue();
-    get_state_mut().check_out_issue(issue.key.clone())?;
+    get_state_mut().check_out_full_issue(issue.clone())?;
 
     open_jirascope_buffer(env)?;
 
@@ -272,6 +382,8 @@ fn edit_graphical(env: &Env, issue_key: String) -> Result<()> {
 
     current_buffer_println(env, &format!("Status: {}", issue.fields.status.name))?;
 
+    print_time_tracking(env, &issue)?;
+
     if let Some(description) = issue.fields.description {
         current_buffer_println(env, &format!("Description: {}", description.to_markdown()))?;
     }
@@ -294,13 +406,35 @@ fn edit_graphical_finish(env: &Env) -> Result<Value<'_>> {
         .unwrap()
         .trim_start_matches("* ")
         .trim_end_matches(" *")
-        .to_string();
+        .parse::<IssueKey>()?;
 
     issue_edit.fields.summary = edited_issue
         .lines()
         .find(|l| l.starts_with("Summary: "))
         .map(|l| l.trim_start_matches("Summary: ").to_string());
 
+    let estimate = |prefix: &str| -> Result<Option<JiraDuration>> {
+        match edited_issue
+            .lines()
+            .find_map(|l| l.strip_prefix(prefix))
+            .map(str::trim)
+        {
+            Some(text) if !text.is_empty() => {
+                Ok(Some(JiraDuration::parse(text, time_tracking_options()?)?))
+            }
+            _ => Ok(None),
+        }
+    };
+    let original_estimate = estimate("Original estimate: ")?;
+    let remaining_estimate = estimate("Remaining estimate: ")?;
+
+    if original_estimate.is_some() || remaining_estimate.is_some() {
+        issue_edit.fields.timetracking = Some(TimeTrackingEdit {
+            original_estimate,
+            remaining_estimate,
+        });
+    }
+
     let description_str = &edited_issue[edited_issue
         .find("Description: ")
         .map(|i| i + "Description: ".len())
@@ -312,7 +446,17 @@ fn edit_graphical_finish(env: &Env) -> Result<Value<'_>> {
         Some(AtlassianDoc::from_markdown(description_str))
     };
 
+    let issue_edit = hooks::run_before(env, hooks::BEFORE_EDIT, Some(key.as_str()), issue_edit)?;
+
     workthread_spawn(move || {
+        // what the edit was made against, the outbox checks it's still current
+        let base_updated = {
+            let state = get_state();
+            state
+                .checked_out_updated(&key)
+                .or_else(|| state.get_issue(&key).map(|i| i.fields.updated))
+        };
+
         if !get_state_mut().try_return_issue(&*key) {
             concurrent::push_command(Box::new(move |env| {
                 env.message("Issue changed since last access.")?;
@@ -320,11 +464,19 @@ fn edit_graphical_finish(env: &Env) -> Result<Value<'_>> {
                 display_old_and_changed(env)?;
 
                 if prompt_force_change(env, "Issue changed since last access")? {
-                    let result = get_jirascope().edit_issue(&*key, issue_edit);
+                    let base_updated = get_state().get_issue(&key).map(|i| i.fields.updated);
 
                     state::get_state_mut().return_issue();
 
-                    signal_result(env, result, "Issue edited.", "Failed to edit issue.")?;
+                    outbox::submit(
+                        Mutation::EditIssue {
+                            issue_key: key.clone(),
+                            edit: Box::new(issue_edit),
+                        },
+                        base_updated,
+                        "Issue edited.",
+                        "Failed to edit issue.",
+                    );
 
                     state::get_state_mut().check_out_issue(key.clone())?;
                 }
@@ -336,11 +488,15 @@ fn edit_graphical_finish(env: &Env) -> Result<Value<'_>> {
             return;
         }
 
-        let result = get_jirascope().edit_issue(&*key, issue_edit);
-
-        state::get_state_mut().return_issue();
-
-        signal_result_async(result, "Issue edited.", "Failed to edit issue.");
+        outbox::submit(
+            Mutation::EditIssue {
+                issue_key: key,
+                edit: Box::new(issue_edit),
+            },
+            base_updated,
+            "Issue edited.",
+            "Failed to edit issue.",
+        );
 
         concurrent::push_command(Box::new(move |env| {
             state::open(env)?;
@@ -353,13 +509,26 @@ fn edit_graphical_finish(env: &Env) -> Result<Value<'_>> {
 }
 
 fn display_old_and_changed(env: &Env) -> Result<()> {
-    let state = get_state();
-
-    let work_issue = state.get_current_work_issue();
+    let work_issue = match get_state().get_current_work_issue() {
+        ConflictCell::Deleted { key } => ConflictCell::Deleted { key: key.clone() },
+        ConflictCell::Outdated { key, old } => ConflictCell::Outdated {
+            key: key.clone(),
+            old: old.clone(),
+        },
+        _ => return Ok(()),
+    };
 
-    if matches!(work_issue, ConflictCell::Empty | ConflictCell::Armed { .. }) {
-        return Ok(());
-    }
+    let diff = match work_issue {
+        ConflictCell::Outdated { ref key, ref old } => {
+            // the synced issue lacks descriptions, compare with the full one
+            let current = get_jirascope()
+                .get_issue(key)
+                .ok()
+                .or_else(|| get_state().get_issue(key));
+            current.map(|current| IssueDiff::new(old, &current))
+        }
+        _ => None,
+    };
 
     with_buffer(env, JIRASCOPE_DIFF_BUFFER_NAME, |env| {
         env.call("erase-buffer", [])?;
@@ -368,52 +537,18 @@ fn display_old_and_changed(env: &Env) -> Result<()> {
                 current_buffer_face_println(env, &format!("* {} *", key), "jirascope-issue-key")?;
                 current_buffer_face_println(env, "Issue was deleted.", JIRASCOPE_FACE_DIFF_ALERT)?;
             }
-            ConflictCell::Outdated { key, old } => {
+            ConflictCell::Outdated { key, .. } => {
                 current_buffer_face_println(env, &format!("* {} *", key), "jirascope-issue-key")?;
                 current_buffer_face_println(
                     env,
                     "Issue was changed since last access.",
                     JIRASCOPE_FACE_DIFF_ALERT,
                 )?;
-                current_buffer_face_println(env, "Old:", JIRASCOPE_FACE_DIFF_ALERT)?;
-                current_buffer_face_println(
-                    env,
-                    &format!("Summary: {}", old.fields.summary),
-                    JIRASCOPE_FACE_DIFF_OLD,
-                )?;
-                current_buffer_face_println(
-                    env,
-                    &format!("Status: {}", old.fields.status.name,),
-                    JIRASCOPE_FACE_DIFF_OLD,
-                )?;
 
-                if let Some(ref description) = old.fields.description {
-                    current_buffer_face_println(
-                        env,
-                        &format!("Description: {}", description.to_markdown()),
-                        JIRASCOPE_FACE_DIFF_OLD,
-                    )?;
-                }
-                current_buffer_face_println(env, "New:", JIRASCOPE_FACE_DIFF_ALERT)?;
-                let current = state.get_issue(key).unwrap();
-
-                current_buffer_face_println(
-                    env,
-                    &format!("Summary: {}", current.fields.summary),
-                    JIRASCOPE_FACE_DIFF_NEW,
-                )?;
-                current_buffer_face_println(
-                    env,
-                    &format!("Status: {}", current.fields.status.name),
-                    JIRASCOPE_FACE_DIFF_NEW,
-                )?;
-
-                if let Some(ref description) = current.fields.description {
-                    current_buffer_face_println(
-                        env,
-                        &format!("Description: {}", description.to_markdown()),
-                        JIRASCOPE_FACE_DIFF_NEW,
-                    )?;
+                if let Some(diff) = diff {
+                    for change in &diff.changes {
+                        print_field_change(env, change)?;
+                    }
                 }
             }
             _ => {}
@@ -426,6 +561,40 @@ fn display_old_and_changed(env: &Env) -> Result<()> {
     Ok(())
 }
 
+fn print_field_change(env: &Env, change: &FieldChange) -> Result<()> {
+    let name = change.name.as_deref().unwrap_or(&change.field);
+    current_buffer_print(env, &format!("{}: ", name))?;
+
+    match change.change {
+        Change::Text { ref chunks } => {
+            for chunk in chunks {
+                match chunk {
+                    TextChunk::Same(text) => current_buffer_print(env, text)?,
+                    TextChunk::Added(text) => {
+                        current_buffer_face_print(env, text, JIRASCOPE_FACE_DIFF_ADDED)?
+                    }
+                    TextChunk::Removed(text) => {
+                        current_buffer_face_print(env, text, JIRASCOPE_FACE_DIFF_REMOVED)?
+                    }
+                }
+            }
+        }
+        Change::Value { ref old, ref new } => {
+            if let Some(old) = old {
+                current_buffer_face_print(env, &display_value(old), JIRASCOPE_FACE_DIFF_REMOVED)?;
+                current_buffer_print(env, " ")?;
+            }
+            if let Some(new) = new {
+                current_buffer_face_print(env, &display_value(new), JIRASCOPE_FACE_DIFF_ADDED)?;
+            }
+        }
+    }
+
+    env.call("newline", [])?;
+
+    Ok(())
+}
+
 #[defun]
 fn delete_interactive(env: &Env) -> Result<Value<'_>> {
     let issue = prompt_issue(env);
@@ -437,14 +606,54 @@ fn delete_interactive(env: &Env) -> Result<Value<'_>> {
     let issue = issue.unwrap();
     let issue_key = issue.key;
 
-    workthread_spawn(move || {
-        let result = get_jirascope().delete_issue(&*issue_key);
-        signal_result_async(result, "Issue deleted.", "Failed to delete issue.");
-    });
+    hooks::run_before(env, hooks::BEFORE_DELETE, Some(issue_key.as_str()), Map::new())?;
+
+    workthread_spawn(move || delete(issue_key, None));
 
     utils::nil(env)
 }
 
+/// Delete an issue, asking for confirmation if the policy wants one, e.g.
+/// because the issue has subtasks.
+fn delete(issue_key: IssueKey, confirmation: Option<Confirmation>) {
+    let result = match confirmation {
+        Some(ref confirmation) => get_jirascope().delete_issue_confirmed(&issue_key, confirmation),
+        None => get_jirascope().delete_issue(&issue_key),
+    };
+
+    match result {
+        Err(jirascope_core::Error::PolicyViolation(PolicyViolation::ConfirmationRequired {
+            target,
+            ..
+        })) if confirmation.is_none() => {
+            concurrent::push_command(Box::new(move |env| {
+                let typed = utils::prompt_string(
+                    env,
+                    &format!(
+                        "{} has subtasks which are deleted with it, type {} to confirm: ",
+                        issue_key, target
+                    ),
+                );
+
+                if let Some(typed) = typed {
+                    workthread_spawn(move || delete(issue_key, Some(Confirmation::new(typed))));
+                }
+
+                Ok(())
+            }));
+        }
+        Err(err @ jirascope_core::Error::PolicyViolation(_)) => {
+            let message = err.to_string();
+            concurrent::push_command(Box::new(move |env| {
+                env.message(format!("Failed to delete issue: {}", message))?;
+
+                Ok(())
+            }));
+        }
+        result => signal_result_async(result, "Issue deleted.", "Failed to delete issue."),
+    }
+}
+
 #[defun]
 fn transition_interactive(env: &Env) -> Result<Value<'_>> {
     let issue = prompt_issue(env);
@@ -455,6 +664,7 @@ fn transition_interactive(env: &Env) -> Result<Value<'_>> {
 
     let issue = issue.unwrap();
     let issue_key = issue.key;
+    let base_updated = issue.fields.updated;
 
     let transition = prompt_issue_transition(env, &issue_key);
 
@@ -462,12 +672,67 @@ fn transition_interactive(env: &Env) -> Result<Value<'_>> {
         return utils::nil(env);
     }
 
-    let transition = transition.unwrap();
+    let transition = hooks::run_before(
+        env,
+        hooks::BEFORE_TRANSITION,
+        Some(issue_key.as_str()),
+        IssueTransition {
+            transition: transition.unwrap(),
+        },
+    )?
+    .transition;
 
     workthread_spawn(move || {
-        let result = get_jirascope().transition_issue(issue_key.as_str(), transition);
+        outbox::submit(
+            Mutation::TransitionIssue {
+                issue_key,
+                transition,
+            },
+            Some(base_updated),
+            "Transitioned issue.",
+            "Failed to transition issue.",
+        );
+    });
+
+    utils::nil(env)
+}
+
+#[defun]
+fn comment_interactive(env: &Env) -> Result<Value<'_>> {
+    let issue = prompt_issue(env);
+
+    if issue.is_none() {
+        return utils::nil(env);
+    }
+
+    let issue = issue.unwrap();
+
+    let body = utils::prompt_string(env, "Enter comment: ");
 
-        signal_result_async(result, "Transitioned issue.", "Failed to transition issue.");
+    if body.is_none() {
+        return utils::nil(env);
+    }
+
+    let body = AtlassianDoc::from_markdown(&body.unwrap());
+    let body = hooks::run_before(
+        env,
+        hooks::BEFORE_COMMENT,
+        Some(issue.key.as_str()),
+        CommentCreation { body },
+    )?
+    .body;
+
+    workthread_spawn(move || {
+        // a comment doesn't overwrite anything, so it never conflicts
+        outbox::submit(
+            Mutation::AddComment {
+                issue_key: issue.key,
+                body,
+            },
+            None,
+            "Comment added.",
+            "Failed to add comment.",
+        );
     });
 
     utils::nil(env)
@@ -475,7 +740,7 @@ fn transition_interactive(env: &Env) -> Result<Value<'_>> {
 
 #[defun]
 fn display(env: &Env, issue_key: String) -> Result<()> {
-    let issue = get_jirascope().get_issue(&*issue_key)?;
+    let issue = get_jirascope().get_issue(issue_key.parse::<IssueKey>()?)?;
     open_jirascope_buffer(env)?;
 
     current_buffer_face_println(env, &format!("* {} *", issue_key), "jirascope-issue-key")?;
@@ -484,6 +749,36 @@ fn display(env: &Env, issue_key: String) -> Result<()> {
 
     current_buffer_println(env, &format!("Status: {}", issue.fields.status.name))?;
 
+    current_buffer_println(
+        env,
+        &format!(
+            "Created: {} ({})",
+            issue.fields.created,
+            issue.fields.created.relative()
+        ),
+    )?;
+    current_buffer_println(
+        env,
+        &format!(
+            "Updated: {} ({})",
+            issue.fields.updated,
+            issue.fields.updated.relative()
+        ),
+    )?;
+
+    if let Some(due) = issue.fields.duedate {
+        current_buffer_println(env, &format!("Due: {} ({})", due, due.relative()))?;
+    }
+
+    if let Some(resolved) = issue.fields.resolutiondate {
+        current_buffer_println(
+            env,
+            &format!("Resolved: {} ({})", resolved, resolved.relative()),
+        )?;
+    }
+
+    print_time_tracking(env, &issue)?;
+
     if let Some(description) = issue.fields.description {
         current_buffer_println(env, &format!("Description: {}", description.to_markdown()))?;
     }
@@ -503,7 +798,7 @@ fn display_interactive(env: &Env) -> Result<()> {
 
     let issue = issue.unwrap();
 
-    display(env, issue.key)?;
+    display(env, issue.key.into())?;
 
     Ok(())
 }
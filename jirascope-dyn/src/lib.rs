@@ -1,15 +1,19 @@
 #![allow(non_snake_case)] // Stops RA from complaining about the Emacs macros.
 
-use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::{path::PathBuf, sync::OnceLock};
 
-use emacs::{defun, Env, Result};
-use jirascope_core::{Auth, Config, Jirascope};
+use emacs::{defun, Env, IntoLisp, Result, Value};
+use jirascope_core::{Auth, Config, Jirascope, Outbox, Policy, SnapshotStore};
 
 #[cfg(feature = "benchmark")]
 mod benchmark;
 mod concurrent;
+mod filter;
+mod hooks;
 mod issue;
+mod outbox;
 mod project;
+mod service_desk;
 mod state;
 #[cfg(feature = "test_server")]
 mod test_server;
@@ -19,9 +23,10 @@ mod utils;
 // Emacs won't load the module without this.
 emacs::plugin_is_GPL_compatible!();
 
-static JIRASCOPE: OnceLock<Mutex<Jirascope>> = OnceLock::new();
+static JIRASCOPE: OnceLock<Jirascope> = OnceLock::new();
 static JIRASCOPE_BUFFER_NAME: &str = "*jirascope*";
 static JIRASCOPE_DIFF_BUFFER_NAME: &str = "*jirascope-diff*";
+static JIRASCOPE_DRY_RUN_BUFFER_NAME: &str = "*jirascope-dry-run*";
 
 // Register the initialization hook that Emacs will call when it loads the module.
 #[emacs::module]
@@ -45,31 +50,144 @@ fn init(env: &Env) -> Result<()> {
     Ok(())
 }
 
+/// Reads `(:read-only t :allowed-projects ("ABC") :denied-projects ("XYZ"))`,
+/// missing entries keep their defaults.
+fn policy_from_plist(env: &Env, plist: Value<'_>) -> Result<Policy> {
+    let allowed_projects = plist_get(env, plist, ":allowed-projects")?;
+
+    Ok(Policy {
+        read_only: plist_get(env, plist, ":read-only")?.is_not_nil(),
+        allowed_projects: if allowed_projects.is_not_nil() {
+            Some(string_list(env, allowed_projects)?)
+        } else {
+            None
+        },
+        denied_projects: string_list(env, plist_get(env, plist, ":denied-projects")?)?,
+        ..Policy::default()
+    })
+}
+
+fn plist_get<'e>(env: &'e Env, plist: Value<'e>, property: &str) -> Result<Value<'e>> {
+    env.call("plist-get", [plist, env.intern(property)?])
+}
+
+fn string_list<'e>(env: &'e Env, mut list: Value<'e>) -> Result<Vec<String>> {
+    let mut strings = Vec::new();
+
+    while list.is_not_nil() {
+        strings.push(env.call("car", [list])?.into_rust()?);
+        list = env.call("cdr", [list])?;
+    }
+
+    Ok(strings)
+}
+
 #[defun]
-fn setup(url: String, login: String, api_token: String) -> Result<()> {
-    let config = Config::new(url);
+fn setup(
+    env: &Env,
+    url: String,
+    login: String,
+    api_token: String,
+    cache_dir: Option<String>,
+    policy: Value<'_>,
+) -> Result<()> {
+    let mut config = Config::new(url);
+    config.cache_dir = cache_dir.map(PathBuf::from);
+    config.policy = policy_from_plist(env, policy)?;
     let auth = Auth::new(login, api_token);
 
-    let mut jirascope = Jirascope::new(config, auth);
-    jirascope.init()?;
+    let store = SnapshotStore::from_config(&config);
+    let queued = Outbox::from_config(&config)?;
+    let has_snapshot = store
+        .as_ref()
+        .map(|store| matches!(store.load(), Ok(Some(_))))
+        .unwrap_or(false);
+
+    let jirascope = Jirascope::new(config, auth);
+    jirascope.add_hook(hooks::LispHooks);
+    // fails with a descriptive error on a bad URL, TLS problem or rejected credentials
+    match jirascope.init() {
+        Ok(()) => {
+            if let (Some(server), Some(user)) = (jirascope.server(), jirascope.current_user()) {
+                env.message(format!(
+                    "[jirascope] Connected to {} as {}",
+                    server.server_title, user.display_name
+                ))?;
+            }
+        }
+        // most likely offline, keep working from the snapshot
+        Err(err @ (jirascope_core::Error::BadHost { .. } | jirascope_core::Error::Transport(_)))
+            if has_snapshot =>
+        {
+            env.message(format!(
+                "[jirascope] Server unreachable, showing cached data: {}",
+                err
+            ))?;
+        }
+        Err(err) => return Err(err.into()),
+    }
 
-    let res = JIRASCOPE.set(Mutex::new(jirascope));
+    let res = JIRASCOPE.set(jirascope);
 
     if res.is_err() {
         panic!("Jirascope already initialized.");
     }
 
-    state::setup(30.0);
+    if !queued.is_empty() {
+        env.message(format!(
+            "[jirascope] {} change(s) waiting in outbox, see `jirascope-outbox-open`.",
+            queued.items().len()
+        ))?;
+    }
+
+    outbox::setup(queued);
+    state::setup(30.0, store);
+
+    Ok(())
+}
+
+#[defun]
+fn toggle_dry_run(env: &Env) -> Result<()> {
+    let dry_run = !get_jirascope().dry_run();
+    get_jirascope().set_dry_run(dry_run);
+
+    env.message(if dry_run {
+        "[jirascope] Dry run on, changes are only logged, see `jirascope-dry-run-open`."
+    } else {
+        "[jirascope] Dry run off, changes are sent again."
+    })?;
+
+    Ok(())
+}
+
+/// Show the changes held back by dry-run mode, below the ones shown before.
+#[defun]
+fn dry_run_open(env: &Env) -> Result<()> {
+    utils::with_buffer(env, JIRASCOPE_DRY_RUN_BUFFER_NAME, |env| {
+        let end = env.call("point-max", [])?;
+        env.call("goto-char", [end])?;
+
+        for request in get_jirascope().take_dry_run_requests() {
+            utils::current_buffer_println(env, &request.to_string())?;
+            env.call("newline", [])?;
+        }
+
+        Ok(())
+    })?;
+
+    let buffer = env.call(
+        "get-buffer-create",
+        [JIRASCOPE_DRY_RUN_BUFFER_NAME.to_string().into_lisp(env)?],
+    )?;
+    env.call("switch-to-buffer", [buffer])?;
 
     Ok(())
 }
 
-fn get_jirascope<'a>() -> MutexGuard<'a, Jirascope> {
-    let j = JIRASCOPE
-        .get_or_init(|| {
-            panic!("Jirascope not setup. Call `jirascope-setup` first.");
-        })
-        .lock()
-        .unwrap();
-    j
+// The client is shared by every thread without locking, requests made by
+// worker threads and the refresh thread run in parallel.
+fn get_jirascope() -> &'static Jirascope {
+    JIRASCOPE.get_or_init(|| {
+        panic!("Jirascope not setup. Call `jirascope-setup` first.");
+    })
 }
@@ -0,0 +1,328 @@
+use std::{
+    sync::{Mutex, MutexGuard, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use emacs::{defun, Env, IntoLisp, Result, Value};
+use jirascope_core::{jira::JiraDateTime, ItemStatus, Mutation, Outbox};
+
+use crate::{
+    concurrent::{self, workthread_spawn},
+    get_jirascope, state,
+    utils::{self, current_buffer_face_println, current_buffer_println, with_buffer},
+};
+
+static OUTBOX: OnceLock<Mutex<Outbox>> = OnceLock::new();
+static JIRASCOPE_OUTBOX_BUFFER_NAME: &str = "*jirascope-outbox*";
+
+pub(crate) fn setup(outbox: Outbox) {
+    let res = OUTBOX.set(Mutex::new(outbox));
+
+    if res.is_err() {
+        panic!("Jirascope outbox already initialized.");
+    }
+}
+
+pub(crate) fn get_outbox<'a>() -> MutexGuard<'a, Outbox> {
+    let o = OUTBOX
+        .get_or_init(|| {
+            panic!("Jirascope not setup. Call `jirascope-setup` first.");
+        })
+        .lock()
+        .unwrap();
+    o
+}
+
+/// Send `mutation` now, or queue it if the server can't be reached.
+///
+/// Meant to be called from a work thread. Changes to an issue which already
+/// has queued changes are queued behind them, to keep them in order. With a
+/// `base_updated`, the change is only sent if the issue is still at it.
+pub(crate) fn submit(
+    mutation: Mutation,
+    base_updated: Option<JiraDateTime>,
+    on_success: &'static str,
+    on_failure: &'static str,
+) {
+    let mut outbox = get_outbox();
+
+    let result = if outbox.has_pending_for(mutation.issue_key()) {
+        Err(None)
+    } else {
+        match base_updated {
+            Some(ref base_updated) => mutation.apply_if_unchanged(get_jirascope(), base_updated),
+            None => mutation.apply(get_jirascope()),
+        }
+        .map_err(Some)
+    };
+
+    match result {
+        Ok(()) => utils::signal_result_async(Ok::<(), ()>(()), on_success, on_failure),
+        // kept in the outbox, where the next replay marks it as conflicting
+        Err(Some(err @ jirascope_core::Error::Conflict { .. })) => {
+            let message = err.to_string();
+            let description = mutation.to_string();
+
+            if let Err(err) = outbox.push(mutation, base_updated) {
+                eprintln!("Error queueing change: {}", err);
+            }
+
+            concurrent::push_command(Box::new(move |env| {
+                env.message(format!(
+                    "[jirascope] {}. \"{}\" waits in `jirascope-outbox-open`.",
+                    message, description
+                ))?;
+                refresh_outbox_buffer(env)?;
+
+                Ok(())
+            }));
+        }
+        // not worth queueing, it would be blocked again
+        Err(Some(
+            err @ (jirascope_core::Error::PolicyViolation(_) | jirascope_core::Error::Vetoed { .. }),
+        )) => {
+            let message = err.to_string();
+            concurrent::push_command(Box::new(move |env| {
+                env.message(format!("{} {}", on_failure, message))?;
+
+                Ok(())
+            }));
+        }
+        Err(err) if err.as_ref().map(|e| e.is_retryable()).unwrap_or(true) => {
+            let description = mutation.to_string();
+
+            match outbox.push(mutation, base_updated) {
+                Ok(_) => {
+                    let count = outbox.items().len();
+                    concurrent::push_command(Box::new(move |env| {
+                        env.message(format!(
+                            "[jirascope] Queued \"{}\" to send later ({} in outbox).",
+                            description, count
+                        ))?;
+                        refresh_outbox_buffer(env)?;
+
+                        Ok(())
+                    }));
+                }
+                Err(err) => {
+                    eprintln!("Error queueing change: {}", err);
+                    utils::signal_result_async(Err::<(), ()>(()), on_success, on_failure);
+                }
+            }
+        }
+        Err(_) => utils::signal_result_async(Err::<(), ()>(()), on_success, on_failure),
+    }
+}
+
+/// Send queued changes, if there are any. Meant to be called from a work thread.
+pub(crate) fn replay() {
+    // nothing is sent in dry-run mode, the changes wait until it's off
+    if get_jirascope().dry_run() {
+        return;
+    }
+
+    let mut outbox = get_outbox();
+
+    if outbox.is_empty() {
+        return;
+    }
+
+    let needing_attention = |outbox: &Outbox| {
+        outbox
+            .items()
+            .iter()
+            .filter(|i| i.status != ItemStatus::Pending)
+            .count()
+    };
+
+    let before = needing_attention(&outbox);
+    let report = outbox.replay(get_jirascope());
+    let after = needing_attention(&outbox);
+    let waiting = outbox.items().len();
+    drop(outbox);
+
+    if let Some(err) = report.error {
+        eprintln!("Error replaying outbox: {}", err);
+    }
+
+    // nothing went out and nothing new needs attention, e.g. still offline
+    if report.applied == 0 && after == before {
+        return;
+    }
+
+    let applied = report.applied;
+    concurrent::push_command(Box::new(move |env| {
+        if applied > 0 {
+            state::refresh(env)?;
+        }
+
+        env.message(format!(
+            "[jirascope] Sent {} queued change(s), {} waiting in outbox.",
+            applied, waiting
+        ))?;
+        refresh_outbox_buffer(env)?;
+
+        Ok(())
+    }));
+}
+
+/// Replay on a work thread, or tell why not.
+fn replay_unless_dry_run(env: &Env) -> Result<()> {
+    if get_jirascope().dry_run() {
+        env.message("[jirascope] Dry-run is on, queued changes are sent once it's off.")?;
+        refresh_outbox_buffer(env)?;
+    } else {
+        workthread_spawn(replay);
+    }
+
+    Ok(())
+}
+
+fn refresh_outbox_buffer(env: &Env) -> Result<()> {
+    let buffer = env.call(
+        "get-buffer",
+        [JIRASCOPE_OUTBOX_BUFFER_NAME.to_string().into_lisp(env)?],
+    )?;
+
+    if buffer.is_not_nil() {
+        print_outbox(env)?;
+    }
+
+    Ok(())
+}
+
+fn print_outbox(env: &Env) -> Result<()> {
+    let outbox = get_outbox();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    with_buffer(env, JIRASCOPE_OUTBOX_BUFFER_NAME, |env| {
+        env.call("set", [env.intern("buffer-read-only")?, utils::nil(env)?])?;
+        env.call("erase-buffer", [])?;
+
+        current_buffer_face_println(env, "* Outbox *", "jirascope-issue-key")?;
+
+        if outbox.is_empty() {
+            current_buffer_println(env, "No queued changes.")?;
+        }
+
+        for item in outbox.items() {
+            current_buffer_println(
+                env,
+                &format!(
+                    "{}: {} (queued {} min ago)",
+                    item.id,
+                    item.mutation,
+                    now.saturating_sub(item.queued_at) / 60
+                ),
+            )?;
+
+            match item.status {
+                ItemStatus::Pending => {}
+                ItemStatus::Conflict {
+                    ref current_updated,
+                } => current_buffer_face_println(
+                    env,
+                    &format!(
+                        "  Issue changed on the server {}, retry, force or discard.",
+                        current_updated.relative()
+                    ),
+                    utils::JIRASCOPE_FACE_DIFF_ALERT,
+                )?,
+                ItemStatus::Failed { ref message } => current_buffer_face_println(
+                    env,
+                    &format!("  Rejected: {}", message),
+                    utils::JIRASCOPE_FACE_DIFF_ALERT,
+                )?,
+            }
+        }
+
+        env.call(
+            "set",
+            [env.intern("buffer-read-only")?, "t".into_lisp(env)?],
+        )?;
+
+        Ok(())
+    })
+}
+
+fn prompt_outbox_item(env: &Env, only_blocked: bool) -> Option<u64> {
+    let outbox = get_outbox();
+    let items = outbox
+        .items()
+        .iter()
+        .filter(|i| !only_blocked || i.status != ItemStatus::Pending)
+        .collect::<Vec<_>>();
+
+    let index = utils::prompt_select_index(
+        env,
+        "Choose queued change: ",
+        items
+            .iter()
+            .map(|i| format!("{}: {}", i.id, i.mutation))
+            .collect::<Vec<_>>()
+            .as_slice(),
+    )?;
+
+    Some(items[index].id)
+}
+
+#[defun]
+fn open(env: &Env) -> Result<()> {
+    let buffer = env.call(
+        "get-buffer-create",
+        [JIRASCOPE_OUTBOX_BUFFER_NAME.to_string().into_lisp(env)?],
+    )?;
+
+    env.call("switch-to-buffer", [buffer])?;
+
+    print_outbox(env)
+}
+
+#[defun]
+fn retry_interactive(env: &Env) -> Result<Value<'_>> {
+    // retrying a blocked item is optional, a replay is always attempted
+    if let Some(id) = prompt_outbox_item(env, true) {
+        get_outbox().retry(id)?;
+    }
+
+    replay_unless_dry_run(env)?;
+
+    utils::nil(env)
+}
+
+#[defun]
+fn force_interactive(env: &Env) -> Result<Value<'_>> {
+    let id = match prompt_outbox_item(env, true) {
+        Some(id) => id,
+        None => return utils::nil(env),
+    };
+
+    if !utils::prompt_force_change(env, "This overwrites changes made on the server")? {
+        return utils::nil(env);
+    }
+
+    get_outbox().force(id)?;
+
+    replay_unless_dry_run(env)?;
+
+    utils::nil(env)
+}
+
+#[defun]
+fn discard_interactive(env: &Env) -> Result<Value<'_>> {
+    let id = match prompt_outbox_item(env, false) {
+        Some(id) => id,
+        None => return utils::nil(env),
+    };
+
+    if let Some(item) = get_outbox().discard(id)? {
+        env.message(format!("[jirascope] Discarded \"{}\".", item.mutation))?;
+    }
+
+    refresh_outbox_buffer(env)?;
+
+    utils::nil(env)
+}
@@ -1,7 +1,10 @@
 use emacs::{defun, Env, Result, Value};
-use jirascope_core::jira::{
-    Project, ProjectCategory, ProjectCreate, ProjectCreateDetails, ProjectEdit, PROJECT_TEMPLATES,
-    PROJECT_TYPE_KEYS, PROJECT_TYPE_NAMES_TO_TEMPLATE_RANGE,
+use jirascope_core::{
+    jira::{
+        Project, ProjectCategory, ProjectCreate, ProjectCreateDetails, ProjectEdit, ProjectKey,
+        PROJECT_TEMPLATES, PROJECT_TYPE_KEYS, PROJECT_TYPE_NAMES_TO_TEMPLATE_RANGE,
+    },
+    Confirmation, Guarded,
 };
 
 use crate::{
@@ -34,13 +37,14 @@ pub fn prompt_select_project(env: &Env) -> Option<Project> {
 
 #[defun]
 fn create_interactive(env: &Env) -> Result<()> {
-    let key = utils::force_prompt_string(env, "Enter project key: ")?;
+    let key: ProjectKey = utils::force_prompt_string(env, "Enter project key: ")?.parse()?;
     let name = utils::force_prompt_string(env, "Enter project name: ")?;
     let description = utils::force_prompt_string(env, "Enter project description: ")?;
     let url = utils::prompt_string(env, "Enter project info URL (or leave empty): ");
 
     let users = get_jirascope()
-        .get_users()?
+        .get_users()
+        .fetch_all()?
         .into_iter()
         .filter(|u| u.active && u.account_type == "atlassian")
         .collect::<Vec<_>>();
@@ -61,7 +65,7 @@ fn create_interactive(env: &Env) -> Result<()> {
 
     let lead_account_id = users[index.unwrap()].account_id.clone();
 
-    let project_categories = get_jirascope().get_project_categories()?;
+    let project_categories = get_jirascope().get_project_categories().fetch_all()?;
 
     let category_id = if project_categories.is_empty() {
         None
@@ -181,7 +185,9 @@ fn edit_interactive(env: &Env) -> Result<()> {
 
     let project = project.unwrap();
 
-    let key = utils::prompt_string(env, "Enter new project key (leave empty for no change): ");
+    let key = utils::prompt_string(env, "Enter new project key (leave empty for no change): ")
+        .map(|key| key.parse::<ProjectKey>())
+        .transpose()?;
 
     let name = utils::prompt_string(env, "Enter new project name (leave empty for no change): ");
 
@@ -202,7 +208,8 @@ fn edit_interactive(env: &Env) -> Result<()> {
     };
 
     let users = get_jirascope()
-        .get_users()?
+        .get_users()
+        .fetch_all()?
         .into_iter()
         .filter(|u| u.active && u.account_type == "atlassian")
         .collect::<Vec<_>>();
@@ -219,7 +226,7 @@ fn edit_interactive(env: &Env) -> Result<()> {
 
     let lead_account_id = index.map(|index| users[index].account_id.clone());
 
-    let mut categories = get_jirascope().get_project_categories()?;
+    let mut categories = get_jirascope().get_project_categories().fetch_all()?;
     categories.push(ProjectCategory {
         id: 0,
         name: "None".to_string(),
@@ -263,7 +270,7 @@ fn edit_interactive(env: &Env) -> Result<()> {
     };
 
     workthread_spawn(move || {
-        let result = get_jirascope().edit_project(&*project.key, project_edit);
+        let result = get_jirascope().edit_project(&project.key, project_edit);
 
         if result.is_ok() {
             concurrent::push_command(Box::new(|env| {
@@ -300,11 +307,12 @@ fn edit_graphical_interactive(env: &Env) -> Result<()> {
         return Ok(());
     }
 
-    edit_graphical(env, project.unwrap().key)
+    edit_graphical(env, project.unwrap().key.into())
 }
 
 #[defun]
 fn edit_graphical(env: &Env, key: String) -> Result<()> {
+    let key: ProjectKey = key.parse()?;
     let project = get_state().get_project_detailed(&key);
 
     if project.is_none() {
@@ -362,12 +370,9 @@ fn edit_graphical_finish(env: &Env) -> Result<()> {
         .unwrap()
         .trim_start_matches("* ")
         .trim_end_matches(" *")
-        .to_string();
+        .parse::<ProjectKey>()?;
 
-    project_edit.key = match key.as_str() {
-        "" => None,
-        _ => Some(key.clone()),
-    };
+    project_edit.key = Some(key.clone());
 
     project_edit.name = edited_project
         .lines()
@@ -402,7 +407,7 @@ fn edit_graphical_finish(env: &Env) -> Result<()> {
 
     project_edit.lead_account_id = match lead_str.as_deref() {
         Some(lead_str) => {
-            let users = get_jirascope().get_users()?;
+            let users = get_jirascope().get_users().fetch_all()?;
 
             let user = users.into_iter().find(|u| u.display_name == lead_str);
 
@@ -422,7 +427,7 @@ fn edit_graphical_finish(env: &Env) -> Result<()> {
                 display_old_and_changed(env)?;
 
                 if prompt_force_change(env, "Project changed since last access")? {
-                    let result = get_jirascope().edit_project(og_key.as_str(), project_edit);
+                    let result = get_jirascope().edit_project(&og_key, project_edit);
 
                     state::get_state_mut().return_project();
 
@@ -438,7 +443,7 @@ fn edit_graphical_finish(env: &Env) -> Result<()> {
             return;
         }
 
-        let result = get_jirascope().edit_project(og_key.as_str(), project_edit);
+        let result = get_jirascope().edit_project(&og_key, project_edit);
 
         state::get_state_mut().return_project();
 
@@ -556,7 +561,7 @@ fn display_old_and_changed(env: &Env) -> Result<()> {
 
 #[defun]
 fn delete_interactive(env: &Env) -> Result<Value<'_>> {
-    let projects = get_jirascope().get_projects()?;
+    let projects = get_jirascope().get_projects().fetch_all()?;
 
     let index = utils::prompt_select_index(
         env,
@@ -574,8 +579,33 @@ fn delete_interactive(env: &Env) -> Result<Value<'_>> {
 
     let project_key = projects[index.unwrap()].key.clone();
 
+    let confirmation = if get_jirascope()
+        .policy()
+        .requires_confirmation(Guarded::DeleteProject)
+    {
+        let typed = utils::prompt_string(
+            env,
+            &format!(
+                "Deleting {} deletes all of its issues, type {} to confirm: ",
+                project_key, project_key
+            ),
+        );
+
+        match typed {
+            Some(typed) => Some(Confirmation::new(typed)),
+            None => return utils::nil(env),
+        }
+    } else {
+        None
+    };
+
     workthread_spawn(move || {
-        let result = get_jirascope().delete_project(&*project_key);
+        let result = match confirmation {
+            Some(ref confirmation) => {
+                get_jirascope().delete_project_confirmed(&project_key, confirmation)
+            }
+            None => get_jirascope().delete_project(&project_key),
+        };
 
         if result.is_ok() {
             concurrent::push_command(Box::new(move |env| {
@@ -585,9 +615,10 @@ fn delete_interactive(env: &Env) -> Result<Value<'_>> {
 
                 Ok(())
             }));
-        } else {
-            concurrent::push_command(Box::new(|env| {
-                env.message("Failed to delete project.")?;
+        } else if let Err(err) = result {
+            let message = err.to_string();
+            concurrent::push_command(Box::new(move |env| {
+                env.message(format!("Failed to delete project: {}", message))?;
 
                 Ok(())
             }));
@@ -0,0 +1,251 @@
+//! A queue view for service desk agents. A queue lists its requests with
+//! their breached SLAs, a request shows its SLAs and comments, internal ones
+//! included, and can be answered publicly or internally.
+
+use std::sync::Mutex;
+
+use emacs::{defun, Env, IntoLisp, Result, Value};
+use jirascope_core::jira::{IssueKey, Queue, ServiceDesk, Sla};
+
+use crate::{
+    concurrent::{self, workthread_spawn},
+    get_jirascope,
+    utils::{
+        self, current_buffer_button, current_buffer_face_print, current_buffer_face_println,
+        current_buffer_print, current_buffer_println, open_jirascope_buffer,
+    },
+};
+
+static JIRASCOPE_FACE_SLA_BREACHED: &str = "jirascope-sla-breached";
+/// At most this many requests of a queue are shown, each costs a request
+/// for its SLAs.
+static QUEUE_ISSUE_LIMIT: usize = 50;
+
+// the request shown last, which comments go to
+static CURRENT_REQUEST: Mutex<Option<IssueKey>> = Mutex::new(None);
+
+fn prompt_service_desk(env: &Env) -> Result<Option<ServiceDesk>> {
+    let mut service_desks = get_jirascope().get_service_desks().fetch_all()?;
+
+    if service_desks.len() == 1 {
+        return Ok(service_desks.pop());
+    }
+
+    let index = utils::prompt_select_index(
+        env,
+        "Choose service desk: ",
+        service_desks
+            .iter()
+            .map(|s| s.project_name.clone())
+            .collect::<Vec<_>>()
+            .as_slice(),
+    );
+
+    Ok(index.map(|index| service_desks.remove(index)))
+}
+
+fn prompt_queue(env: &Env, service_desk: &ServiceDesk) -> Result<Option<Queue>> {
+    let mut queues = get_jirascope().get_queues(service_desk.id).fetch_all()?;
+
+    let index = utils::prompt_select_index(
+        env,
+        "Choose queue: ",
+        queues
+            .iter()
+            .map(|q| match q.issue_count {
+                Some(count) => format!("{} ({})", q.name, count),
+                None => q.name.clone(),
+            })
+            .collect::<Vec<_>>()
+            .as_slice(),
+    );
+
+    Ok(index.map(|index| queues.remove(index)))
+}
+
+fn print_slas(env: &Env, slas: &[Sla]) -> Result<()> {
+    for sla in slas {
+        let remaining = sla
+            .ongoing_cycle
+            .as_ref()
+            .or(sla.completed_cycles.last())
+            .map(|cycle| cycle.remaining_time.friendly.as_str())
+            .unwrap_or("-");
+        let line = format!("  {}: {} remaining", sla.name, remaining);
+
+        if sla.breached() {
+            current_buffer_face_println(
+                env,
+                &format!("{} (breached)", line),
+                JIRASCOPE_FACE_SLA_BREACHED,
+            )?;
+        } else {
+            current_buffer_println(env, &line)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn display_queue(env: &Env, service_desk: &ServiceDesk, queue: &Queue) -> Result<()> {
+    let jirascope = get_jirascope();
+    let issues = jirascope
+        .get_queue_issues(service_desk.id, queue.id)
+        .with_limit(QUEUE_ISSUE_LIMIT)
+        .fetch_all()?;
+
+    open_jirascope_buffer(env)?;
+
+    current_buffer_face_println(
+        env,
+        &format!("* {} - {} *", service_desk.project_name, queue.name),
+        "jirascope-project-key",
+    )?;
+
+    if issues.is_empty() {
+        current_buffer_println(env, "No requests.")?;
+    }
+
+    for issue in issues {
+        current_buffer_button(env, &issue.key, "jirascope-request-button")?;
+        current_buffer_print(env, &format!(": {} - {}", issue.summary(), issue.status()))?;
+
+        let breached = jirascope
+            .get_request_slas(&issue.key)
+            .fetch_all()?
+            .into_iter()
+            .filter(Sla::breached)
+            .map(|sla| sla.name)
+            .collect::<Vec<_>>();
+        if !breached.is_empty() {
+            current_buffer_face_print(
+                env,
+                &format!(" [breached: {}]", breached.join(", ")),
+                JIRASCOPE_FACE_SLA_BREACHED,
+            )?;
+        }
+
+        current_buffer_println(env, "")?;
+    }
+
+    utils::set_buffer_mode(env, utils::JirascopeBufferMode::Queue)?;
+
+    Ok(())
+}
+
+#[defun]
+fn display_request(env: &Env, issue_key: String) -> Result<()> {
+    let issue_key: IssueKey = issue_key.parse()?;
+    let jirascope = get_jirascope();
+    let request = jirascope.get_customer_request(&issue_key)?;
+    let slas = jirascope.get_request_slas(&issue_key).fetch_all()?;
+    let comments = jirascope.get_request_comments(&issue_key).fetch_all()?;
+
+    open_jirascope_buffer(env)?;
+
+    current_buffer_face_println(env, &format!("* {} *", issue_key), "jirascope-issue-key")?;
+
+    current_buffer_println(env, &format!("Summary: {}", request.summary()))?;
+
+    if let Some(ref status) = request.current_status {
+        current_buffer_println(env, &format!("Status: {}", status.status))?;
+    }
+
+    if let Some(ref reporter) = request.reporter {
+        current_buffer_println(env, &format!("Reporter: {}", reporter.display_name))?;
+    }
+
+    current_buffer_println(env, &format!("Created: {}", request.created_date.friendly))?;
+
+    current_buffer_println(env, "SLAs:")?;
+    print_slas(env, &slas)?;
+
+    current_buffer_println(env, "Comments:")?;
+    for comment in comments {
+        let author = comment
+            .author
+            .map(|a| a.display_name)
+            .unwrap_or_else(|| "Anonymous".to_string());
+        let created = comment.created.map(|c| c.friendly).unwrap_or_default();
+        let visibility = if comment.public { "public" } else { "internal" };
+
+        current_buffer_println(
+            env,
+            &format!(
+                "  [{}] {} {}: {}",
+                visibility, author, created, comment.body
+            ),
+        )?;
+    }
+
+    *CURRENT_REQUEST.lock().unwrap() = Some(issue_key);
+    utils::set_buffer_mode(env, utils::JirascopeBufferMode::Request)?;
+
+    Ok(())
+}
+
+#[defun]
+fn request_button_action(env: &Env, button: Value<'_>) -> Result<()> {
+    let button_content = env.call("button-label", [button])?.into_rust::<String>()?;
+
+    display_request(env, button_content)
+}
+
+#[defun]
+fn queue_open_interactive(env: &Env) -> Result<()> {
+    let service_desk = match prompt_service_desk(env)? {
+        Some(service_desk) => service_desk,
+        None => return Ok(()),
+    };
+
+    let queue = match prompt_queue(env, &service_desk)? {
+        Some(queue) => queue,
+        None => return Ok(()),
+    };
+
+    display_queue(env, &service_desk, &queue)
+}
+
+#[defun]
+fn comment_interactive(env: &Env) -> Result<()> {
+    let current = CURRENT_REQUEST.lock().unwrap().clone();
+    let issue_key = match current {
+        Some(issue_key) => issue_key,
+        None => match utils::prompt_string(env, "Enter request key: ") {
+            Some(issue_key) => issue_key.parse::<IssueKey>()?,
+            None => return Ok(()),
+        },
+    };
+
+    let body = match utils::prompt_string(env, &format!("Comment on {}: ", issue_key)) {
+        Some(body) => body,
+        None => return Ok(()),
+    };
+
+    let public = env
+        .call(
+            "y-or-n-p",
+            ["Show the comment to the customer? ".into_lisp(env)?],
+        )?
+        .is_not_nil();
+
+    workthread_spawn(move || {
+        let result = get_jirascope().add_request_comment(&issue_key, &body, public);
+
+        concurrent::push_command(Box::new(move |env| {
+            match result {
+                Ok(_) => {
+                    env.message("Comment added.")?;
+                    display_request(env, issue_key.into())?;
+                }
+                Err(err) => {
+                    env.message(format!("Failed to add comment: {}", err))?;
+                }
+            }
+
+            Ok(())
+        }));
+    });
+
+    Ok(())
+}
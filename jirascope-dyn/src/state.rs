@@ -1,15 +1,23 @@
-use std::sync::{OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::{
+    collections::HashMap,
+    sync::{OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
 
-use emacs::{defun, Env};
-use jirascope_core::jira::{Issue, Project, ProjectDetailed};
+use emacs::{defun, Env, IntoLisp};
+use jirascope_core::{
+    jira::{Filter, Issue, IssueKey, JiraDateTime, Project, ProjectDetailed, ProjectKey},
+    FieldSet, IssueSync, SnapshotStore, SyncChanges,
+};
 
 use crate::{
-    concurrent, get_jirascope,
-    utils::{self, current_buffer_button, current_buffer_print, current_buffer_println},
+    concurrent, get_jirascope, outbox,
+    utils::{self, current_buffer_button, current_buffer_print, current_buffer_println, nil},
     JIRASCOPE_BUFFER_NAME,
 };
 
 static STATE: OnceLock<RwLock<State>> = OnceLock::new();
+/// At most this many issues are shown under a filter.
+static FILTER_ISSUE_LIMIT: usize = 100;
 
 pub(crate) trait ConflictAware: Sized {
     type Key;
@@ -19,7 +27,7 @@ pub(crate) trait ConflictAware: Sized {
 }
 
 impl ConflictAware for Issue {
-    type Key = String;
+    type Key = IssueKey;
     fn key(&self) -> Self::Key {
         self.key.clone()
     }
@@ -32,7 +40,7 @@ impl ConflictAware for Issue {
 }
 
 impl ConflictAware for ProjectDetailed {
-    type Key = String;
+    type Key = ProjectKey;
     fn key(&self) -> Self::Key {
         self.key.clone()
     }
@@ -51,12 +59,44 @@ pub(crate) enum ConflictCell<T: ConflictAware> {
     Deleted { key: T::Key },
 }
 
+/// What changed during a [`State::refresh`].
+#[derive(Debug, Default)]
+pub(crate) struct StateChanges {
+    pub projects: bool,
+    /// The favourite filters, or the issues of a shown filter, changed.
+    pub filters: bool,
+    pub issues: SyncChanges,
+    /// Some updated issue moved to another project or parent.
+    pub moved: bool,
+}
+
+impl StateChanges {
+    pub fn is_empty(&self) -> bool {
+        !self.projects && !self.filters && self.issues.is_empty()
+    }
+}
+
+// where an issue sits in the tree
+fn placement(issue: &Issue) -> (ProjectKey, Option<i64>) {
+    (
+        issue.fields.project.key.clone(),
+        issue.fields.parent.as_ref().map(|p| p.id),
+    )
+}
+
 pub struct State {
     projects: Vec<ProjectDetailed>,
     issues: Vec<Issue>,
+    filters: Vec<Filter>,
+    // issues of the filters shown in the tree, by filter id
+    filter_issues: HashMap<i64, Vec<Issue>>,
     dirty: bool,
     issue_rentcell: ConflictCell<Issue>,
+    // the checked out issue as it was fetched for editing, with all its fields
+    issue_base: Option<Issue>,
     project_rentcell: ConflictCell<ProjectDetailed>,
+    store: Option<SnapshotStore>,
+    sync: IssueSync,
 }
 
 impl State {
@@ -64,9 +104,36 @@ impl State {
         Self {
             projects: Vec::new(),
             issues: Vec::new(),
+            filters: Vec::new(),
+            filter_issues: HashMap::new(),
             dirty: false,
             issue_rentcell: ConflictCell::Empty,
+            issue_base: None,
             project_rentcell: ConflictCell::Empty,
+            store: None,
+            // descriptions are loaded with the full issue when one is opened
+            sync: IssueSync::new().with_fields(FieldSet::lean()),
+        }
+    }
+
+    /// Fill the state from the on-disk snapshot, and keep it updated from now on.
+    pub fn attach_store(&mut self, store: SnapshotStore) -> Result<(), jirascope_core::Error> {
+        let snapshot = store.load();
+        self.store = Some(store);
+
+        if let Some(snapshot) = snapshot? {
+            self.projects = snapshot.projects;
+            self.issues = snapshot.issues;
+            self.dirty = true;
+        }
+
+        Ok(())
+    }
+
+    pub fn save_snapshot(&self) -> Result<(), jirascope_core::Error> {
+        match self.store {
+            Some(ref store) => store.save(&self.projects, &self.issues),
+            None => Ok(()),
         }
     }
 
@@ -75,11 +142,12 @@ impl State {
     }
 
     pub fn get_project_detailed(&self, key: &str) -> Option<ProjectDetailed> {
-        ProjectDetailed::lookup(&self.projects, &key.to_string())
+        self.projects.iter().find(|p| p.key == key).cloned()
     }
 
     pub fn get_project(&self, key: &str) -> Option<Project> {
-        ProjectDetailed::lookup(&self.projects, &key.to_string()).map(ProjectDetailed::to_project)
+        self.get_project_detailed(key)
+            .map(ProjectDetailed::to_project)
     }
 
     pub fn issues(&self) -> &[Issue] {
@@ -87,7 +155,30 @@ impl State {
     }
 
     pub fn get_issue(&self, key: &str) -> Option<Issue> {
-        Issue::lookup(&self.issues, &key.to_string())
+        self.issues.iter().find(|i| i.key == key).cloned()
+    }
+
+    /// The current user's favourite filters.
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    pub fn get_filter(&self, name: &str) -> Option<Filter> {
+        self.filters.iter().find(|f| f.name == name).cloned()
+    }
+
+    /// The issues of a filter shown in the tree, `None` if it isn't shown.
+    pub fn filter_issues(&self, filter_id: i64) -> Option<&[Issue]> {
+        self.filter_issues.get(&filter_id).map(Vec::as_slice)
+    }
+
+    pub fn show_filter(&mut self, filter_id: i64, issues: Vec<Issue>) {
+        self.filter_issues.insert(filter_id, issues);
+    }
+
+    /// Stop showing the issues of a filter. Returns whether they were shown.
+    pub fn hide_filter(&mut self, filter_id: i64) -> bool {
+        self.filter_issues.remove(&filter_id).is_some()
     }
 
     pub(crate) fn get_current_work_project(&self) -> &ConflictCell<ProjectDetailed> {
@@ -98,7 +189,7 @@ impl State {
         &self.issue_rentcell
     }
 
-    pub fn check_out_issue(&mut self, issue_key: String) -> Result<(), jirascope_core::Error> {
+    pub fn check_out_issue(&mut self, issue_key: IssueKey) -> Result<(), jirascope_core::Error> {
         match self.issue_rentcell {
             ConflictCell::Empty => {
                 self.issue_rentcell = ConflictCell::Armed { key: issue_key };
@@ -110,7 +201,26 @@ impl State {
         }
     }
 
-    pub fn check_out_project(&mut self, project_key: String) -> Result<(), jirascope_core::Error> {
+    /// Check out an issue fetched in full, which is what it is compared
+    /// against when it changes in the meantime.
+    pub fn check_out_full_issue(&mut self, issue: Issue) -> Result<(), jirascope_core::Error> {
+        self.check_out_issue(issue.key.clone())?;
+        self.issue_base = Some(issue);
+        Ok(())
+    }
+
+    /// `updated` of the checked out issue when it was fetched for editing.
+    pub fn checked_out_updated(&self, issue_key: &str) -> Option<JiraDateTime> {
+        self.issue_base
+            .as_ref()
+            .filter(|base| base.key == issue_key)
+            .map(|base| base.fields.updated)
+    }
+
+    pub fn check_out_project(
+        &mut self,
+        project_key: ProjectKey,
+    ) -> Result<(), jirascope_core::Error> {
         match self.project_rentcell {
             ConflictCell::Empty => {
                 self.project_rentcell = ConflictCell::Armed { key: project_key };
@@ -126,6 +236,7 @@ impl State {
         match self.issue_rentcell {
             ConflictCell::Armed { ref key } if key == issue_key.into() => {
                 self.issue_rentcell = ConflictCell::Empty;
+                self.issue_base = None;
                 true
             }
             _ => false,
@@ -134,6 +245,7 @@ impl State {
 
     pub fn return_issue(&mut self) {
         self.issue_rentcell = ConflictCell::Empty;
+        self.issue_base = None;
     }
 
     pub fn try_return_project<'a>(&mut self, project_key: impl Into<&'a str>) -> bool {
@@ -150,11 +262,15 @@ impl State {
         self.project_rentcell = ConflictCell::Empty;
     }
 
-    pub fn refresh(&mut self) -> Result<(), jirascope_core::Error> {
-        let new_projects = get_jirascope().get_projects()?;
+    pub(crate) fn refresh(&mut self) -> Result<StateChanges, jirascope_core::Error> {
+        self.dirty = false;
+        let mut changes = StateChanges::default();
+
+        let new_projects = get_jirascope().get_projects().fetch_all()?;
 
         if !new_projects.iter().eq(self.projects.iter()) {
             self.dirty = true;
+            changes.projects = true;
 
             if let ConflictCell::Armed { ref key } = self.project_rentcell {
                 if let Some(project) = ProjectDetailed::lookup(&new_projects, key) {
@@ -174,34 +290,75 @@ impl State {
 
         self.projects = new_projects;
 
-        let new_issues = get_jirascope()
-            .get_all_issues()
-            .map(|issues| issues.issues)?;
+        let new_filters = get_jirascope().get_favourite_filters().fetch_all()?;
+        // run the shown filters again, dropping the ones no longer favourite
+        let mut new_filter_issues = HashMap::new();
+        for filter in &new_filters {
+            if self.filter_issues.contains_key(&filter.id) {
+                new_filter_issues.insert(filter.id, run_filter(filter)?);
+            }
+        }
 
-        if !new_issues.iter().eq(self.issues.iter()) {
+        if new_filters != self.filters || new_filter_issues != self.filter_issues {
             self.dirty = true;
+            changes.filters = true;
+        }
 
-            if let ConflictCell::Armed { ref key } = self.issue_rentcell {
-                if let Some(issue) = Issue::lookup(&new_issues, key) {
-                    let old = Issue::lookup(&self.issues, key).expect("Issue not found");
-                    if issue.has_changed(&old) {
-                        self.issue_rentcell = ConflictCell::Outdated {
-                            key: key.clone(),
-                            old,
-                        };
-                    }
-                } else {
-                    self.issue_rentcell = ConflictCell::Deleted { key: key.clone() };
+        self.filters = new_filters;
+        self.filter_issues = new_filter_issues;
+
+        // keep the checked out issue as it was, to compare after the sync
+        let checked_out = match self.issue_rentcell {
+            ConflictCell::Armed { ref key } => self
+                .issue_base
+                .clone()
+                .filter(|base| base.key == *key)
+                .or_else(|| Issue::lookup(&self.issues, key)),
+            _ => None,
+        };
+        let placements = self
+            .issues
+            .iter()
+            .map(|i| (i.key.clone(), placement(i)))
+            .collect::<HashMap<_, _>>();
+
+        changes.issues = self.sync.sync(get_jirascope(), &mut self.issues)?;
+
+        changes.moved =
+            changes.issues.updated.iter().any(|key| {
+                self.get_issue(key).map(|i| placement(&i)) != placements.get(key).cloned()
+            });
+
+        if !changes.issues.is_empty() {
+            self.dirty = true;
+        }
+
+        if let Some(old) = checked_out {
+            if changes.issues.removed.contains(&old.key) {
+                self.issue_rentcell = ConflictCell::Deleted { key: old.key };
+            } else if changes.issues.updated.contains(&old.key) {
+                let issue = Issue::lookup(&self.issues, &old.key).expect("Issue not found");
+                if issue.has_changed(&old) {
+                    self.issue_rentcell = ConflictCell::Outdated {
+                        key: old.key.clone(),
+                        old,
+                    };
                 }
             }
         }
 
-        self.issues = new_issues;
-
-        Ok(())
+        Ok(changes)
     }
 }
 
+/// The issues of `filter` to show in the tree.
+pub(crate) fn run_filter(filter: &Filter) -> Result<Vec<Issue>, jirascope_core::Error> {
+    get_jirascope()
+        .run_filter(filter)
+        .with_limit(FILTER_ISSUE_LIMIT)
+        .fetch_all()
+}
+
 pub(crate) fn get_state<'a>() -> RwLockReadGuard<'a, State> {
     let s = STATE
         .get_or_init(|| RwLock::new(State::new()))
@@ -218,22 +375,42 @@ pub(crate) fn get_state_mut<'a>() -> RwLockWriteGuard<'a, State> {
     s
 }
 
-pub(crate) fn setup(refresh_interval: f64) {
+pub(crate) fn setup(refresh_interval: f64, store: Option<SnapshotStore>) {
+    if let Some(store) = store {
+        if let Err(err) = get_state_mut().attach_store(store) {
+            eprintln!("Error loading snapshot: {}", err);
+        }
+    }
+
     let refresh_interval = std::time::Duration::from_secs_f64(refresh_interval);
     std::thread::spawn(move || loop {
         let mut state = get_state_mut();
-        match state.refresh() {
-            Ok(_) => {
+        let online = match state.refresh() {
+            Ok(changes) => {
                 if state.dirty {
-                    concurrent::push_command(Box::new(|env| {
-                        update_buffers(env, &get_state());
+                    if let Err(err) = state.save_snapshot() {
+                        eprintln!("Error saving snapshot: {}", err);
+                    }
+
+                    concurrent::push_command(Box::new(move |env| {
+                        update_buffers(env, &get_state(), &changes);
                         Ok(())
                     }));
                 }
+                true
             }
-            Err(err) => eprintln!("Error refreshing state: {}", err),
-        }
+            Err(err) => {
+                eprintln!("Error refreshing state: {}", err);
+                false
+            }
+        };
         drop(state);
+
+        // the server is reachable again, send what was queued meanwhile
+        if online {
+            outbox::replay();
+        }
+
         std::thread::sleep(refresh_interval);
     });
 }
@@ -241,9 +418,10 @@ pub(crate) fn setup(refresh_interval: f64) {
 pub(crate) fn refresh(env: &Env) -> Result<(), jirascope_core::Error> {
     let mut state = get_state_mut();
     match state.refresh() {
-        Ok(_) => {
+        Ok(changes) => {
             if state.dirty {
-                update_buffers(env, &state);
+                state.save_snapshot()?;
+                update_buffers(env, &state, &changes);
             }
             Ok(())
         }
@@ -251,12 +429,35 @@ pub(crate) fn refresh(env: &Env) -> Result<(), jirascope_core::Error> {
     }
 }
 
-fn update_buffers(env: &Env, state: &State) {
+pub(crate) fn update_buffers(env: &Env, state: &State, changes: &StateChanges) {
+    if changes.is_empty() {
+        return;
+    }
+
     if let Some(utils::JirascopeBufferMode::Tree) = utils::get_buffer_mode() {
         utils::with_buffer(env, JIRASCOPE_BUFFER_NAME, |env| {
-            env.call("erase-buffer", [])?;
+            env.call("set", [env.intern("buffer-read-only")?, nil(env)?])?;
+
+            // issues which only changed their content are updated in place,
+            // anything that changes the shape of the tree redraws it
+            let redraw = changes.projects
+                || changes.filters
+                || changes.moved
+                || !changes.issues.added.is_empty()
+                || !changes.issues.removed.is_empty()
+                || !changes
+                    .issues
+                    .updated
+                    .iter()
+                    .all(|key| update_tree_line(env, state, key).unwrap_or(false));
+
+            if redraw {
+                env.call("erase-buffer", [])?;
+
+                print_tree(env, state)?;
+            }
 
-            print_tree(env, state)?;
+            env.call("set", [env.intern("buffer-read-only")?, env.intern("t")?])?;
 
             Ok(())
         })
@@ -264,6 +465,44 @@ fn update_buffers(env: &Env, state: &State) {
     }
 }
 
+/// Replace the summary and status on the tree lines of `key`, the issue can
+/// be listed under filters too. Returns `false` if there's no line to update.
+fn update_tree_line(env: &Env, state: &State, key: &str) -> emacs::Result<bool> {
+    let issue = match state.get_issue(key) {
+        Some(issue) => issue,
+        None => return Ok(false),
+    };
+
+    let pattern = format!(
+        "^[ ├└]*{}: ",
+        env.call("regexp-quote", [key.to_string().into_lisp(env)?])?
+            .into_rust::<String>()?
+    );
+
+    env.call("goto-char", [env.call("point-min", [])?])?;
+    let mut updated = false;
+
+    while env
+        .call(
+            "re-search-forward",
+            [pattern.clone().into_lisp(env)?, nil(env)?, env.intern("t")?],
+        )?
+        .is_not_nil()
+    {
+        env.call(
+            "delete-region",
+            [env.call("point", [])?, env.call("line-end-position", [])?],
+        )?;
+        current_buffer_print(
+            env,
+            &format!("{} - {}", issue.fields.summary, issue.fields.status.name),
+        )?;
+        updated = true;
+    }
+
+    Ok(updated)
+}
+
 #[defun]
 pub fn open(env: &emacs::Env) -> emacs::Result<()> {
     let state = get_state();
@@ -339,5 +578,42 @@ fn print_tree(env: &emacs::Env, state: &State) -> emacs::Result<()> {
         }
     }
 
+    print_filters(env, state)
+}
+
+fn print_filters(env: &emacs::Env, state: &State) -> emacs::Result<()> {
+    if state.filters().is_empty() {
+        return Ok(());
+    }
+
+    current_buffer_println(env, "Filters")?;
+
+    let size = state.filters().len();
+    for (i, filter) in state.filters().iter().enumerate() {
+        current_buffer_print(env, &format!("{} ", get_icon(i, size)))?;
+
+        current_buffer_button(env, &filter.name, "jirascope-filter-button")?;
+        current_buffer_println(env, &format!(": {}", filter.jql))?;
+
+        let issues = match state.filter_issues(filter.id) {
+            Some(issues) => issues,
+            None => continue,
+        };
+
+        if issues.is_empty() {
+            current_buffer_println(env, "  (no issues)")?;
+        }
+
+        for (i, issue) in issues.iter().enumerate() {
+            current_buffer_print(env, &format!("  {} ", get_icon(i, issues.len())))?;
+
+            current_buffer_button(env, &issue.key, "jirascope-issue-button")?;
+            current_buffer_println(
+                env,
+                &format!(": {} - {}", issue.fields.summary, issue.fields.status.name),
+            )?;
+        }
+    }
+
     Ok(())
 }
@@ -7,6 +7,8 @@ use crate::{concurrent, state, JIRASCOPE_BUFFER_NAME, JIRASCOPE_DIFF_BUFFER_NAME
 pub(crate) static JIRASCOPE_FACE_DIFF_ALERT: &str = "jirascope-diff-alert";
 pub(crate) static JIRASCOPE_FACE_DIFF_NEW: &str = "jirascope-diff-new";
 pub(crate) static JIRASCOPE_FACE_DIFF_OLD: &str = "jirascope-diff-old";
+pub(crate) static JIRASCOPE_FACE_DIFF_ADDED: &str = "jirascope-diff-added";
+pub(crate) static JIRASCOPE_FACE_DIFF_REMOVED: &str = "jirascope-diff-removed";
 
 pub fn nil(env: &Env) -> Result<Value<'_>> {
     ().into_lisp(env)
@@ -322,16 +324,12 @@ pub fn current_buffer_println(env: &Env, s: &str) -> Result<()> {
 }
 
 pub fn current_buffer_face_print(env: &Env, s: &str, face: &str) -> Result<()> {
-    let len = s.len();
-    let current_point = env.call("point", [])?.into_rust::<i64>()? - 1;
+    // points count characters, so take them from emacs rather than the byte
+    // length, which is wrong as soon as the text isn't ascii
+    let start = env.call("point", [])?;
     current_buffer_print(env, s)?;
-    let overlay = env.call(
-        "make-overlay",
-        [
-            current_point.into_lisp(env)?,
-            (current_point + 1 + len as i64).into_lisp(env)?,
-        ],
-    )?;
+    let end = env.call("point", [])?;
+    let overlay = env.call("make-overlay", [start, end])?;
 
     env.call(
         "overlay-put",
@@ -362,6 +360,8 @@ pub enum JirascopeBufferMode {
     Tree,
     IssueEdit,
     ProjectEdit,
+    Queue,
+    Request,
 }
 
 static JIRASCOPE_BUFFER_MODE: Mutex<JirascopeBufferMode> = Mutex::new(JirascopeBufferMode::Issue);
@@ -0,0 +1,665 @@
+//! A small, in-memory mock of the Jira Cloud REST API.
+//!
+//! Covers what jirascope needs to connect and work with issues: server info,
+//! the current user, time tracking options, users, projects, issue creation
+//! metadata, fetching and editing issues, search, and saved filters.
+//! Every request must carry a `Basic` authorization header.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+pub const ISSUE_COUNT: usize = 3;
+pub const USER_COUNT: usize = 5;
+/// Like Jira, `users/search` returns fewer users than asked for past this.
+pub const MAX_USER_PAGE: usize = 3;
+
+#[derive(Clone)]
+struct Jira {
+    issues: Arc<Mutex<Vec<Value>>>,
+    filters: Arc<Mutex<Vec<Value>>>,
+}
+
+pub fn routes() -> Router {
+    let jira = Jira {
+        issues: Arc::new(Mutex::new((1..=ISSUE_COUNT).map(issue).collect())),
+        filters: Arc::new(Mutex::new(vec![filter()])),
+    };
+
+    Router::new()
+        .route("/serverInfo", get(server_info))
+        .route("/myself", get(myself))
+        .route(
+            "/configuration/timetracking/options",
+            get(time_tracking_options),
+        )
+        .route("/users/search", get(users))
+        .route("/project", get(projects))
+        .route("/project/search", get(search_projects))
+        .route(
+            "/issue/:key",
+            get(get_issue).put(edit_issue).delete(delete_issue),
+        )
+        .route("/search", get(search))
+        .route("/search/jql", get(search_jql))
+        .route("/search/approximate-count", post(approximate_count))
+        .route(
+            "/issue/createmeta/:project/issuetypes",
+            get(creation_issue_types),
+        )
+        .route(
+            "/issue/createmeta/:project/issuetypes/:id",
+            get(creation_fields),
+        )
+        .route("/filter", post(create_filter))
+        .route("/filter/favourite", get(favourite_filters))
+        .route("/filter/my", get(my_filters))
+        .route("/filter/search", get(search_filters))
+        .route("/filter/:id", get(get_filter).delete(delete_filter))
+        .route(
+            "/filter/:id/permission",
+            get(filter_permissions).post(share_filter),
+        )
+        .route("/filter/:id/permission/:permission", delete(unshare_filter))
+        .layer(middleware::from_fn(require_auth))
+        .with_state(jira)
+}
+
+pub(crate) fn error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({ "errorMessages": [message], "errors": {} })),
+    )
+        .into_response()
+}
+
+pub(crate) async fn require_auth<B>(
+    headers: HeaderMap,
+    request: axum::http::Request<B>,
+    next: Next<B>,
+) -> Response {
+    let authorized = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.starts_with("Basic "))
+        .unwrap_or(false);
+
+    if !authorized {
+        return error(StatusCode::UNAUTHORIZED, "Client must be authenticated.");
+    }
+
+    next.run(request).await
+}
+
+pub(crate) fn user() -> Value {
+    json!({
+        "displayName": "Test User",
+        "active": true,
+        "accountId": "5b10a2844c20165700ede21g",
+        "accountType": "atlassian"
+    })
+}
+
+fn other_user(n: usize) -> Value {
+    json!({
+        "displayName": format!("User {}", n),
+        "active": true,
+        "accountId": format!("5b10a2844c20165700ede{:03}", n),
+        "accountType": "atlassian"
+    })
+}
+
+fn project() -> Value {
+    json!({
+        "id": "10000",
+        "key": "TEST",
+        "name": "Test project",
+        "description": "Issues served by the test server",
+        "lead": user(),
+        "url": null
+    })
+}
+
+// TEST-3 is a subtask of TEST-1, TEST-1 is due and estimated at a day of 6 hours
+fn issue(n: usize) -> Value {
+    let mut issue = json!({
+        "id": (10000 + n).to_string(),
+        "key": format!("TEST-{}", n),
+        "fields": {
+            "summary": format!("Test issue {}", n),
+            "description": {
+                "version": 1,
+                "type": "doc",
+                "content": [{
+                    "type": "paragraph",
+                    "content": [{ "type": "text", "text": format!("Description of issue {}", n) }]
+                }]
+            },
+            "priority": { "name": "Medium" },
+            "status": { "id": "1", "name": "To Do" },
+            "assignee": null,
+            "reporter": user(),
+            "created": "2023-05-01T10:00:00.000+0000",
+            "updated": "2023-05-01T10:00:00.000+0000",
+            "duedate": null,
+            "resolutiondate": null,
+            "project": { "id": "10000", "key": "TEST", "name": "Test project" },
+            "subtasks": []
+        }
+    });
+
+    match n {
+        1 => {
+            issue["fields"]["subtasks"] = json!([{ "id": "10003", "key": "TEST-3" }]);
+            issue["fields"]["duedate"] = json!("2023-05-10");
+            issue["fields"]["timetracking"] = json!({
+                "originalEstimate": "1d",
+                "remainingEstimate": "1d",
+                "originalEstimateSeconds": 21600,
+                "remainingEstimateSeconds": 21600
+            });
+        }
+        3 => issue["fields"]["parent"] = json!({ "id": "10001", "key": "TEST-1" }),
+        _ => {}
+    }
+
+    issue
+}
+
+async fn server_info() -> Json<Value> {
+    Json(json!({
+        "baseUrl": "http://localhost",
+        "version": "1001.0.0-SNAPSHOT",
+        "serverTitle": "Jirascope test server",
+        "deploymentType": "Cloud"
+    }))
+}
+
+async fn myself() -> Json<Value> {
+    Json(user())
+}
+
+async fn time_tracking_options() -> Json<Value> {
+    Json(json!({
+        "workingHoursPerDay": 6,
+        "workingDaysPerWeek": 5,
+        "timeFormat": "pretty",
+        "defaultUnit": "minute"
+    }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageQuery {
+    #[serde(default)]
+    start_at: usize,
+    max_results: Option<usize>,
+}
+
+// A bare array, the client has to notice the last page by its size
+async fn users(Query(query): Query<PageQuery>) -> Json<Value> {
+    let users = (1..=USER_COUNT)
+        .map(other_user)
+        .skip(query.start_at)
+        .take(query.max_results.unwrap_or(50).min(MAX_USER_PAGE))
+        .collect::<Vec<_>>();
+
+    Json(json!(users))
+}
+
+async fn projects() -> Json<Value> {
+    Json(json!([project()]))
+}
+
+async fn search_projects(Query(query): Query<PageQuery>) -> Json<Value> {
+    let projects = [project()]
+        .into_iter()
+        .skip(query.start_at)
+        .take(query.max_results.unwrap_or(50))
+        .collect::<Vec<_>>();
+
+    Json(json!({
+        "startAt": query.start_at,
+        "maxResults": query.max_results.unwrap_or(50),
+        "total": 1,
+        "isLast": query.start_at + projects.len() >= 1,
+        "values": projects
+    }))
+}
+
+#[derive(Deserialize)]
+struct IssueQuery {
+    fields: Option<String>,
+    expand: Option<String>,
+}
+
+// Drops fields which weren't asked for, and renders the description when
+// `renderedFields` is expanded
+fn shape_issue(issue: &Value, fields: Option<&str>, expand: Option<&str>) -> Value {
+    let mut issue = issue.clone();
+
+    if let Some(fields) = fields.filter(|f| !f.starts_with('*')) {
+        let fields = fields.split(',').collect::<Vec<_>>();
+        if let Some(all) = issue["fields"].as_object_mut() {
+            all.retain(|k, _| fields.contains(&k.as_str()));
+        }
+    }
+
+    if expand.map(|e| e.split(',').any(|e| e == "renderedFields")) == Some(true) {
+        let text = &issue["fields"]["description"]["content"][0]["content"][0]["text"];
+        issue["renderedFields"] = match text.as_str() {
+            Some(text) => json!({ "description": format!("<p>{}</p>", text) }),
+            None => json!({ "description": null }),
+        };
+    }
+
+    issue
+}
+
+async fn get_issue(
+    State(jira): State<Jira>,
+    Path(key): Path<String>,
+    Query(query): Query<IssueQuery>,
+) -> Response {
+    let issues = jira.issues.lock().await;
+
+    match issues
+        .iter()
+        .find(|i| i["key"] == key.as_str() || i["id"] == key.as_str())
+    {
+        Some(issue) => Json(shape_issue(
+            issue,
+            query.fields.as_deref(),
+            query.expand.as_deref(),
+        ))
+        .into_response(),
+        None => error(
+            StatusCode::NOT_FOUND,
+            "Issue does not exist or you do not have permission to see it.",
+        ),
+    }
+}
+
+async fn edit_issue(
+    State(jira): State<Jira>,
+    Path(key): Path<String>,
+    Json(edit): Json<Value>,
+) -> Response {
+    let mut issues = jira.issues.lock().await;
+
+    let issue = match issues.iter_mut().find(|i| i["key"] == key.as_str()) {
+        Some(issue) => issue,
+        None => {
+            return error(
+                StatusCode::NOT_FOUND,
+                "Issue does not exist or you do not have permission to see it.",
+            )
+        }
+    };
+
+    if let Some(summary) = edit["fields"].get("summary") {
+        if summary.as_str().map(str::is_empty).unwrap_or(true) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "errorMessages": [], "errors": { "summary": "You must specify a summary of the issue." } })),
+            )
+                .into_response();
+        }
+        issue["fields"]["summary"] = summary.clone();
+    }
+    if let Some(timetracking) = edit["fields"].get("timetracking") {
+        for field in ["originalEstimate", "remainingEstimate"] {
+            // only minutes, which is what jirascope sends
+            let minutes = timetracking[field]
+                .as_str()
+                .and_then(|m| m.strip_suffix('m'))
+                .and_then(|m| m.parse::<u64>().ok());
+
+            if let Some(minutes) = minutes {
+                let time_tracking = &mut issue["fields"]["timetracking"];
+                time_tracking[field] = json!(format!("{}m", minutes));
+                time_tracking[format!("{}Seconds", field)] = json!(minutes * 60);
+            }
+        }
+    }
+    issue["fields"]["updated"] = json!("2023-05-02T10:00:00.000+0000");
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+// JQL is ignored, every search returns all issues
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteQuery {
+    #[serde(default)]
+    delete_subtasks: bool,
+}
+
+async fn delete_issue(
+    State(jira): State<Jira>,
+    Path(key): Path<String>,
+    Query(query): Query<DeleteQuery>,
+) -> Response {
+    let mut issues = jira.issues.lock().await;
+
+    let subtasks = match issues.iter().find(|i| i["key"] == key.as_str()) {
+        Some(issue) => issue["fields"]["subtasks"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default(),
+        None => {
+            return error(
+                StatusCode::NOT_FOUND,
+                "Issue does not exist or you do not have permission to see it.",
+            )
+        }
+    };
+
+    if !subtasks.is_empty() && !query.delete_subtasks {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "The issue has subtasks, set deleteSubtasks to true to delete them too.",
+        );
+    }
+
+    issues.retain(|i| i["key"] != key.as_str() && !subtasks.iter().any(|s| s["key"] == i["key"]));
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn search(State(jira): State<Jira>, Query(query): Query<PageQuery>) -> Json<Value> {
+    let issues = jira.issues.lock().await;
+    let max_results = query.max_results.unwrap_or(50);
+
+    let page = issues
+        .iter()
+        .skip(query.start_at)
+        .take(max_results)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    Json(json!({
+        "startAt": query.start_at,
+        "maxResults": max_results,
+        "total": issues.len(),
+        "issues": page
+    }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JqlSearchQuery {
+    next_page_token: Option<String>,
+    max_results: Option<usize>,
+    fields: Option<String>,
+    expand: Option<String>,
+}
+
+// JQL is ignored here too. The token is just the offset of the next page,
+// real ones are opaque.
+async fn search_jql(State(jira): State<Jira>, Query(query): Query<JqlSearchQuery>) -> Response {
+    let issues = jira.issues.lock().await;
+    let max_results = query.max_results.unwrap_or(50);
+
+    let start_at = match query.next_page_token.as_deref().map(str::parse::<usize>) {
+        None => 0,
+        Some(Ok(start_at)) => start_at,
+        Some(Err(_)) => return error(StatusCode::BAD_REQUEST, "Invalid nextPageToken."),
+    };
+
+    let page = issues
+        .iter()
+        .skip(start_at)
+        .take(max_results)
+        .map(|i| shape_issue(i, query.fields.as_deref(), query.expand.as_deref()))
+        .collect::<Vec<_>>();
+    let next = start_at + page.len();
+
+    let mut body = json!({ "issues": page, "isLast": next >= issues.len() });
+    if next < issues.len() {
+        body["nextPageToken"] = json!(next.to_string());
+    }
+
+    Json(body).into_response()
+}
+
+async fn approximate_count(State(jira): State<Jira>) -> Json<Value> {
+    Json(json!({ "count": jira.issues.lock().await.len() }))
+}
+
+fn issue_types() -> Vec<Value> {
+    vec![
+        json!({ "id": "10001", "name": "Task", "description": "A task", "subtask": false }),
+        json!({ "id": "10002", "name": "Bug", "description": "A problem", "subtask": false }),
+        json!({ "id": "10003", "name": "Subtask", "description": "Part of a task", "subtask": true }),
+    ]
+}
+
+async fn creation_issue_types(
+    Path(project): Path<String>,
+    Query(query): Query<PageQuery>,
+) -> Response {
+    if project != "TEST" && project != "10000" {
+        return error(StatusCode::NOT_FOUND, "No project could be found.");
+    }
+
+    let issue_types = issue_types();
+    let max_results = query.max_results.unwrap_or(50);
+    let page = issue_types
+        .iter()
+        .skip(query.start_at)
+        .take(max_results)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    Json(json!({
+        "startAt": query.start_at,
+        "maxResults": max_results,
+        "total": issue_types.len(),
+        "issueTypes": page
+    }))
+    .into_response()
+}
+
+async fn creation_fields(Path((project, id)): Path<(String, String)>) -> Response {
+    if project != "TEST" && project != "10000" {
+        return error(StatusCode::NOT_FOUND, "No project could be found.");
+    }
+    if !issue_types().iter().any(|t| t["id"] == id.as_str()) {
+        return error(StatusCode::NOT_FOUND, "Issue type not found.");
+    }
+
+    Json(json!({
+        "startAt": 0,
+        "maxResults": 50,
+        "total": 1,
+        "fields": [{
+            "fieldId": "summary",
+            "key": "summary",
+            "name": "Summary",
+            "required": true,
+            "schema": { "type": "string", "system": "summary" },
+            "operations": ["set"],
+            "hasDefaultValue": false
+        }]
+    }))
+    .into_response()
+}
+
+const FILTER_NOT_FOUND: &str = "The selected filter is not available to you, perhaps it has been deleted or had its permissions changed.";
+
+fn filter() -> Value {
+    json!({
+        "id": "10000",
+        "name": "Open test issues",
+        "description": "Everything not done in TEST",
+        "owner": user(),
+        "jql": "project = TEST AND statusCategory != Done",
+        "favourite": true,
+        "sharePermissions": []
+    })
+}
+
+async fn favourite_filters(State(jira): State<Jira>) -> Json<Value> {
+    let filters = jira.filters.lock().await;
+
+    Json(json!(filters
+        .iter()
+        .filter(|f| f["favourite"] == true)
+        .collect::<Vec<_>>()))
+}
+
+// every filter belongs to the test user
+async fn my_filters(State(jira): State<Jira>) -> Json<Value> {
+    Json(json!(*jira.filters.lock().await))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FilterSearchQuery {
+    #[serde(default)]
+    filter_name: String,
+    #[serde(default)]
+    start_at: usize,
+    max_results: Option<usize>,
+}
+
+async fn search_filters(
+    State(jira): State<Jira>,
+    Query(query): Query<FilterSearchQuery>,
+) -> Json<Value> {
+    let filters = jira.filters.lock().await;
+    let name = query.filter_name.to_lowercase();
+    let matching = filters
+        .iter()
+        .filter(|f| {
+            f["name"]
+                .as_str()
+                .map(|n| n.to_lowercase().contains(&name))
+                .unwrap_or(false)
+        })
+        .collect::<Vec<_>>();
+    let page = matching
+        .iter()
+        .skip(query.start_at)
+        .take(query.max_results.unwrap_or(50))
+        .collect::<Vec<_>>();
+
+    Json(json!({
+        "startAt": query.start_at,
+        "maxResults": query.max_results.unwrap_or(50),
+        "total": matching.len(),
+        "isLast": query.start_at + page.len() >= matching.len(),
+        "values": page
+    }))
+}
+
+async fn get_filter(State(jira): State<Jira>, Path(id): Path<String>) -> Response {
+    let filters = jira.filters.lock().await;
+
+    match filters.iter().find(|f| f["id"] == id.as_str()) {
+        Some(filter) => Json(filter.clone()).into_response(),
+        None => error(StatusCode::NOT_FOUND, FILTER_NOT_FOUND),
+    }
+}
+
+async fn create_filter(State(jira): State<Jira>, Json(details): Json<Value>) -> Response {
+    let mut filters = jira.filters.lock().await;
+
+    if details["name"].as_str().map(str::is_empty).unwrap_or(true) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "errorMessages": [], "errors": { "filterName": "You must specify a name to save this filter as." } })),
+        )
+            .into_response();
+    }
+
+    let filter = json!({
+        "id": (10000 + filters.len()).to_string(),
+        "name": details["name"],
+        "description": details["description"],
+        "owner": user(),
+        "jql": details["jql"].as_str().unwrap_or_default(),
+        "favourite": details["favourite"].as_bool().unwrap_or(false),
+        "sharePermissions": []
+    });
+    filters.push(filter.clone());
+
+    Json(filter).into_response()
+}
+
+async fn delete_filter(State(jira): State<Jira>, Path(id): Path<String>) -> Response {
+    let mut filters = jira.filters.lock().await;
+    let count = filters.len();
+
+    filters.retain(|f| f["id"] != id.as_str());
+
+    if filters.len() == count {
+        return error(StatusCode::NOT_FOUND, FILTER_NOT_FOUND);
+    }
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn filter_permissions(State(jira): State<Jira>, Path(id): Path<String>) -> Response {
+    let filters = jira.filters.lock().await;
+
+    match filters.iter().find(|f| f["id"] == id.as_str()) {
+        Some(filter) => Json(filter["sharePermissions"].clone()).into_response(),
+        None => error(StatusCode::NOT_FOUND, FILTER_NOT_FOUND),
+    }
+}
+
+// Only sharing with everyone and with the test project is supported
+async fn share_filter(
+    State(jira): State<Jira>,
+    Path(id): Path<String>,
+    Json(share): Json<Value>,
+) -> Response {
+    let mut filters = jira.filters.lock().await;
+
+    let filter = match filters.iter_mut().find(|f| f["id"] == id.as_str()) {
+        Some(filter) => filter,
+        None => return error(StatusCode::NOT_FOUND, FILTER_NOT_FOUND),
+    };
+
+    let mut permission = match (share["type"].as_str(), share["projectId"].as_str()) {
+        (Some("global"), _) => json!({ "type": "global" }),
+        (Some("project"), Some("10000")) => json!({ "type": "project", "project": project() }),
+        _ => return error(StatusCode::BAD_REQUEST, "Can't share the filter like this."),
+    };
+
+    let permissions = filter["sharePermissions"].as_array_mut().unwrap();
+    permission["id"] = json!(10000 + permissions.len());
+    permissions.push(permission);
+
+    Json(json!(permissions)).into_response()
+}
+
+async fn unshare_filter(
+    State(jira): State<Jira>,
+    Path((id, permission)): Path<(String, i64)>,
+) -> Response {
+    let mut filters = jira.filters.lock().await;
+
+    let filter = match filters.iter_mut().find(|f| f["id"] == id.as_str()) {
+        Some(filter) => filter,
+        None => return error(StatusCode::NOT_FOUND, FILTER_NOT_FOUND),
+    };
+
+    if let Some(permissions) = filter["sharePermissions"].as_array_mut() {
+        permissions.retain(|p| p["id"] != permission);
+    }
+
+    StatusCode::NO_CONTENT.into_response()
+}
@@ -0,0 +1,110 @@
+//! A local server for testing jirascope against.
+//!
+//! Besides the notes API used by the `test_server` feature, it serves a small
+//! mock of the Jira Cloud REST API under `/rest/api/3`, see [`jira`], and of
+//! the Jira Service Management one under `/rest/servicedeskapi`, see
+//! [`service_desk`].
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::net::TcpListener;
+use tokio::sync::Mutex;
+
+pub mod jira;
+pub mod service_desk;
+
+static mut NOTES: Lazy<Mutex<Vec<Note>>> = Lazy::new(|| Mutex::new(Vec::new())); // Needs to be
+                                                                                 // lazy because of
+                                                                                 // the tokio::sync::Mutex
+
+pub fn app() -> Router {
+    Router::new()
+        // `GET /` goes to `root`
+        .route("/", get(root))
+        // `POST /notes` goes to `create_note`
+        .route("/notes", post(create_note))
+        // `GET /notes` goes to `get_notes`
+        .route("/notes", get(get_notes))
+        // `GET /notes/{id}` goes to `get_note_by_id`
+        .route("/notes/:id", get(get_note_by_id))
+        // POST /notes/{id} goes to `update_note_by_id`
+        .route("/notes/:id", post(update_note_by_id))
+        // the mock Jira API
+        .nest("/rest/api/3", jira::routes())
+        .nest("/rest/servicedeskapi", service_desk::routes())
+}
+
+/// Serve [`app`] on an already bound listener, e.g. one on port 0 in tests.
+pub async fn serve(listener: TcpListener) {
+    // `axum::Server` is a re-export of `hyper::Server`
+    axum::Server::from_tcp(listener)
+        .unwrap()
+        .serve(app().into_make_service())
+        .await
+        .unwrap();
+}
+
+// basic handler that responds with a static string
+async fn root() -> &'static str {
+    "Nothing to see here"
+}
+
+async fn create_note(Json(note): Json<Note>) -> (StatusCode, Json<Option<Note>>) {
+    if note.id.is_some() {
+        (StatusCode::BAD_REQUEST, Json(None))
+    } else {
+        let mut notes = unsafe { NOTES.lock().await };
+        let id = notes.len();
+        let note = Note {
+            id: Some(id),
+            message: note.message,
+        };
+        notes.push(note.clone());
+
+        (StatusCode::CREATED, Json(Some(note)))
+    }
+}
+
+async fn get_notes() -> Json<Vec<Note>> {
+    let notes = unsafe { NOTES.lock().await };
+    Json(notes.clone())
+}
+
+async fn get_note_by_id(Path(id): Path<usize>) -> (StatusCode, Json<Option<Note>>) {
+    let notes = unsafe { NOTES.lock().await };
+    let note = notes.get(id).cloned();
+
+    match note {
+        Some(note) => (StatusCode::OK, Json(Some(note))),
+        None => (StatusCode::NOT_FOUND, Json(None)),
+    }
+}
+
+async fn update_note_by_id(
+    Path(id): Path<usize>,
+    Json(note): Json<Note>,
+) -> (StatusCode, Json<Option<Note>>) {
+    let mut notes = unsafe { NOTES.lock().await };
+    let current_note = notes.get_mut(id);
+
+    match current_note {
+        Some(current_note) => {
+            current_note.message = note.message.clone();
+            (StatusCode::OK, Json(Some(note.clone())))
+        }
+        None => (StatusCode::NOT_FOUND, Json(None)),
+    }
+}
+
+// the output to our `create_user` handler
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Note {
+    id: Option<usize>,
+    message: String,
+}
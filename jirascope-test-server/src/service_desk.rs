@@ -0,0 +1,392 @@
+//! A small, in-memory mock of the Jira Service Management REST API.
+//!
+//! Serves a single service desk for the project HELP, with one request
+//! type, one queue of all open requests, and the requests themselves with
+//! their comments and SLAs. HELP-1 starts out with a public and an internal
+//! comment, and has breached its first response SLA.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use crate::jira::{error, require_auth, user};
+
+pub const SERVICE_DESK_ID: i64 = 1;
+pub const REQUEST_TYPE_ID: i64 = 10;
+pub const QUEUE_ID: i64 = 100;
+
+const REQUEST_NOT_FOUND: &str = "The request doesn't exist or you don't have permission to see it.";
+const SERVICE_DESK_NOT_FOUND: &str =
+    "The service desk doesn't exist or you don't have permission to see it.";
+
+#[derive(Clone)]
+struct ServiceDesk {
+    requests: Arc<Mutex<Vec<Value>>>,
+    comments: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+pub fn routes() -> Router {
+    let service_desk = ServiceDesk {
+        requests: Arc::new(Mutex::new(vec![request(1, "VPN is down")])),
+        comments: Arc::new(Mutex::new(vec![
+            (
+                "HELP-1".to_string(),
+                comment(1, "We're looking into it", true),
+            ),
+            (
+                "HELP-1".to_string(),
+                comment(2, "Probably the certificate", false),
+            ),
+        ])),
+    };
+
+    Router::new()
+        .route("/servicedesk", get(service_desks))
+        .route("/servicedesk/:id", get(get_service_desk))
+        .route("/servicedesk/:id/requesttype", get(request_types))
+        .route("/servicedesk/:id/queue", get(queues))
+        .route("/servicedesk/:id/queue/:queue/issue", get(queue_issues))
+        .route("/request", get(requests).post(create_request))
+        .route("/request/:key", get(get_request))
+        .route("/request/:key/comment", get(comments).post(add_comment))
+        .route("/request/:key/sla", get(slas))
+        .layer(middleware::from_fn(require_auth))
+        .with_state(service_desk)
+}
+
+fn date(friendly: &str) -> Value {
+    json!({
+        "iso8601": "2023-05-01T10:00:00+0000",
+        "jira": "2023-05-01T10:00:00.000+0000",
+        "friendly": friendly,
+        "epochMillis": 1682935200000i64
+    })
+}
+
+fn customer() -> Value {
+    json!({
+        "accountId": "qm:a713c8ea-1075-4e30-9d96-891a7d181739:5ad6d3581db05e2a66fa80b",
+        "displayName": "Customer",
+        "emailAddress": "customer@example.org",
+        "active": true
+    })
+}
+
+fn service_desk() -> Value {
+    json!({
+        "id": SERVICE_DESK_ID.to_string(),
+        "projectId": "10010",
+        "projectKey": "HELP",
+        "projectName": "Help desk"
+    })
+}
+
+fn request(n: usize, summary: &str) -> Value {
+    json!({
+        "issueId": (20000 + n).to_string(),
+        "issueKey": format!("HELP-{}", n),
+        "requestTypeId": REQUEST_TYPE_ID.to_string(),
+        "serviceDeskId": SERVICE_DESK_ID.to_string(),
+        "createdDate": date("01/May/23 10:00 AM"),
+        "reporter": customer(),
+        "requestFieldValues": [
+            { "fieldId": "summary", "label": "What do you need?", "value": summary }
+        ],
+        "currentStatus": {
+            "status": "Waiting for support",
+            "statusCategory": "NEW",
+            "statusDate": date("01/May/23 10:00 AM")
+        }
+    })
+}
+
+fn comment(id: usize, body: &str, public: bool) -> Value {
+    json!({
+        "id": id.to_string(),
+        "body": body,
+        "public": public,
+        "author": user(),
+        "created": date("01/May/23 11:00 AM")
+    })
+}
+
+/// A page as the service desk API pages everything.
+fn page(values: Vec<Value>, query: &PageQuery) -> Json<Value> {
+    let limit = query.limit.unwrap_or(50);
+    let page = values
+        .iter()
+        .skip(query.start)
+        .take(limit)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    Json(json!({
+        "size": page.len(),
+        "start": query.start,
+        "limit": limit,
+        "isLastPage": query.start + page.len() >= values.len(),
+        "values": page
+    }))
+}
+
+#[derive(Deserialize)]
+struct PageQuery {
+    #[serde(default)]
+    start: usize,
+    limit: Option<usize>,
+}
+
+async fn service_desks(Query(query): Query<PageQuery>) -> Json<Value> {
+    page(vec![service_desk()], &query)
+}
+
+async fn get_service_desk(Path(id): Path<i64>) -> Response {
+    match id {
+        SERVICE_DESK_ID => Json(service_desk()).into_response(),
+        _ => error(StatusCode::NOT_FOUND, SERVICE_DESK_NOT_FOUND),
+    }
+}
+
+async fn request_types(Path(id): Path<i64>, Query(query): Query<PageQuery>) -> Response {
+    if id != SERVICE_DESK_ID {
+        return error(StatusCode::NOT_FOUND, SERVICE_DESK_NOT_FOUND);
+    }
+
+    let request_type = json!({
+        "id": REQUEST_TYPE_ID.to_string(),
+        "name": "Get IT help",
+        "description": "Get assistance for general IT problems and questions.",
+        "helpText": "Don't panic.",
+        "issueTypeId": "10020",
+        "serviceDeskId": SERVICE_DESK_ID.to_string(),
+        "groupIds": []
+    });
+
+    page(vec![request_type], &query).into_response()
+}
+
+async fn queues(
+    State(service_desk): State<ServiceDesk>,
+    Path(id): Path<i64>,
+    Query(query): Query<PageQuery>,
+) -> Response {
+    if id != SERVICE_DESK_ID {
+        return error(StatusCode::NOT_FOUND, SERVICE_DESK_NOT_FOUND);
+    }
+
+    let queue = json!({
+        "id": QUEUE_ID.to_string(),
+        "name": "All open",
+        "jql": "project = HELP AND resolution = EMPTY ORDER BY created DESC",
+        "fields": ["issuetype", "issuekey", "summary", "status"],
+        "issueCount": service_desk.requests.lock().await.len()
+    });
+
+    page(vec![queue], &query).into_response()
+}
+
+async fn queue_issues(
+    State(service_desk): State<ServiceDesk>,
+    Path((id, queue)): Path<(i64, i64)>,
+    Query(query): Query<PageQuery>,
+) -> Response {
+    if id != SERVICE_DESK_ID || queue != QUEUE_ID {
+        return error(StatusCode::NOT_FOUND, "The queue doesn't exist.");
+    }
+
+    let issues = service_desk
+        .requests
+        .lock()
+        .await
+        .iter()
+        .map(|request| {
+            json!({
+                "id": request["issueId"],
+                "key": request["issueKey"],
+                "fields": {
+                    "summary": request["requestFieldValues"][0]["value"],
+                    "status": { "name": request["currentStatus"]["status"] }
+                }
+            })
+        })
+        .collect();
+
+    page(issues, &query).into_response()
+}
+
+async fn requests(
+    State(service_desk): State<ServiceDesk>,
+    Query(query): Query<PageQuery>,
+) -> Json<Value> {
+    page(service_desk.requests.lock().await.clone(), &query)
+}
+
+async fn get_request(State(service_desk): State<ServiceDesk>, Path(key): Path<String>) -> Response {
+    let requests = service_desk.requests.lock().await;
+
+    match requests.iter().find(|r| r["issueKey"] == key.as_str()) {
+        Some(request) => Json(request.clone()).into_response(),
+        None => error(StatusCode::NOT_FOUND, REQUEST_NOT_FOUND),
+    }
+}
+
+async fn create_request(
+    State(service_desk): State<ServiceDesk>,
+    Json(creation): Json<Value>,
+) -> Response {
+    if creation["serviceDeskId"] != SERVICE_DESK_ID.to_string().as_str()
+        || creation["requestTypeId"] != REQUEST_TYPE_ID.to_string().as_str()
+    {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "The service desk or request type doesn't exist.",
+        );
+    }
+
+    let summary = match creation["requestFieldValues"]["summary"].as_str() {
+        Some(summary) if !summary.is_empty() => summary,
+        _ => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "Summary: You must enter a value for this field.",
+            )
+        }
+    };
+
+    let mut requests = service_desk.requests.lock().await;
+    let request = request(requests.len() + 1, summary);
+    requests.push(request.clone());
+
+    (StatusCode::CREATED, Json(request)).into_response()
+}
+
+#[derive(Deserialize)]
+struct CommentQuery {
+    public: Option<bool>,
+    internal: Option<bool>,
+    #[serde(default)]
+    start: usize,
+    limit: Option<usize>,
+}
+
+async fn comments(
+    State(service_desk): State<ServiceDesk>,
+    Path(key): Path<String>,
+    Query(query): Query<CommentQuery>,
+) -> Json<Value> {
+    let public = query.public.unwrap_or(true);
+    let internal = query.internal.unwrap_or(true);
+
+    let comments = service_desk
+        .comments
+        .lock()
+        .await
+        .iter()
+        .filter(|(request, _)| *request == key)
+        .map(|(_, comment)| comment)
+        .filter(|comment| match comment["public"].as_bool() {
+            Some(true) => public,
+            _ => internal,
+        })
+        .cloned()
+        .collect();
+
+    page(
+        comments,
+        &PageQuery {
+            start: query.start,
+            limit: query.limit,
+        },
+    )
+}
+
+async fn add_comment(
+    State(service_desk): State<ServiceDesk>,
+    Path(key): Path<String>,
+    Json(creation): Json<Value>,
+) -> Response {
+    if !service_desk
+        .requests
+        .lock()
+        .await
+        .iter()
+        .any(|r| r["issueKey"] == key.as_str())
+    {
+        return error(StatusCode::NOT_FOUND, REQUEST_NOT_FOUND);
+    }
+
+    let mut comments = service_desk.comments.lock().await;
+    let comment = comment(
+        comments.len() + 1,
+        creation["body"].as_str().unwrap_or_default(),
+        creation["public"].as_bool().unwrap_or(true),
+    );
+    comments.push((key, comment.clone()));
+
+    (StatusCode::CREATED, Json(comment)).into_response()
+}
+
+// every request has breached its first response SLA, and is within the
+// resolution one
+async fn slas(
+    State(service_desk): State<ServiceDesk>,
+    Path(key): Path<String>,
+    Query(query): Query<PageQuery>,
+) -> Response {
+    if !service_desk
+        .requests
+        .lock()
+        .await
+        .iter()
+        .any(|r| r["issueKey"] == key.as_str())
+    {
+        return error(StatusCode::NOT_FOUND, REQUEST_NOT_FOUND);
+    }
+
+    let sla = |id: &str, name: &str, goal: (i64, &str), remaining: (i64, &str)| {
+        json!({
+            "id": id,
+            "name": name,
+            "ongoingCycle": {
+                "startTime": date("01/May/23 10:00 AM"),
+                "breachTime": date("01/May/23 2:00 PM"),
+                "breached": remaining.0 < 0,
+                "paused": false,
+                "withinCalendarHours": true,
+                "goalDuration": { "millis": goal.0, "friendly": goal.1 },
+                "elapsedTime": { "millis": goal.0 - remaining.0, "friendly": "" },
+                "remainingTime": { "millis": remaining.0, "friendly": remaining.1 }
+            },
+            "completedCycles": []
+        })
+    };
+
+    page(
+        vec![
+            sla(
+                "1",
+                "Time to first response",
+                (14400000, "4h"),
+                (-3600000, "-1h"),
+            ),
+            sla(
+                "2",
+                "Time to resolution",
+                (86400000, "24h"),
+                (68400000, "19h"),
+            ),
+        ],
+        &query,
+    )
+    .into_response()
+}
@@ -43,6 +43,76 @@ Example setting:
   :group 'jirascope
   :type 'directory)
 
+(defcustom jirascope-cache-directory (locate-user-emacs-file "jirascope/")
+  "Directory where jirascope keeps its cache of projects and issues.
+The cache is shown right after `jirascope-setup', before the first
+refresh finishes, and while the server can't be reached.
+Set to nil to disable the cache."
+  :group 'jirascope
+  :type '(choice directory (const :tag "Disabled" nil)))
+
+(defcustom jirascope-read-only nil
+  "When non-nil, jirascope refuses to change anything in Jira."
+  :group 'jirascope
+  :type 'boolean)
+
+(defcustom jirascope-allowed-projects nil
+  "Keys of the projects jirascope may change, nil allows every project."
+  :group 'jirascope
+  :type '(repeat string))
+
+(defcustom jirascope-denied-projects nil
+  "Keys of projects jirascope must never change."
+  :group 'jirascope
+  :type '(repeat string))
+
+(defvar jirascope-before-create-functions nil
+  "Functions called with an issue about to be created.
+Each function gets the issue as a plist like
+\\=(:fields (:summary \"...\" :issuetype (:name \"Bug\" ...) ...)),
+which is what is sent to Jira.  It may return a changed plist to
+create instead, or nil to leave it as is.  Signal an error, e.g. with
+`user-error', to stop the issue from being created.")
+
+(defvar jirascope-before-edit-functions nil
+  "Functions called with an edit about to be made to an issue.
+Each function gets a plist like (:key \"ABC-1\" :fields (...)), see
+`jirascope-before-create-functions' for what it may return.")
+
+(defvar jirascope-before-transition-functions nil
+  "Functions called with a transition about to be made.
+Each function gets a plist like (:key \"ABC-1\" :transition (...)), see
+`jirascope-before-create-functions' for what it may return.")
+
+(defvar jirascope-before-comment-functions nil
+  "Functions called with a comment about to be added to an issue.
+Each function gets a plist like (:key \"ABC-1\" :body (...)), see
+`jirascope-before-create-functions' for what it may return.")
+
+(defvar jirascope-before-delete-functions nil
+  "Functions called with an issue about to be deleted, as (:key \"ABC-1\").
+Signal an error to stop the deletion.")
+
+(defvar jirascope-after-create-functions nil
+  "Functions called after an issue was created.
+Each function gets the plist the issue was created from, with the :key
+of the new issue added.")
+
+(defvar jirascope-after-edit-functions nil
+  "Functions called after an issue was edited, with the plist of the edit.")
+
+(defvar jirascope-after-transition-functions nil
+  "Functions called after an issue was transitioned.
+Each function gets a plist like (:key \"ABC-1\" :transition (...)).")
+
+(defvar jirascope-after-comment-functions nil
+  "Functions called after a comment was added, with the plist of the comment.")
+
+(defvar jirascope-after-delete-functions nil
+  "Functions called after an issue was deleted, with (:key \"ABC-1\").")
+
+(require 'diff-mode)
+(require 'json)
 (require 'jirascope-dyn-get)
 
 (defun jirascope-install ()
@@ -62,17 +132,31 @@ Example setting:
 (declare-function jirascope-dyn-issue-edit-graphical-finish "ext:jirascope-dyn")
 (declare-function jirascope-dyn-issue-delete-interactive "ext:jirascope-dyn")
 (declare-function jirascope-dyn-issue-transition-interactive "ext:jirascope-dyn")
+(declare-function jirascope-dyn-issue-comment-interactive "ext:jirascope-dyn")
 (declare-function jirascope-dyn-project-create-interactive "ext:jirascope-dyn")
 (declare-function jirascope-dyn-project-edit-interactive "ext:jirascope-dyn")
 (declare-function jirascope-dyn-project-edit-graphical-interactive "ext:jirascope-dyn")
 (declare-function jirascope-dyn-project-edit-graphical-finish "ext:jirascope-dyn")
 (declare-function jirascope-dyn-project-delete-interactive "ext:jirascope-dyn")
 (declare-function jirascope-dyn-state-open "ext:jirascope-dyn")
+(declare-function jirascope-dyn-filter-run-interactive "ext:jirascope-dyn")
+(declare-function jirascope-dyn-service-desk-queue-open-interactive "ext:jirascope-dyn")
+(declare-function jirascope-dyn-service-desk-comment-interactive "ext:jirascope-dyn")
+(declare-function jirascope-dyn-outbox-open "ext:jirascope-dyn")
+(declare-function jirascope-dyn-outbox-retry-interactive "ext:jirascope-dyn")
+(declare-function jirascope-dyn-outbox-force-interactive "ext:jirascope-dyn")
+(declare-function jirascope-dyn-outbox-discard-interactive "ext:jirascope-dyn")
+(declare-function jirascope-dyn-toggle-dry-run "ext:jirascope-dyn")
+(declare-function jirascope-dyn-dry-run-open "ext:jirascope-dyn")
 
 (defun jirascope-setup (url login api_token)
   "Setup Jirascope with the given cloud URL, LOGIN and API_TOKEN."
   (if (jirascope-dyn-get-installed)
-    (jirascope-dyn-setup url login api_token)
+    (jirascope-dyn-setup url login api_token
+      (and jirascope-cache-directory (expand-file-name jirascope-cache-directory))
+      (list :read-only jirascope-read-only
+            :allowed-projects jirascope-allowed-projects
+            :denied-projects jirascope-denied-projects))
     (user-error jirascope--first-time-user-error)))
 
 ;; add bindings for interactive use
@@ -125,6 +209,13 @@ Example setting:
     (jirascope-dyn-issue-transition-interactive)
     (user-error jirascope--first-time-user-error)))
 
+(defun jirascope-issue-comment ()
+  "Add a comment to an issue."
+  (interactive)
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-issue-comment-interactive)
+    (user-error jirascope--first-time-user-error)))
+
 (defun jirascope-project-create ()
   "Create a project via prompt."
   (interactive)
@@ -167,6 +258,79 @@ Example setting:
     (jirascope-dyn-state-open)
     (user-error jirascope--first-time-user-error)))
 
+(defun jirascope-buttonize-keys (start end)
+  "Turn the issue keys of known projects in the region into buttons.
+Without an active region, the whole buffer is searched."
+  (interactive (if (use-region-p)
+                 (list (region-beginning) (region-end))
+                 (list (point-min) (point-max))))
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-issue-buttonize-keys start end)
+    (user-error jirascope--first-time-user-error)))
+
+(defun jirascope-filter-run ()
+  "Show the issues of a favourite filter in the project tree buffer."
+  (interactive)
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-filter-run-interactive)
+    (user-error jirascope--first-time-user-error)))
+
+(defun jirascope-queue-open ()
+  "Open a service desk queue, with the requests in it and their breached SLAs."
+  (interactive)
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-service-desk-queue-open-interactive)
+    (user-error jirascope--first-time-user-error)))
+
+(defun jirascope-request-comment ()
+  "Comment on the service desk request shown last, publicly or internally."
+  (interactive)
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-service-desk-comment-interactive)
+    (user-error jirascope--first-time-user-error)))
+
+(defun jirascope-outbox-open ()
+  "Open the buffer listing changes waiting to be sent."
+  (interactive)
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-outbox-open)
+    (user-error jirascope--first-time-user-error)))
+
+(defun jirascope-outbox-retry ()
+  "Retry a change that conflicted or was rejected, and send queued changes."
+  (interactive)
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-outbox-retry-interactive)
+    (user-error jirascope--first-time-user-error)))
+
+(defun jirascope-outbox-force ()
+  "Send a conflicting change anyway, overwriting changes made on the server."
+  (interactive)
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-outbox-force-interactive)
+    (user-error jirascope--first-time-user-error)))
+
+(defun jirascope-outbox-discard ()
+  "Drop a queued change without sending it."
+  (interactive)
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-outbox-discard-interactive)
+    (user-error jirascope--first-time-user-error)))
+
+(defun jirascope-toggle-dry-run ()
+  "Toggle dry-run mode, where changes are logged instead of sent to Jira."
+  (interactive)
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-toggle-dry-run)
+    (user-error jirascope--first-time-user-error)))
+
+(defun jirascope-dry-run-open ()
+  "Open the buffer listing changes held back by dry-run mode."
+  (interactive)
+  (if (jirascope-dyn-get-installed)
+    (jirascope-dyn-dry-run-open)
+    (user-error jirascope--first-time-user-error)))
+
 (defface jirascope-issue-key
   '((t (:inherit info-title-1)))
   "Face used for issue key headers."
@@ -192,6 +356,21 @@ Example setting:
     "Face used for issue old diff."
     :group 'jirascope)
 
+(defface jirascope-diff-added
+    '((t (:inherit diff-refine-added)))
+    "Face used for text added to an issue field."
+    :group 'jirascope)
+
+(defface jirascope-diff-removed
+    '((t (:inherit diff-refine-removed)))
+    "Face used for text removed from an issue field."
+    :group 'jirascope)
+
+(defface jirascope-sla-breached
+    '((t (:inherit error)))
+    "Face used for breached service desk SLAs."
+    :group 'jirascope)
+
 (define-button-type 'jirascope-issue-button
     'follow-link t
     'action 'jirascope-dyn-issue-button-action)
@@ -200,12 +379,45 @@ Example setting:
     'follow-link t
     'action 'jirascope-dyn-project-button-action)
 
+(define-button-type 'jirascope-filter-button
+    'follow-link t
+    'action 'jirascope-dyn-filter-button-action)
+
+(define-button-type 'jirascope-request-button
+    'follow-link t
+    'action 'jirascope-dyn-service-desk-request-button-action)
+
 (defun jirascope-insert-button (text ty)
   "Used by jirascope-dyn.
 Create a button with the given TEXT and type TY in the current buffer."
   (insert-button text
     :type ty))
 
+(defun jirascope--json-to-plist (json)
+  "Used by jirascope-dyn.
+Parse the JSON string of a change into a plist."
+  (let ((json-object-type 'plist)
+        (json-array-type 'vector)
+        (json-key-type 'keyword))
+    (json-read-from-string json)))
+
+(defun jirascope--run-before-hook (hook json)
+  "Used by jirascope-dyn.
+Pass the change in the JSON string through the functions of HOOK.
+Return the resulting change as JSON, or nil when HOOK is empty."
+  (when (symbol-value hook)
+    (let ((change (jirascope--json-to-plist json)))
+      (run-hook-wrapped hook
+        (lambda (function)
+          (setq change (or (funcall function change) change))
+          nil))
+      (json-encode change))))
+
+(defun jirascope--run-after-hook (hook json)
+  "Used by jirascope-dyn.
+Run the functions of HOOK with the change in the JSON string."
+  (with-demoted-errors "Error in jirascope hook: %S"
+    (run-hook-with-args hook (jirascope--json-to-plist json))))
 
 (provide 'jirascope)
 